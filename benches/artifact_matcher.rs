@@ -0,0 +1,108 @@
+//! Times `ArtifactMatcher::matches` over directory names that mostly miss,
+//! as in a real walk, with the built-in names alone and with glob and
+//! regex patterns added, and checks that misses never allocate.
+//!
+//! Run with `cargo bench --bench artifact_matcher`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use clean_my_code::{ArtifactMatcher, DEFAULT_ARTIFACT_DIR_NAMES, NamePattern};
+use regex::Regex;
+
+const ROUNDS: usize = 200_000;
+const RUNS: usize = 7;
+
+/// Names a walk typically meets: source dirs, with the odd artifact.
+const NAMES: &[&str] = &[
+    "src",
+    "lib",
+    "components",
+    "tests",
+    "docs",
+    "assets",
+    "utils",
+    "internal",
+    "scripts",
+    "node_modules",
+    "packages",
+    "examples",
+    "target",
+    "fixtures",
+    "config",
+    "public",
+];
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn main() {
+    let names = NAMES.iter().map(OsStr::new).collect::<Vec<_>>();
+    let exact = ArtifactMatcher::new(
+        DEFAULT_ARTIFACT_DIR_NAMES
+            .iter()
+            .map(OsString::from)
+            .collect::<HashSet<_>>(),
+    );
+    let mut patterns = exact.clone();
+    patterns.patterns = vec![
+        NamePattern::glob("cmake-build-*").unwrap(),
+        NamePattern::glob("*.egg-info").unwrap(),
+        NamePattern::Regex(Regex::new("-cache$").unwrap()),
+    ];
+
+    for (label, matcher) in [("names only", &exact), ("with patterns", &patterns)] {
+        let (per_match, allocations) = time(matcher, &names);
+        println!(
+            "artifact_matcher {label}: {:.1}ns per name, {allocations} allocations",
+            per_match.as_secs_f64() * 1e9
+        );
+        assert_eq!(allocations, 0, "{label}: matching allocated");
+    }
+}
+
+/// Median time per `matches` call, and the allocations made by all runs
+/// after a warm-up, which lets the regex engines set up their caches.
+fn time(matcher: &ArtifactMatcher, names: &[&OsStr]) -> (Duration, usize) {
+    for name in names {
+        black_box(matcher.matches(name));
+    }
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let mut times = [Duration::ZERO; RUNS];
+    for time in &mut times {
+        let started = Instant::now();
+        let mut hits = 0usize;
+        for _ in 0..ROUNDS {
+            for name in names {
+                hits += usize::from(matcher.matches(black_box(name)));
+            }
+        }
+        black_box(hits);
+        *time = started.elapsed();
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    times.sort();
+    let calls = u32::try_from(ROUNDS * names.len()).unwrap_or(u32::MAX);
+    (times[RUNS / 2] / calls, allocations)
+}
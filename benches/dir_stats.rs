@@ -0,0 +1,80 @@
+//! Times `dir_stats` over a wide tree of small dirs, the node_modules shape
+//! where merging every dir's totals dominates once there are many cores.
+//! A single-threaded run is timed first as the baseline, so the speedup
+//! shows whether the extra threads pay for their merging.
+//!
+//! Run with `cargo bench --bench dir_stats`; set `DIR_STATS_BENCH_DIRS` to
+//! change the tree size (default 50000 dirs).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use clean_my_code::{ScanOptions, dir_stats};
+
+const FILES_PER_DIR: usize = 2;
+const RUNS: usize = 7;
+
+fn main() {
+    let dirs = std::env::var("DIR_STATS_BENCH_DIRS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(50_000);
+    let root = std::env::temp_dir().join(format!("clean-my-code-bench-{}", std::process::id()));
+    build_tree(&root, dirs);
+
+    let options = ScanOptions::default();
+    let expected = dir_stats(&root, &options).expect("warm-up walk failed");
+    let time_runs = || {
+        let mut times = (0..RUNS)
+            .map(|_| {
+                let started = Instant::now();
+                let stats = dir_stats(&root, &options).expect("walk failed");
+                let elapsed = started.elapsed();
+                assert_eq!(stats, expected);
+                elapsed
+            })
+            .collect::<Vec<_>>();
+        times.sort();
+        times
+    };
+    let baseline = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .expect("failed to build the baseline pool")
+        .install(time_runs);
+    let times = time_runs();
+    let _ = fs::remove_dir_all(&root);
+
+    let threads = rayon::current_num_threads();
+    report(dirs, expected.file_count, 1, &baseline);
+    report(dirs, expected.file_count, threads, &times);
+    println!(
+        "speedup over 1 thread: {:.2}x",
+        baseline[RUNS / 2].as_secs_f64()
+            / times[RUNS / 2].max(Duration::from_nanos(1)).as_secs_f64()
+    );
+}
+
+fn report(dirs: usize, files: u64, threads: usize, sorted_times: &[Duration]) {
+    let median = sorted_times[RUNS / 2];
+    println!(
+        "dir_stats: {dirs} dirs, {files} files on {threads} threads: median {:.1?}, best {:.1?} ({:.0} dirs/s)",
+        median,
+        sorted_times[0],
+        dirs as f64 / median.max(Duration::from_nanos(1)).as_secs_f64()
+    );
+}
+
+/// `dirs` leaf dirs spread 100 per parent, each holding a few small files.
+fn build_tree(root: &Path, dirs: usize) {
+    for i in 0..dirs {
+        let dir: PathBuf = root.join(format!("p{}", i / 100)).join(format!("d{i}"));
+        fs::create_dir_all(&dir).expect("failed to create bench tree");
+        for f in 0..FILES_PER_DIR {
+            fs::write(dir.join(format!("f{f}")), b"bench").expect("failed to create bench file");
+        }
+    }
+}
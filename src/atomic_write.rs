@@ -0,0 +1,77 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{Context, Result};
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes `contents` to a temp file next to `path`, then renames it over
+/// `path`, so a crash never leaves a truncated file. `what` names the file
+/// in errors. The temp file is removed if either step fails.
+pub(crate) fn write_atomically(path: &Path, contents: &str, what: &str) -> Result<()> {
+    let tmp = unique_tmp_path(path);
+    let written = fs::write(&tmp, contents)
+        .with_context(|| format!("failed to write {what}: {tmp:?}"))
+        .and_then(|()| {
+            fs::rename(&tmp, path).with_context(|| format!("failed to replace {what}: {path:?}"))
+        });
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// A sibling of `path` to write before renaming over it, unique to this
+/// process and call so two concurrent saves never share one.
+fn unique_tmp_path(path: &Path) -> PathBuf {
+    let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.{n}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::make_temp_dir;
+
+    #[test]
+    fn temp_paths_are_unique_siblings() {
+        let path = Path::new("/cache/scan-cache.json");
+        let (a, b) = (unique_tmp_path(path), unique_tmp_path(path));
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), path.parent());
+        assert!(
+            a.to_string_lossy().starts_with("/cache/scan-cache.json."),
+            "{a:?}"
+        );
+    }
+
+    #[test]
+    fn failed_writes_leave_no_temp_file_behind() {
+        let dir = make_temp_dir("clean-my-code-atomic");
+        let path = dir.join("state");
+        write_atomically(&path, "a=1\n", "state").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a=1\n");
+
+        // Renaming a file over a non-empty dir fails.
+        let blocked = dir.join("blocked");
+        fs::create_dir_all(blocked.join("inside")).unwrap();
+        let err = write_atomically(&blocked, "x", "state").unwrap_err();
+        assert!(
+            err.to_string().starts_with("failed to replace state: "),
+            "{err}"
+        );
+
+        let mut names = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["blocked", "state"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,408 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::{
+    atomic_write::write_atomically,
+    json::{self, JsonObject, JsonValue},
+    scan::{DirStats, ScanOptions},
+};
+
+const CACHE_VERSION: u64 = 2;
+
+/// Children of an artifact dir whose mtimes go into its fingerprint.
+const FINGERPRINT_SAMPLE: usize = 16;
+
+/// Sizes of artifact dirs from earlier runs, so a repeat scan only walks the
+/// dirs that changed.
+///
+/// An entry is reused while the dir's fingerprint (its own mtime and those
+/// of its first few children by name) is unchanged. That catches entries
+/// being added or removed one or two levels down, but not a file rewritten
+/// in place deeper inside; `--refresh` re-walks everything.
+///
+/// Lives at `$XDG_CACHE_HOME/clean-my-code/scan-cache.json`, falling back to
+/// `~/.cache/clean-my-code/scan-cache.json`. A missing, unreadable or
+/// outdated file is treated as empty.
+#[derive(Debug, Default)]
+pub struct ScanCache {
+    path: Option<PathBuf>,
+    /// The sizing options entries were made with. Entries sized with other
+    /// options are dropped on load.
+    sizing: String,
+    /// How many largest files callers want per dir. Entries that kept at
+    /// least this many are reused, cut down to it.
+    largest_files: usize,
+    /// Walk every dir again, but still record the fresh sizes.
+    refresh: bool,
+    entries: Mutex<HashMap<PathBuf, CacheEntry>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CacheEntry {
+    fingerprint: Fingerprint,
+    stats: DirStats,
+    /// The `largest_files` limit the dir was sized with.
+    largest_files: usize,
+}
+
+/// Modification times in nanoseconds since the epoch: the dir's own, then
+/// its first `FINGERPRINT_SAMPLE` children sorted by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint(Vec<u64>);
+
+impl ScanCache {
+    pub fn load(options: &ScanOptions, refresh: bool) -> Self {
+        match default_cache_path() {
+            Some(path) => Self::load_from(path, options, refresh),
+            None => Self {
+                sizing: sizing_key(options),
+                largest_files: options.largest_files,
+                refresh,
+                ..Self::default()
+            },
+        }
+    }
+
+    pub fn load_from(path: PathBuf, options: &ScanOptions, refresh: bool) -> Self {
+        let sizing = sizing_key(options);
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| parse(&text, &sizing).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            sizing,
+            largest_files: options.largest_files,
+            refresh,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// The cached stats of `path` if it is unchanged since they were taken,
+    /// otherwise the result of `compute`, which is cached for next time.
+    pub fn dir_stats<F>(&self, path: &Path, compute: F) -> Result<DirStats>
+    where
+        F: FnOnce() -> Result<DirStats>,
+    {
+        // Taken before the walk, so changes made during it count as changes.
+        let fingerprint = fingerprint(path);
+        if !self.refresh
+            && let Some(fingerprint) = &fingerprint
+            && let Some(entry) = self.lock().get(path)
+            && entry.fingerprint == *fingerprint
+            && entry.largest_files >= self.largest_files
+        {
+            let mut stats = entry.stats.clone();
+            stats.largest_files.truncate(self.largest_files);
+            return Ok(stats);
+        }
+
+        let stats = compute()?;
+        // Keys are stored as JSON strings, so only UTF-8 paths are cached.
+        if let Some(fingerprint) = fingerprint
+            && path.to_str().is_some()
+        {
+            self.lock().insert(
+                path.to_path_buf(),
+                CacheEntry {
+                    fingerprint,
+                    stats: stats.clone(),
+                    largest_files: self.largest_files,
+                },
+            );
+        }
+        Ok(stats)
+    }
+
+    /// Writes the cache file, dropping entries whose dirs no longer exist;
+    /// a no-op when no cache dir could be determined.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let text = {
+            let mut entries = self.lock();
+            entries.retain(|dir, _| fs::symlink_metadata(dir).is_ok());
+            to_json(&self.sizing, &entries)
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create cache dir: {parent:?}"))?;
+        }
+
+        write_atomically(path, &(text + "\n"), "scan cache")
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, CacheEntry>> {
+        match self.entries.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// The `ScanOptions` that change what `dir_stats` counts.
+fn sizing_key(options: &ScanOptions) -> String {
+    let mut key = format!(
+        "same_device={},follow_symlinks={}",
+        options.same_device, options.follow_symlinks
+    );
+    if !options.excludes.is_empty() {
+        key.push_str(",excludes=");
+        key.push_str(&options.excludes.key());
+    }
+    key
+}
+
+fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let mut mtimes = vec![mtime_nanos(path)?];
+    let mut names = fs::read_dir(path)
+        .ok()?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .ok()?;
+    names.sort();
+    for name in names.into_iter().take(FINGERPRINT_SAMPLE) {
+        mtimes.push(mtime_nanos(&path.join(name))?);
+    }
+    Some(Fingerprint(mtimes))
+}
+
+fn mtime_nanos(path: &Path) -> Option<u64> {
+    let mtime = fs::symlink_metadata(path).ok()?.modified().ok()?;
+    Some(unix_nanos(mtime))
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+fn to_json(sizing: &str, entries: &HashMap<PathBuf, CacheEntry>) -> String {
+    let entries = json::array(entries.iter().filter_map(|(path, entry)| {
+        let time = |time: Option<SystemTime>| {
+            time.map(|t| unix_nanos(t).to_string())
+                .unwrap_or_else(|| "null".to_string())
+        };
+        let fingerprint = json::array(entry.fingerprint.0.iter().map(u64::to_string));
+        let largest_files =
+            json::array(entry.stats.largest_files.iter().filter_map(|(file, size)| {
+                Some(
+                    JsonObject::new()
+                        .str("path", file.to_str()?)
+                        .num("size_bytes", *size)
+                        .finish(),
+                )
+            }));
+        Some(
+            JsonObject::new()
+                .str("path", path.to_str()?)
+                .raw("fingerprint", &fingerprint)
+                .num("size_bytes", entry.stats.size_bytes)
+                .num("file_count", entry.stats.file_count)
+                .raw("newest_mtime", &time(entry.stats.newest_mtime))
+                .raw("oldest_mtime", &time(entry.stats.oldest_mtime))
+                .num("unreadable", entry.stats.unreadable as u64)
+                .num("other_device", entry.stats.other_device as u64)
+                .num("excluded", entry.stats.excluded as u64)
+                .raw("largest_files", &largest_files)
+                .num("largest_files_limit", entry.largest_files as u64)
+                .finish(),
+        )
+    }));
+    JsonObject::new()
+        .num("version", CACHE_VERSION)
+        .str("sizing", sizing)
+        .raw("entries", &entries)
+        .finish()
+}
+
+fn parse(text: &str, sizing: &str) -> Result<HashMap<PathBuf, CacheEntry>> {
+    let value = json::parse(text)?;
+    let version = value.get("version").and_then(JsonValue::as_u64);
+    if version != Some(CACHE_VERSION) {
+        bail!("unsupported scan cache version: {version:?}");
+    }
+    if value.get("sizing").and_then(JsonValue::as_str) != Some(sizing) {
+        return Ok(HashMap::new());
+    }
+    value
+        .get("entries")
+        .and_then(JsonValue::as_array)
+        .ok_or_else(|| anyhow!("scan cache has no entries"))?
+        .iter()
+        .map(|entry| {
+            let num = |key: &str| {
+                entry
+                    .get(key)
+                    .and_then(JsonValue::as_u64)
+                    .ok_or_else(|| anyhow!("scan cache entry has no {key}"))
+            };
+            let path = entry
+                .get("path")
+                .and_then(JsonValue::as_str)
+                .ok_or_else(|| anyhow!("scan cache entry has no path"))?;
+            let fingerprint = entry
+                .get("fingerprint")
+                .and_then(JsonValue::as_array)
+                .ok_or_else(|| anyhow!("scan cache entry has no fingerprint"))?
+                .iter()
+                .map(|mtime| {
+                    mtime
+                        .as_u64()
+                        .ok_or_else(|| anyhow!("invalid fingerprint in scan cache"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let time = |key: &str| {
+                entry
+                    .get(key)
+                    .and_then(JsonValue::as_u64)
+                    .map(|nanos| UNIX_EPOCH + Duration::from_nanos(nanos))
+            };
+            let stats = DirStats {
+                size_bytes: num("size_bytes")?,
+                file_count: num("file_count")?,
+                newest_mtime: time("newest_mtime"),
+                oldest_mtime: time("oldest_mtime"),
+                // Reading files moves atimes without touching the
+                // fingerprint, so they are never cached.
+                newest_atime: None,
+                unreadable: usize::try_from(num("unreadable")?).unwrap_or(usize::MAX),
+                other_device: usize::try_from(num("other_device")?).unwrap_or(usize::MAX),
+                // Written since excludes reach into artifacts; older
+                // entries were sized without any.
+                excluded: entry
+                    .get("excluded")
+                    .and_then(JsonValue::as_u64)
+                    .map_or(0, |n| usize::try_from(n).unwrap_or(usize::MAX)),
+                largest_files: entry
+                    .get("largest_files")
+                    .and_then(JsonValue::as_array)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|file| {
+                        let path = file.get("path")?.as_str()?;
+                        let size = file.get("size_bytes")?.as_u64()?;
+                        Some((PathBuf::from(path), size))
+                    })
+                    .collect(),
+            };
+            Ok((
+                PathBuf::from(path),
+                CacheEntry {
+                    fingerprint: Fingerprint(fingerprint),
+                    stats,
+                    largest_files: entry
+                        .get("largest_files_limit")
+                        .and_then(JsonValue::as_u64)
+                        .map_or(0, |n| usize::try_from(n).unwrap_or(usize::MAX)),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn default_cache_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| Path::new(&home).join(".cache"))
+        })?;
+    Some(base.join("clean-my-code").join("scan-cache.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::make_temp_dir;
+    use std::cell::Cell;
+
+    #[test]
+    fn reuses_unchanged_dirs_and_rewalks_changed_ones() {
+        let dir = make_temp_dir("clean-my-code-cache");
+        let artifact = dir.join("repo/target");
+        fs::create_dir_all(artifact.join("debug")).unwrap();
+        fs::write(artifact.join("debug/blob"), vec![0u8; 10]).unwrap();
+        let cache_path = dir.join("cache/scan-cache.json");
+        let options = ScanOptions::default();
+
+        let walks = Cell::new(0);
+        let size = |cache: &ScanCache| {
+            cache
+                .dir_stats(&artifact, || {
+                    walks.set(walks.get() + 1);
+                    crate::scan::dir_stats(&artifact, &options)
+                })
+                .unwrap()
+        };
+
+        let cache = ScanCache::load_from(cache_path.clone(), &options, false);
+        assert_eq!(size(&cache).size_bytes, 10);
+        assert_eq!(size(&cache).size_bytes, 10);
+        assert_eq!(walks.get(), 1);
+        cache.save().unwrap();
+        // Only the cache itself is left behind, no temp file.
+        assert_eq!(fs::read_dir(dir.join("cache")).unwrap().count(), 1);
+
+        let reloaded = ScanCache::load_from(cache_path.clone(), &options, false);
+        assert_eq!(size(&reloaded).file_count, 1);
+        assert_eq!(walks.get(), 1);
+
+        // A new file one level down changes the sampled child's mtime.
+        fs::write(artifact.join("debug/more"), vec![0u8; 5]).unwrap();
+        assert_eq!(size(&reloaded).size_bytes, 15);
+        assert_eq!(walks.get(), 2);
+
+        let refreshing = ScanCache::load_from(cache_path.clone(), &options, true);
+        size(&refreshing);
+        assert_eq!(walks.get(), 3);
+
+        let other_options = ScanOptions {
+            follow_symlinks: true,
+            ..ScanOptions::default()
+        };
+        let other = ScanCache::load_from(cache_path.clone(), &other_options, false);
+        assert!(other.lock().is_empty());
+
+        // Entries made for a shorter largest-files list are walked again;
+        // longer lists are reused, cut down.
+        let listing = |largest_files| ScanOptions {
+            largest_files,
+            ..ScanOptions::default()
+        };
+        let detailed = ScanCache::load_from(cache_path.clone(), &listing(5), false);
+        let stats = detailed
+            .dir_stats(&artifact, || {
+                walks.set(walks.get() + 1);
+                crate::scan::dir_stats(&artifact, &listing(5))
+            })
+            .unwrap();
+        assert_eq!(stats.largest_files.len(), 2);
+        assert_eq!(walks.get(), 4);
+        detailed.save().unwrap();
+        let shorter = ScanCache::load_from(cache_path.clone(), &listing(1), false);
+        let stats = shorter.dir_stats(&artifact, || unreachable!()).unwrap();
+        assert_eq!(stats.largest_files.len(), 1);
+        let plain = ScanCache::load_from(cache_path.clone(), &options, false);
+        assert!(size(&plain).largest_files.is_empty());
+        assert_eq!(walks.get(), 4);
+
+        fs::remove_dir_all(dir.join("repo")).unwrap();
+        reloaded.save().unwrap();
+        let pruned = ScanCache::load_from(cache_path, &options, false);
+        assert!(pruned.lock().is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
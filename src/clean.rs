@@ -1,12 +1,29 @@
 use std::{
+    collections::{BTreeMap, HashMap},
     ffi::OsStr,
     fs,
     path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
 };
 
-use anyhow::anyhow;
+use rayon::prelude::*;
 
-use crate::{git::is_git_ignored, report::RepoReport};
+use anyhow::{Context, anyhow, bail};
+
+use crate::{
+    format::{ByteUnit, display_rel_path, format_bytes_with},
+    git::{GitOptions, find_git_root, has_uncommitted_changes, is_git_ignored},
+    report::RepoReport,
+    roots::ScanRoots,
+    scan::{ScanOptions, dir_stats},
+};
+
+/// How far the measured size of the deleted dirs may drift from the plan
+/// before the summary warns about it.
+const SIZE_DIVERGENCE_WARN_RATIO: f64 = 0.10;
 
 #[derive(Debug, Clone)]
 pub struct DeleteTarget {
@@ -15,10 +32,37 @@ pub struct DeleteTarget {
     pub planned_bytes: u64,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct DeleteOptions {
+    pub dry_run: bool,
+    /// Delete different repos' targets concurrently; targets within a repo
+    /// are still removed one after another.
+    pub parallel: bool,
+    /// Skip every target of a repo with uncommitted changes.
+    pub require_clean_worktree: bool,
+    /// After removing a target, also remove parents it left empty, up to
+    /// (but never including) the repo root.
+    pub prune_empty_parents: bool,
+    /// Stop once this many bytes are freed, going through the targets
+    /// largest first so it takes as few deletions as possible. A dry run
+    /// counts what it would have freed.
+    pub free_goal: Option<u64>,
+    /// The options the targets were scanned with. Each target is sized
+    /// again with them right before it goes, so the device, symlink and
+    /// open-dir settings apply to that walk too.
+    pub scan: ScanOptions,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct DeleteProgress {
     pub processed: usize,
     pub total: usize,
+    /// Index into the targets of the one just handled. With parallel
+    /// deletion this is not `processed - 1`.
+    pub target_index: usize,
+    /// Whether that target passed every check (and, unless this is a dry
+    /// run, was deleted).
+    pub target_cleared: bool,
     pub deleted_paths: usize,
     pub deleted_bytes: u64,
     pub skipped_paths: usize,
@@ -30,11 +74,76 @@ pub struct DeleteSummary {
     pub planned_paths: usize,
     pub planned_bytes: u64,
     pub deleted_paths: usize,
+    /// Size of the deleted dirs, measured just before each was removed.
     pub deleted_bytes: u64,
+    /// What the scan planned for those same dirs.
+    pub deleted_planned_bytes: u64,
+    /// Dry runs only: targets that passed every check, i.e. what a real run
+    /// would have deleted, and what the scan planned for them.
+    pub kept_paths: usize,
+    pub kept_planned_bytes: u64,
+    /// Targets a safety check left alone, always `skipped.len()`. Targets
+    /// whose checks failed with an error are counted in `errors` instead.
     pub skipped_paths: usize,
+    /// Targets a safety check deliberately left alone.
+    pub skipped: Vec<(PathBuf, SkipReason)>,
     pub errors: Vec<(PathBuf, anyhow::Error)>,
+    /// Parent dirs removed because deleting a target left them empty.
+    pub pruned_parents: Vec<PathBuf>,
+    /// Set when `DeleteOptions::free_goal` was met; the targets not yet
+    /// handled at that point were left alone.
+    pub free_goal_reached: bool,
+    pub untouched_paths: usize,
+}
+
+/// Why a target was left alone on purpose. Unlike `errors`, these are the
+/// safety checks doing their job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A `.git` dir, which is never deleted.
+    Blocked,
+    /// The target is a symlink itself.
+    Symlink,
+    /// The target resolves outside its repo through a symlinked ancestor.
+    OutsideRepo,
+    /// Git no longer ignores the target.
+    NotIgnored,
+    /// The target is already gone.
+    NotFound,
+    /// The repo has uncommitted changes and a clean worktree was required.
+    WorktreeDirty,
+    /// The target is a repo itself or holds one.
+    NestedRepo,
+}
+
+impl SkipReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::Blocked => "blocked",
+            SkipReason::Symlink => "symlink",
+            SkipReason::OutsideRepo => "outside-repo",
+            SkipReason::NotIgnored => "not-ignored",
+            SkipReason::NotFound => "not-found",
+            SkipReason::WorktreeDirty => "worktree-dirty",
+            SkipReason::NestedRepo => "nested-repo",
+        }
+    }
+}
+
+impl DeleteSummary {
+    /// Relative change of the measured size of the deleted dirs against
+    /// the plan, when it is large enough to be worth pointing out.
+    pub fn size_divergence(&self) -> Option<f64> {
+        if self.deleted_planned_bytes == 0 {
+            return None;
+        }
+        let planned = self.deleted_planned_bytes as f64;
+        let change = (self.deleted_bytes as f64 - planned) / planned;
+        (change.abs() > SIZE_DIVERGENCE_WARN_RATIO).then_some(change)
+    }
 }
 
+/// Only deletable artifacts are planned; see `ArtifactRecord::is_deletable`.
 pub fn plan_delete_targets<'a, I>(reports: I) -> Vec<DeleteTarget>
 where
     I: IntoIterator<Item = (&'a RepoReport, bool)>,
@@ -45,7 +154,7 @@ where
             continue;
         }
 
-        for artifact in &report.artifacts {
+        for artifact in report.artifacts.iter().filter(|a| a.is_deletable()) {
             targets.push(DeleteTarget {
                 repo_root: report.repo_root.clone(),
                 path: artifact.path.clone(),
@@ -58,16 +167,120 @@ where
     targets
 }
 
+/// Turns a dir named on the command line into a delete target, refusing
+/// anything a scan would not have reported: symlinks, dirs outside a git
+/// repo and dirs git does not ignore. The dir is sized for the plan.
+pub fn resolve_delete_target(path: &Path, options: &ScanOptions) -> anyhow::Result<DeleteTarget> {
+    let meta =
+        fs::symlink_metadata(path).with_context(|| format!("no such directory: {path:?}"))?;
+    if meta.file_type().is_symlink() {
+        bail!("refusing to delete a symlink: {path:?}");
+    }
+    if !meta.is_dir() {
+        bail!("not a directory: {path:?}");
+    }
+    let path =
+        fs::canonicalize(path).with_context(|| format!("failed to resolve path: {path:?}"))?;
+    if is_blocked_path(&path) {
+        bail!("refusing to delete a .git dir: {path:?}");
+    }
+    if let Some(git_dir) = find_nested_git(&path)? {
+        bail!("refusing to delete a dir holding a git repo: {git_dir:?}");
+    }
+    let repo_root = path
+        .parent()
+        .and_then(find_git_root)
+        .ok_or_else(|| anyhow!("not inside a git repo: {path:?}"))?;
+    if !is_git_ignored(&repo_root, &path, &options.git)? {
+        bail!("not ignored by git in {repo_root:?}: {path:?}");
+    }
+    let stats = dir_stats(&path, options)?;
+    Ok(DeleteTarget {
+        repo_root,
+        path,
+        planned_bytes: stats.size_bytes,
+    })
+}
+
+/// Delete targets of a single repo, as shown in plan printouts.
+#[derive(Debug)]
+pub struct RepoPlan<'a> {
+    pub repo_root: &'a Path,
+    pub targets: Vec<&'a DeleteTarget>,
+    pub planned_bytes: u64,
+}
+
+impl RepoPlan<'_> {
+    pub fn header_line(&self, scan_roots: &ScanRoots, unit: Option<ByteUnit>) -> String {
+        format!(
+            "{}  {} dirs, {}",
+            scan_roots.display_rel(self.repo_root),
+            self.targets.len(),
+            format_bytes_with(self.planned_bytes, unit)
+        )
+    }
+
+    pub fn target_lines(&self, unit: Option<ByteUnit>) -> impl Iterator<Item = String> + '_ {
+        self.targets.iter().map(move |target| {
+            format!(
+                "  {}  {}",
+                format_bytes_with(target.planned_bytes, unit),
+                display_rel_path(self.repo_root, &target.path)
+            )
+        })
+    }
+}
+
+/// Groups targets by repo, largest repos first and largest dirs first within
+/// each repo.
+pub fn group_targets_by_repo(targets: &[DeleteTarget]) -> Vec<RepoPlan<'_>> {
+    let mut groups: Vec<RepoPlan<'_>> = Vec::new();
+    for target in targets {
+        match groups.iter_mut().find(|g| g.repo_root == target.repo_root) {
+            Some(group) => {
+                group.targets.push(target);
+                group.planned_bytes = group.planned_bytes.saturating_add(target.planned_bytes);
+            }
+            None => groups.push(RepoPlan {
+                repo_root: &target.repo_root,
+                targets: vec![target],
+                planned_bytes: target.planned_bytes,
+            }),
+        }
+    }
+
+    for group in &mut groups {
+        group.targets.sort_by(|a, b| {
+            b.planned_bytes
+                .cmp(&a.planned_bytes)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+    }
+    groups.sort_by(|a, b| {
+        b.planned_bytes
+            .cmp(&a.planned_bytes)
+            .then_with(|| a.repo_root.cmp(b.repo_root))
+    });
+    groups
+}
+
 pub fn execute_delete_with_progress<C, F>(
     targets: &[DeleteTarget],
-    dry_run: bool,
+    mut options: DeleteOptions,
     should_cancel: C,
     mut on_progress: F,
 ) -> DeleteSummary
 where
-    C: Fn() -> bool,
-    F: FnMut(DeleteProgress),
+    C: Fn() -> bool + Sync,
+    F: FnMut(DeleteProgress) + Send,
 {
+    // Only the total is wanted when re-sizing a target.
+    options.scan.largest_files = 0;
+    if options.parallel {
+        return execute_delete_parallel(targets, options, should_cancel, on_progress);
+    }
+    let worktrees = WorktreeCheck::new(options.require_clean_worktree, &options.scan.git);
+
     let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
     let mut summary = DeleteSummary {
         planned_paths: targets.len(),
@@ -75,99 +288,893 @@ where
         ..DeleteSummary::default()
     };
 
-    for (index, target) in targets.iter().enumerate() {
-        let processed = index + 1;
-        let total = summary.planned_paths;
-
+    let mut freed = 0u64;
+    for (processed, index) in processing_order(targets, &options).into_iter().enumerate() {
         if should_cancel() {
             break;
         }
-
-        if is_blocked_path(&target.path) {
-            summary.skipped_paths += 1;
-            summary.errors.push((
-                target.path.clone(),
-                anyhow!("refusing to delete blocked path"),
-            ));
-            on_progress(DeleteProgress {
-                processed,
-                total,
-                deleted_paths: summary.deleted_paths,
-                deleted_bytes: summary.deleted_bytes,
-                skipped_paths: summary.skipped_paths,
-                error_count: summary.errors.len(),
-            });
-            continue;
-        }
-
-        match is_git_ignored(&target.repo_root, &target.path) {
-            Ok(true) => {}
-            Ok(false) => {
-                summary.skipped_paths += 1;
-                on_progress(DeleteProgress {
-                    processed,
-                    total,
-                    deleted_paths: summary.deleted_paths,
-                    deleted_bytes: summary.deleted_bytes,
-                    skipped_paths: summary.skipped_paths,
-                    error_count: summary.errors.len(),
-                });
-                continue;
-            }
-            Err(err) => {
-                summary.skipped_paths += 1;
-                summary.errors.push((target.path.clone(), err));
-                on_progress(DeleteProgress {
-                    processed,
-                    total,
-                    deleted_paths: summary.deleted_paths,
-                    deleted_bytes: summary.deleted_bytes,
-                    skipped_paths: summary.skipped_paths,
-                    error_count: summary.errors.len(),
-                });
-                continue;
-            }
-        }
-
-        if dry_run {
-            on_progress(DeleteProgress {
-                processed,
-                total,
-                deleted_paths: summary.deleted_paths,
-                deleted_bytes: summary.deleted_bytes,
-                skipped_paths: summary.skipped_paths,
-                error_count: summary.errors.len(),
-            });
-            continue;
+        if options.free_goal.is_some_and(|goal| freed >= goal) {
+            summary.free_goal_reached = true;
+            summary.untouched_paths = targets.len() - processed;
+            break;
         }
 
-        match fs::remove_dir_all(&target.path) {
-            Ok(()) => {
+        let target = &targets[index];
+        let outcome = delete_target(target, &options, &worktrees);
+        let target_cleared = outcome.is_cleared();
+        freed = freed.saturating_add(outcome.freed_bytes(target));
+        match outcome {
+            TargetOutcome::Deleted {
+                measured_bytes,
+                pruned,
+            } => {
+                summary.pruned_parents.extend(pruned);
                 summary.deleted_paths += 1;
-                summary.deleted_bytes = summary.deleted_bytes.saturating_add(target.planned_bytes);
+                summary.deleted_bytes = summary.deleted_bytes.saturating_add(measured_bytes);
+                summary.deleted_planned_bytes = summary
+                    .deleted_planned_bytes
+                    .saturating_add(target.planned_bytes);
+            }
+            TargetOutcome::Kept => {
+                summary.kept_paths += 1;
+                summary.kept_planned_bytes = summary
+                    .kept_planned_bytes
+                    .saturating_add(target.planned_bytes);
             }
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            TargetOutcome::Skipped(reason) => {
                 summary.skipped_paths += 1;
+                summary.skipped.push((target.path.clone(), reason));
             }
-            Err(err) => {
-                summary.errors.push((target.path.clone(), err.into()));
+            TargetOutcome::CheckFailed(err) | TargetOutcome::Failed(err) => {
+                summary.errors.push((target.path.clone(), err));
             }
         }
 
         on_progress(DeleteProgress {
-            processed,
-            total,
+            processed: processed + 1,
+            total: summary.planned_paths,
+            target_index: index,
+            target_cleared,
             deleted_paths: summary.deleted_paths,
             deleted_bytes: summary.deleted_bytes,
             skipped_paths: summary.skipped_paths,
             error_count: summary.errors.len(),
         });
     }
+    if options.free_goal.is_some_and(|goal| freed >= goal) {
+        summary.free_goal_reached = true;
+    }
 
     summary
 }
 
+/// Indexes into `targets` in the order to handle them: as planned, or
+/// largest first when working toward a free-space goal.
+fn processing_order(targets: &[DeleteTarget], options: &DeleteOptions) -> Vec<usize> {
+    let mut order = (0..targets.len()).collect::<Vec<_>>();
+    if options.free_goal.is_some() {
+        order.sort_by(|&a, &b| targets[b].planned_bytes.cmp(&targets[a].planned_bytes));
+    }
+    order
+}
+
+/// Runs each repo's targets on its own rayon task. Progress callbacks are
+/// serialized, so `processed` still only ever grows by one.
+fn execute_delete_parallel<C, F>(
+    targets: &[DeleteTarget],
+    options: DeleteOptions,
+    should_cancel: C,
+    on_progress: F,
+) -> DeleteSummary
+where
+    C: Fn() -> bool + Sync,
+    F: FnMut(DeleteProgress) + Send,
+{
+    let mut by_repo: BTreeMap<&Path, Vec<(usize, &DeleteTarget)>> = BTreeMap::new();
+    for index in processing_order(targets, &options) {
+        let target = &targets[index];
+        by_repo
+            .entry(target.repo_root.as_path())
+            .or_default()
+            .push((index, target));
+    }
+    let mut by_repo = by_repo.into_values().collect::<Vec<_>>();
+    if options.free_goal.is_some() {
+        // Start the repos holding the largest targets first.
+        by_repo.sort_by_key(|repo_targets| {
+            std::cmp::Reverse(repo_targets.first().map_or(0, |(_, t)| t.planned_bytes))
+        });
+    }
+
+    let total = targets.len();
+    let deleted_paths = AtomicUsize::new(0);
+    let deleted_bytes = AtomicU64::new(0);
+    let deleted_planned_bytes = AtomicU64::new(0);
+    let kept_paths = AtomicUsize::new(0);
+    let kept_planned_bytes = AtomicU64::new(0);
+    let skipped_paths = AtomicUsize::new(0);
+    let skipped = Mutex::new(Vec::new());
+    let errors = Mutex::new(Vec::new());
+    let pruned_parents = Mutex::new(Vec::new());
+    let freed = AtomicU64::new(0);
+    let goal_reached = || {
+        options
+            .free_goal
+            .is_some_and(|goal| freed.load(Ordering::Relaxed) >= goal)
+    };
+    let progress = Mutex::new((0usize, on_progress));
+    let worktrees = WorktreeCheck::new(options.require_clean_worktree, &options.scan.git);
+
+    by_repo.into_par_iter().for_each(|repo_targets| {
+        for (index, target) in repo_targets {
+            if should_cancel() || goal_reached() {
+                return;
+            }
+
+            let outcome = delete_target(target, &options, &worktrees);
+            let target_cleared = outcome.is_cleared();
+            freed.fetch_add(outcome.freed_bytes(target), Ordering::Relaxed);
+            match outcome {
+                TargetOutcome::Deleted {
+                    measured_bytes,
+                    pruned,
+                } => {
+                    lock(&pruned_parents).extend(pruned);
+                    deleted_paths.fetch_add(1, Ordering::Relaxed);
+                    deleted_bytes.fetch_add(measured_bytes, Ordering::Relaxed);
+                    deleted_planned_bytes.fetch_add(target.planned_bytes, Ordering::Relaxed);
+                }
+                TargetOutcome::Kept => {
+                    kept_paths.fetch_add(1, Ordering::Relaxed);
+                    kept_planned_bytes.fetch_add(target.planned_bytes, Ordering::Relaxed);
+                }
+                TargetOutcome::Skipped(reason) => {
+                    skipped_paths.fetch_add(1, Ordering::Relaxed);
+                    lock(&skipped).push((target.path.clone(), reason));
+                }
+                TargetOutcome::CheckFailed(err) | TargetOutcome::Failed(err) => {
+                    lock(&errors).push((target.path.clone(), err));
+                }
+            }
+
+            let mut progress = lock(&progress);
+            let (processed, on_progress) = &mut *progress;
+            *processed += 1;
+            on_progress(DeleteProgress {
+                processed: *processed,
+                total,
+                target_index: index,
+                target_cleared,
+                deleted_paths: deleted_paths.load(Ordering::Relaxed),
+                deleted_bytes: deleted_bytes.load(Ordering::Relaxed),
+                skipped_paths: skipped_paths.load(Ordering::Relaxed),
+                error_count: lock(&errors).len(),
+            });
+        }
+    });
+
+    let mut errors = errors.into_inner().unwrap_or_else(|e| e.into_inner());
+    errors.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut skipped = skipped.into_inner().unwrap_or_else(|e| e.into_inner());
+    skipped.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut pruned_parents = pruned_parents
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner());
+    pruned_parents.sort();
+    let (processed, _) = progress.into_inner().unwrap_or_else(|e| e.into_inner());
+    let free_goal_reached = goal_reached();
+    DeleteSummary {
+        planned_paths: total,
+        planned_bytes: targets.iter().map(|t| t.planned_bytes).sum(),
+        deleted_paths: deleted_paths.into_inner(),
+        deleted_bytes: deleted_bytes.into_inner(),
+        deleted_planned_bytes: deleted_planned_bytes.into_inner(),
+        kept_paths: kept_paths.into_inner(),
+        kept_planned_bytes: kept_planned_bytes.into_inner(),
+        skipped_paths: skipped_paths.into_inner(),
+        skipped,
+        errors,
+        pruned_parents,
+        free_goal_reached,
+        untouched_paths: if free_goal_reached {
+            total - processed
+        } else {
+            0
+        },
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+enum TargetOutcome {
+    Deleted {
+        measured_bytes: u64,
+        /// Empty parents removed after the target, innermost first.
+        pruned: Vec<PathBuf>,
+    },
+    /// Passed every check but left in place (dry run).
+    Kept,
+    Skipped(SkipReason),
+    /// A safety check could not be completed, so the target was kept.
+    /// Reported as an error, not a skip.
+    CheckFailed(anyhow::Error),
+    Failed(anyhow::Error),
+}
+
+impl TargetOutcome {
+    fn is_cleared(&self) -> bool {
+        matches!(self, Self::Deleted { .. } | Self::Kept)
+    }
+
+    /// What handling `target` counts toward a free-space goal.
+    fn freed_bytes(&self, target: &DeleteTarget) -> u64 {
+        match self {
+            Self::Deleted { measured_bytes, .. } => *measured_bytes,
+            Self::Kept => target.planned_bytes,
+            _ => 0,
+        }
+    }
+}
+
+/// `git status` results per repo, so a repo with many targets is only
+/// checked once.
+struct WorktreeCheck<'a> {
+    required: bool,
+    git: &'a GitOptions,
+    dirty: Mutex<HashMap<PathBuf, bool>>,
+}
+
+impl<'a> WorktreeCheck<'a> {
+    fn new(required: bool, git: &'a GitOptions) -> Self {
+        Self {
+            required,
+            git,
+            dirty: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn is_dirty(&self, repo_root: &Path) -> bool {
+        if !self.required {
+            return false;
+        }
+        let cached = lock(&self.dirty).get(repo_root).copied();
+        match cached {
+            Some(dirty) => dirty,
+            None => {
+                // A failed check counts as dirty: the point is to be sure.
+                let dirty = has_uncommitted_changes(repo_root, self.git).unwrap_or(true);
+                lock(&self.dirty).insert(repo_root.to_path_buf(), dirty);
+                dirty
+            }
+        }
+    }
+}
+
+/// Re-checks a single target and removes it unless this is a dry run.
+fn delete_target(
+    target: &DeleteTarget,
+    options: &DeleteOptions,
+    worktrees: &WorktreeCheck<'_>,
+) -> TargetOutcome {
+    if is_blocked_path(&target.path) {
+        return TargetOutcome::Skipped(SkipReason::Blocked);
+    }
+
+    match check_within_repo(target) {
+        Ok(None) => {}
+        Ok(Some(reason)) => return TargetOutcome::Skipped(reason),
+        Err(err) => return TargetOutcome::CheckFailed(err),
+    }
+
+    match find_nested_git(&target.path) {
+        Ok(None) => {}
+        Ok(Some(_)) => return TargetOutcome::Skipped(SkipReason::NestedRepo),
+        Err(err) => return TargetOutcome::CheckFailed(err),
+    }
+
+    if worktrees.is_dirty(&target.repo_root) {
+        return TargetOutcome::Skipped(SkipReason::WorktreeDirty);
+    }
+
+    match is_git_ignored(&target.repo_root, &target.path, &options.scan.git) {
+        Ok(true) => {}
+        Ok(false) => return TargetOutcome::Skipped(SkipReason::NotIgnored),
+        Err(err) => return TargetOutcome::CheckFailed(err),
+    }
+
+    if options.dry_run {
+        return TargetOutcome::Kept;
+    }
+
+    // The dir may have grown or shrunk since the scan; credit what is
+    // actually there, falling back to the plan if it can't be walked.
+    let measured_bytes = dir_stats(&target.path, &options.scan)
+        .map(|stats| stats.size_bytes)
+        .unwrap_or(target.planned_bytes);
+    match fs::remove_dir_all(&target.path) {
+        Ok(()) => TargetOutcome::Deleted {
+            measured_bytes,
+            pruned: if options.prune_empty_parents {
+                prune_empty_parents(target)
+            } else {
+                Vec::new()
+            },
+        },
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            TargetOutcome::Skipped(SkipReason::NotFound)
+        }
+        Err(err) => TargetOutcome::Failed(err.into()),
+    }
+}
+
+/// Removes the target's parents while they are empty, stopping at the first
+/// one that still has entries and never touching the repo root itself.
+/// `remove_dir` only succeeds on an empty dir, so a file appearing in the
+/// meantime just ends the walk.
+fn prune_empty_parents(target: &DeleteTarget) -> Vec<PathBuf> {
+    let mut pruned = Vec::new();
+    let mut dir = target.path.parent();
+    while let Some(parent) = dir {
+        if parent == target.repo_root
+            || !parent.starts_with(&target.repo_root)
+            || is_blocked_path(parent)
+            || fs::remove_dir(parent).is_err()
+        {
+            break;
+        }
+        pruned.push(parent.to_path_buf());
+        dir = parent.parent();
+    }
+    pruned
+}
+
+pub fn format_delete_summary(
+    scan_roots: &ScanRoots,
+    summary: &DeleteSummary,
+    dry_run: bool,
+    canceled: bool,
+    unit: Option<ByteUnit>,
+) -> Vec<String> {
+    let dry_run_label = if dry_run { " (dry run)" } else { "" };
+
+    let mut lines = Vec::new();
+    lines.push(format!("root: {}", scan_roots));
+    if canceled {
+        lines.push("status: canceled".to_string());
+    } else if summary.free_goal_reached && summary.untouched_paths > 0 {
+        lines.push(format!(
+            "status: free-space goal reached; left {} dirs untouched",
+            summary.untouched_paths
+        ));
+    }
+    lines.push(format!(
+        "planned: {} dirs, reclaim {}{}",
+        summary.planned_paths,
+        format_bytes_with(summary.planned_bytes, unit),
+        dry_run_label
+    ));
+    lines.push(format!(
+        "deleted: {} dirs, reclaimed {}",
+        summary.deleted_paths,
+        format_bytes_with(summary.deleted_bytes, unit)
+    ));
+    if let Some(change) = summary.size_divergence() {
+        lines.push(format!(
+            "warning: deleted dirs measured {} at deletion vs {} planned ({:+.0}%); they changed since the scan",
+            format_bytes_with(summary.deleted_bytes, unit),
+            format_bytes_with(summary.deleted_planned_bytes, unit),
+            change * 100.0
+        ));
+    }
+    lines.push(format!("skipped: {} dirs", summary.skipped_paths));
+
+    if !summary.skipped.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("skipped ({}):", summary.skipped.len()));
+        for (path, reason) in &summary.skipped {
+            lines.push(format!(
+                "- {}: {}",
+                scan_roots.display_rel(path),
+                reason.as_str()
+            ));
+        }
+    }
+
+    if !summary.pruned_parents.is_empty() {
+        lines.push(String::new());
+        lines.push(format!(
+            "pruned empty parents ({}):",
+            summary.pruned_parents.len()
+        ));
+        for path in &summary.pruned_parents {
+            lines.push(format!("- {}", scan_roots.display_rel(path)));
+        }
+    }
+
+    if !summary.errors.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("errors ({}):", summary.errors.len()));
+        for (path, err) in &summary.errors {
+            // With the context chain, so a failed git check says why.
+            lines.push(format!("- {}: {err:#}", scan_roots.display_rel(path)));
+        }
+    }
+
+    lines
+}
+
+/// Refuses targets that are symlinks themselves or that resolve (through a
+/// symlinked ancestor) to a location outside their repo root.
+fn check_within_repo(target: &DeleteTarget) -> anyhow::Result<Option<SkipReason>> {
+    let meta = match fs::symlink_metadata(&target.path) {
+        Ok(meta) => meta,
+        // Missing targets are handled (and counted) by the delete step.
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    if meta.file_type().is_symlink() {
+        return Ok(Some(SkipReason::Symlink));
+    }
+
+    let repo_root = fs::canonicalize(&target.repo_root)?;
+    let path = fs::canonicalize(&target.path)?;
+    if path == repo_root || !path.starts_with(&repo_root) {
+        return Ok(Some(SkipReason::OutsideRepo));
+    }
+
+    Ok(None)
+}
+
+/// The first `.git` at or below `dir`, which would make `dir` a repo of its
+/// own or the home of nested ones. Symlinks are not followed.
+fn find_nested_git(dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Gone since it was listed (or before the check), so it holds
+            // nothing; a missing target is counted by the delete step.
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read dir: {dir:?}"));
+            }
+        };
+        for entry in entries {
+            let entry = entry.with_context(|| format!("failed to read dir: {dir:?}"))?;
+            let path = entry.path();
+            if entry.file_name() == OsStr::new(".git") {
+                return Ok(Some(path));
+            }
+            if entry.file_type().is_ok_and(|ty| ty.is_dir()) {
+                pending.push(path);
+            }
+        }
+    }
+    Ok(None)
+}
+
 fn is_blocked_path(path: &Path) -> bool {
     path.file_name()
         .is_some_and(|name| name == OsStr::new(".git"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{git, init_repo, make_temp_dir};
+
+    #[test]
+    fn groups_targets_by_repo_largest_first() {
+        let target = |repo: &str, path: &str, planned_bytes| DeleteTarget {
+            repo_root: PathBuf::from(repo),
+            path: PathBuf::from(repo).join(path),
+            planned_bytes,
+        };
+        let targets = vec![
+            target("/a", "dist", 5),
+            target("/a", "target", 50),
+            target("/b", "node_modules", 40),
+            target("/c", "build", 1),
+        ];
+
+        let groups = group_targets_by_repo(&targets);
+        let order = groups
+            .iter()
+            .map(|g| (g.repo_root.to_str().unwrap(), g.planned_bytes))
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![("/a", 55), ("/b", 40), ("/c", 1)]);
+        assert_eq!(groups[0].targets[0].path, PathBuf::from("/a/target"));
+        assert_eq!(
+            groups[0].target_lines(None).collect::<Vec<_>>(),
+            vec!["  50 B  target", "  5 B  dist"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_targets_that_resolve_outside_repo() {
+        let root = make_temp_dir("clean-my-code-clean");
+        let repo = root.join("repo");
+        let outside = root.join("outside");
+        fs::create_dir_all(repo.join("sub")).unwrap();
+        fs::create_dir_all(outside.join("target")).unwrap();
+        fs::write(outside.join("target/keep.txt"), "keep").unwrap();
+        std::os::unix::fs::symlink(&outside, repo.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("target"), repo.join("sub/target")).unwrap();
+
+        let targets = vec![
+            DeleteTarget {
+                repo_root: repo.clone(),
+                path: repo.join("link/target"),
+                planned_bytes: 4,
+            },
+            DeleteTarget {
+                repo_root: repo.clone(),
+                path: repo.join("sub/target"),
+                planned_bytes: 4,
+            },
+        ];
+        let summary =
+            execute_delete_with_progress(&targets, DeleteOptions::default(), || false, |_| {});
+
+        assert_eq!(summary.deleted_paths, 0);
+        assert_eq!(summary.skipped_paths, 2);
+        assert_eq!(
+            summary.skipped,
+            vec![
+                (repo.join("link/target"), SkipReason::OutsideRepo),
+                (repo.join("sub/target"), SkipReason::Symlink),
+            ]
+        );
+        assert!(summary.errors.is_empty());
+        assert!(outside.join("target/keep.txt").exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn failed_checks_count_as_errors_not_skips() {
+        let root = make_temp_dir("clean-my-code-clean");
+        // A `.git` git itself rejects, so the ignore check fails.
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("target")).unwrap();
+        let targets = vec![DeleteTarget {
+            repo_root: repo.clone(),
+            path: repo.join("target"),
+            planned_bytes: 4,
+        }];
+
+        for parallel in [false, true] {
+            let options = DeleteOptions {
+                parallel,
+                ..DeleteOptions::default()
+            };
+            let summary = execute_delete_with_progress(&targets, options, || false, |_| {});
+            assert_eq!(summary.skipped_paths, 0);
+            assert_eq!(summary.skipped_paths, summary.skipped.len());
+            assert_eq!(summary.errors.len(), 1);
+            assert!(repo.join("target").exists());
+        }
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn parallel_delete_reports_monotonic_progress() {
+        let root = make_temp_dir("clean-my-code-parallel");
+        let mut targets = Vec::new();
+        for repo in ["a", "b", "c"] {
+            let repo_root = root.join(repo);
+            fs::create_dir_all(&repo_root).unwrap();
+            init_repo(&repo_root);
+            fs::write(repo_root.join(".gitignore"), "target/\ndist/\n").unwrap();
+            for name in ["target", "dist"] {
+                fs::create_dir_all(repo_root.join(name)).unwrap();
+                fs::write(repo_root.join(name).join("out.bin"), "xx").unwrap();
+                targets.push(DeleteTarget {
+                    repo_root: repo_root.clone(),
+                    path: repo_root.join(name),
+                    planned_bytes: 2,
+                });
+            }
+        }
+
+        let mut seen = Vec::new();
+        let summary = execute_delete_with_progress(
+            &targets,
+            DeleteOptions {
+                parallel: true,
+                ..DeleteOptions::default()
+            },
+            || false,
+            |progress| seen.push((progress.processed, progress.target_index)),
+        );
+
+        assert_eq!(summary.deleted_paths, 6);
+        assert_eq!(summary.deleted_bytes, 12);
+        assert!(summary.errors.is_empty());
+        assert_eq!(
+            seen.iter()
+                .map(|(processed, _)| *processed)
+                .collect::<Vec<_>>(),
+            (1..=6).collect::<Vec<_>>()
+        );
+        let mut indexes = seen.iter().map(|(_, index)| *index).collect::<Vec<_>>();
+        indexes.sort();
+        assert_eq!(indexes, (0..6).collect::<Vec<_>>());
+        assert!(targets.iter().all(|t| !t.path.exists()));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn measures_deleted_dirs_and_warns_on_divergence() {
+        let root = make_temp_dir("clean-my-code-measure");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("target")).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+        fs::write(repo.join("target/out.bin"), vec![0u8; 300]).unwrap();
+
+        let targets = vec![DeleteTarget {
+            repo_root: repo.clone(),
+            path: repo.join("target"),
+            planned_bytes: 100,
+        }];
+        let summary =
+            execute_delete_with_progress(&targets, DeleteOptions::default(), || false, |_| {});
+
+        assert_eq!(summary.deleted_bytes, 300);
+        assert_eq!(summary.deleted_planned_bytes, 100);
+        assert_eq!(summary.size_divergence(), Some(2.0));
+        let roots = ScanRoots::from_canonical(vec![root.clone()]);
+        let lines = format_delete_summary(&roots, &summary, false, false, None);
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("warning: ") && l.contains("+200%"))
+        );
+
+        let close = DeleteSummary {
+            deleted_bytes: 105,
+            deleted_planned_bytes: 100,
+            ..DeleteSummary::default()
+        };
+        assert_eq!(close.size_divergence(), None);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn measures_deleted_dirs_with_the_scan_options() {
+        let root = make_temp_dir("clean-my-code-measure-opts");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("target")).unwrap();
+        fs::create_dir_all(root.join("shared")).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+        fs::write(repo.join("target/out.bin"), vec![0u8; 10]).unwrap();
+        fs::write(root.join("shared/blob"), vec![0u8; 90]).unwrap();
+        std::os::unix::fs::symlink(root.join("shared"), repo.join("target/shared")).unwrap();
+
+        let summary = execute_delete_with_progress(
+            &[DeleteTarget {
+                repo_root: repo.clone(),
+                path: repo.join("target"),
+                planned_bytes: 100,
+            }],
+            DeleteOptions {
+                scan: ScanOptions {
+                    follow_symlinks: true,
+                    ..ScanOptions::default()
+                },
+                ..DeleteOptions::default()
+            },
+            || false,
+            |_| {},
+        );
+        let shared_kept = root.join("shared/blob").exists();
+        let _ = fs::remove_dir_all(root);
+
+        assert_eq!(summary.deleted_paths, 1);
+        assert_eq!(summary.deleted_bytes, 100);
+        assert!(shared_kept);
+    }
+
+    #[test]
+    fn skips_repos_with_uncommitted_changes() {
+        let root = make_temp_dir("clean-my-code-dirty");
+        let mut targets = Vec::new();
+        for repo in ["clean", "dirty"] {
+            let repo_root = root.join(repo);
+            fs::create_dir_all(repo_root.join("target")).unwrap();
+            init_repo(&repo_root);
+            fs::write(repo_root.join(".gitignore"), "target/\n").unwrap();
+            git(&repo_root, &["add", ".gitignore"]);
+            git(&repo_root, &["commit", "-q", "-m", "init"]);
+            targets.push(DeleteTarget {
+                repo_root: repo_root.clone(),
+                path: repo_root.join("target"),
+                planned_bytes: 0,
+            });
+        }
+        fs::write(root.join("dirty/notes.txt"), "wip").unwrap();
+
+        let summary = execute_delete_with_progress(
+            &targets,
+            DeleteOptions {
+                require_clean_worktree: true,
+                ..DeleteOptions::default()
+            },
+            || false,
+            |_| {},
+        );
+
+        assert_eq!(summary.deleted_paths, 1);
+        assert_eq!(summary.skipped_paths, 1);
+        assert_eq!(
+            summary.skipped,
+            vec![(root.join("dirty/target"), SkipReason::WorktreeDirty)]
+        );
+        assert!(summary.errors.is_empty());
+        let roots = ScanRoots::from_canonical(vec![root.clone()]);
+        let lines = format_delete_summary(&roots, &summary, false, false, None);
+        assert!(lines.iter().any(|l| l == "- dirty/target: worktree-dirty"));
+        assert!(root.join("dirty/target").exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn cleans_repos_without_commits() {
+        let root = make_temp_dir("clean-my-code-fresh");
+        let repo_root = root.join("fresh");
+        fs::create_dir_all(repo_root.join("target/deep")).unwrap();
+        fs::write(repo_root.join("target/deep/blob"), vec![0u8; 10]).unwrap();
+        init_repo(&repo_root);
+        fs::write(repo_root.join(".gitignore"), "target/\n").unwrap();
+
+        let summary = execute_delete_with_progress(
+            &[DeleteTarget {
+                repo_root: repo_root.clone(),
+                path: repo_root.join("target"),
+                planned_bytes: 10,
+            }],
+            DeleteOptions::default(),
+            || false,
+            |_| {},
+        );
+        let gone = !repo_root.join("target").exists();
+        let _ = fs::remove_dir_all(root);
+
+        assert_eq!(summary.deleted_paths, 1);
+        assert!(summary.skipped.is_empty());
+        assert!(summary.errors.is_empty());
+        assert!(gone);
+    }
+
+    #[test]
+    fn prunes_parents_left_empty_up_to_the_repo_root() {
+        let root = make_temp_dir("clean-my-code-prune");
+        let repo_root = root.join("repo");
+        fs::create_dir_all(repo_root.join("cmake-out/wrap/build")).unwrap();
+        fs::create_dir_all(repo_root.join("keep/build")).unwrap();
+        fs::write(repo_root.join("keep/notes.txt"), "x").unwrap();
+        init_repo(&repo_root);
+        fs::write(repo_root.join(".gitignore"), "build/\n").unwrap();
+        let target = |path: &str| DeleteTarget {
+            repo_root: repo_root.clone(),
+            path: repo_root.join(path),
+            planned_bytes: 0,
+        };
+
+        let summary = execute_delete_with_progress(
+            &[target("cmake-out/wrap/build"), target("keep/build")],
+            DeleteOptions {
+                prune_empty_parents: true,
+                ..DeleteOptions::default()
+            },
+            || false,
+            |_| {},
+        );
+
+        assert_eq!(summary.deleted_paths, 2);
+        assert_eq!(
+            summary.pruned_parents,
+            vec![
+                repo_root.join("cmake-out/wrap"),
+                repo_root.join("cmake-out")
+            ]
+        );
+        assert!(!repo_root.join("cmake-out").exists());
+        assert!(repo_root.join("keep").exists());
+        assert!(repo_root.exists());
+        let roots = ScanRoots::from_canonical(vec![root.clone()]);
+        let lines = format_delete_summary(&roots, &summary, false, false, None);
+        assert!(lines.iter().any(|l| l == "pruned empty parents (2):"));
+        assert!(lines.iter().any(|l| l == "- repo/cmake-out"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn free_goal_deletes_largest_first_and_stops_once_met() {
+        let root = make_temp_dir("clean-my-code-free");
+        let repo_root = root.join("repo");
+        for (dir, size) in [("a/build", 10), ("b/build", 60), ("c/build", 30)] {
+            fs::create_dir_all(repo_root.join(dir)).unwrap();
+            fs::write(repo_root.join(dir).join("blob"), vec![0u8; size]).unwrap();
+        }
+        init_repo(&repo_root);
+        fs::write(repo_root.join(".gitignore"), "build/\n").unwrap();
+        let target = |path: &str, planned_bytes| DeleteTarget {
+            repo_root: repo_root.clone(),
+            path: repo_root.join(path),
+            planned_bytes,
+        };
+        let targets = [
+            target("a/build", 10),
+            target("b/build", 60),
+            target("c/build", 30),
+        ];
+
+        let mut handled = Vec::new();
+        let summary = execute_delete_with_progress(
+            &targets,
+            DeleteOptions {
+                free_goal: Some(80),
+                ..DeleteOptions::default()
+            },
+            || false,
+            |progress| handled.push(progress.target_index),
+        );
+        let left = ["a/build", "b/build", "c/build"].map(|dir| repo_root.join(dir).exists());
+        let _ = fs::remove_dir_all(root);
+
+        assert_eq!(handled, [1, 2]);
+        assert_eq!((summary.deleted_paths, summary.deleted_bytes), (2, 90));
+        assert!(summary.free_goal_reached);
+        assert_eq!(summary.untouched_paths, 1);
+        assert_eq!(left, [true, false, false]);
+    }
+
+    #[test]
+    fn resolve_delete_target_requires_an_ignored_dir_in_a_repo() {
+        let root = make_temp_dir("clean-my-code-resolve");
+        let repo_root = root.join("repo");
+        for dir in ["build", "src", "../loose", "nested", "vendor/lib"] {
+            fs::create_dir_all(repo_root.join(dir)).unwrap();
+        }
+        fs::write(repo_root.join("build/blob"), vec![0u8; 42]).unwrap();
+        init_repo(&repo_root);
+        fs::write(repo_root.join(".gitignore"), "build/\n").unwrap();
+        let options = ScanOptions::default();
+
+        let target = resolve_delete_target(&repo_root.join("build"), &options);
+        let tracked = resolve_delete_target(&repo_root.join("src"), &options);
+        let loose = resolve_delete_target(&root.join("loose"), &options);
+        let missing = resolve_delete_target(&repo_root.join("nope"), &options);
+        // Ignored by the outer repo, but a repo itself or holding one.
+        fs::write(repo_root.join(".gitignore"), "build/\nnested/\nvendor/\n").unwrap();
+        git(&repo_root.join("nested"), &["init", "-q"]);
+        git(&repo_root.join("vendor/lib"), &["init", "-q"]);
+        let nested = resolve_delete_target(&repo_root.join("nested"), &options);
+        let vendor = resolve_delete_target(&repo_root.join("vendor"), &options);
+        let canonical_root = fs::canonicalize(&repo_root).unwrap();
+        let _ = fs::remove_dir_all(root);
+
+        let target = target.unwrap();
+        assert_eq!(target.repo_root, canonical_root);
+        assert_eq!(target.path, canonical_root.join("build"));
+        assert_eq!(target.planned_bytes, 42);
+        assert!(tracked.is_err());
+        assert!(loose.is_err());
+        assert!(missing.is_err());
+        assert!(format!("{:#}", nested.unwrap_err()).contains("holding a git repo"));
+        assert!(format!("{:#}", vendor.unwrap_err()).contains("holding a git repo"));
+    }
+}
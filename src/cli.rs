@@ -1,11 +1,45 @@
-use std::{collections::HashSet, ffi::OsString, path::PathBuf, str::FromStr};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    io::{BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::SystemTime,
+};
 
 use anyhow::{Context, Result, anyhow};
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use regex::Regex;
 
-use crate::{report::collect_reports, report::print_scan_report, tui::TuiOptions};
+#[cfg(feature = "tui")]
+use crate::tui::TuiOptions;
+use crate::{
+    cache::ScanCache,
+    clean::{
+        DeleteOptions, DeleteProgress, DeleteSummary, DeleteTarget, execute_delete_with_progress,
+        format_delete_summary, group_targets_by_repo, plan_delete_targets, resolve_delete_target,
+    },
+    exclude::{ExcludeSet, read_exclude_file},
+    format::{ByteUnit, format_bytes_with},
+    git::{GitLimit, GitOptions},
+    json::{self, JsonObject},
+    mounts::{network_mounts_under, noatime_mounts_for},
+    report::{
+        AgeBasis, ArtifactOrder, collect_reports, estimate_candidates, failure_lines, is_stale,
+        is_within_file_range, is_within_size_range, print_scan_report, sort_artifacts,
+        sort_by_stale_ratio, walk_error_lines,
+    },
+    roots::ScanRoots,
+    scan::{ArtifactMatcher, DirLimit, NamePattern, ScanOptions},
+    script::{ScriptShell, delete_script},
+    signal::{Terminated, TerminationFlag},
+    snapshot::{Snapshot, print_diff},
+};
 
-const DEFAULT_ARTIFACT_DIR_NAMES: &[&str] = &[
+/// Directory names treated as artifact candidates unless `--no-default-artifacts`
+/// is given. Only dirs that Git reports as ignored are ever deleted.
+pub const DEFAULT_ARTIFACT_DIR_NAMES: &[&str] = &[
     // General build outputs.
     "target",
     "dist",
@@ -65,6 +99,14 @@ const DEFAULT_ARTIFACT_DIR_NAMES: &[&str] = &[
     "coverage",
 ];
 
+/// Directories the walkers may have open at once unless `--max-open-dirs`
+/// says otherwise; well under the common 1024 open-file soft limit.
+const DEFAULT_MAX_OPEN_DIRS: u64 = 256;
+
+/// How long a single git subprocess may run unless `--git-timeout` says
+/// otherwise.
+const DEFAULT_GIT_TIMEOUT_SECS: u64 = 30;
+
 #[derive(Parser, Debug)]
 #[command(name = "clean-my-code")]
 #[command(about = "Scan and clean gitignored build artifacts per Git repo.")]
@@ -79,32 +121,293 @@ pub struct Cli {
 #[derive(Args, Debug, Clone)]
 struct CommonArgs {
     #[arg(long, global = true, default_value = ".", value_name = "PATH")]
-    root: PathBuf,
+    root: Vec<PathBuf>,
 
     #[arg(long, global = true, value_name = "N")]
     threads: Option<usize>,
 
-    #[arg(long = "artifact", global = true, value_name = "NAME")]
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    git_concurrency: Option<u64>,
+
+    #[arg(long, global = true, value_name = "SECS", default_value_t = DEFAULT_GIT_TIMEOUT_SECS)]
+    git_timeout: u64,
+
+    #[arg(long, global = true, value_name = "N", default_value_t = DEFAULT_MAX_OPEN_DIRS, value_parser = clap::value_parser!(u64).range(1..))]
+    max_open_dirs: u64,
+
+    #[arg(long = "artifact", global = true, value_name = "NAME", value_parser = parse_artifact_name)]
     artifacts: Vec<String>,
 
+    #[arg(
+        long = "artifact-regex",
+        global = true,
+        value_name = "PATTERN",
+        allow_hyphen_values = true
+    )]
+    artifact_regexes: Vec<Regex>,
+
     #[arg(long, global = true)]
     no_default_artifacts: bool,
+
+    #[arg(long, global = true, value_name = "N")]
+    max_depth: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DEPTH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2"
+    )]
+    prune_outside_repos: Option<usize>,
+
+    #[arg(long = "exclude", global = true, value_name = "PATTERN")]
+    excludes: Vec<String>,
+
+    #[arg(long, global = true, value_name = "FILE")]
+    exclude_from: Vec<PathBuf>,
+
+    #[arg(long, global = true)]
+    include_network_fs: bool,
+
+    #[arg(long, short = 'x', global = true)]
+    one_file_system: bool,
+
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+
+    #[arg(long, global = true)]
+    root_is_repo: bool,
+
+    #[arg(long, global = true)]
+    no_heuristics: bool,
+
+    #[arg(long, global = true)]
+    strict_detection: bool,
+
+    #[arg(long, global = true, value_name = "N", default_value_t = 5)]
+    largest_files: usize,
+
+    #[arg(long, global = true, value_enum, value_name = "TIME", default_value_t = StaleBy::Modified)]
+    stale_by: StaleBy,
+
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    scan_timeout: Option<std::time::Duration>,
+
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    #[arg(long, global = true, conflicts_with = "no_cache")]
+    refresh: bool,
+
+    #[arg(long, global = true, value_name = "UNIT", value_parser = parse_display_unit)]
+    display_unit: Option<ByteUnit>,
 }
 
 #[derive(Subcommand, Debug, Clone)]
 enum Command {
-    Scan,
+    Scan(ScanArgs),
+
+    Clean(CleanArgs),
+
+    Delete(DeleteArgs),
 
+    Estimate,
+
+    ListArtifacts(ListArtifactsArgs),
+
+    #[cfg(feature = "tui")]
     Tui(TuiArgs),
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum StaleBy {
+    /// When artifact files were last written.
+    Modified,
+    /// When artifact files were last read or written; falls back to
+    /// `modified` on mounts that do not record access times.
+    Access,
+}
+
+#[derive(Args, Debug, Clone)]
+struct ListArtifactsArgs {
+    #[arg(long, value_enum, default_value_t = ListFormat::Text)]
+    format: ListFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum ListFormat {
+    /// One name per line on stdout, ready to pass back as `--artifact`
+    /// values; each regex goes to stderr as a `regex: PATTERN` line.
+    Text,
+    /// A single JSON object, `{"names":[...],"patterns":[...]}`: the
+    /// `--artifact` names and the `--artifact-regex` patterns.
+    Json,
+}
+
+#[derive(Args, Debug, Clone)]
+struct ScanArgs {
+    #[arg(long, default_value = "0B")]
+    min_size: ByteSize,
+
+    #[arg(long)]
+    max_size: Option<ByteSize>,
+
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    min_files: u64,
+
+    #[arg(long, value_name = "N")]
+    max_files: Option<u64>,
+
+    #[arg(long, value_enum, default_value_t = ReportSort::Age)]
+    sort: ReportSort,
+
+    #[arg(long, value_name = "FILE")]
+    snapshot: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE")]
+    diff: Option<PathBuf>,
+
+    #[arg(long, short = 'v')]
+    verbose: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportSort {
+    /// Oldest head commit first.
+    Age,
+    /// Largest total first.
+    Size,
+    /// Largest share of stale artifact bytes first.
+    StaleRatio,
+}
+
+impl ReportSort {
+    fn artifact_order(self, basis: AgeBasis) -> ArtifactOrder {
+        match self {
+            ReportSort::Age => ArtifactOrder::Age(basis),
+            ReportSort::Size | ReportSort::StaleRatio => ArtifactOrder::Size,
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+struct CleanArgs {
+    #[arg(long, default_value = "1MiB")]
+    min_size: ByteSize,
+
+    #[arg(long)]
+    max_size: Option<ByteSize>,
+
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    min_files: u64,
+
+    #[arg(long, value_name = "N")]
+    max_files: Option<u64>,
+
+    #[arg(long)]
+    all: bool,
+
+    #[arg(long)]
+    dry_run: bool,
+
+    #[arg(long, short = 'y')]
+    yes: bool,
+
+    #[arg(long)]
+    progress_json: bool,
+
+    #[arg(long)]
+    parallel_delete: bool,
+
+    #[arg(long, value_name = "FILE", requires = "dry_run")]
+    emit_script: Option<PathBuf>,
+
+    #[arg(long)]
+    require_clean_worktree: bool,
+
+    #[arg(long)]
+    prune_empty_parents: bool,
+
+    #[arg(long, value_name = "SIZE")]
+    free: Option<ByteSize>,
+
+    #[arg(long, short = 'v')]
+    verbose: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct DeleteArgs {
+    #[arg(value_name = "PATH", required = true)]
+    paths: Vec<PathBuf>,
+
+    #[arg(long)]
+    dry_run: bool,
+
+    #[arg(long, short = 'y')]
+    yes: bool,
+
+    #[arg(long)]
+    require_clean_worktree: bool,
+
+    #[arg(long)]
+    prune_empty_parents: bool,
+}
+
+#[cfg(feature = "tui")]
 #[derive(Args, Debug, Clone)]
 struct TuiArgs {
     #[arg(long, default_value = "1MiB")]
     min_size: ByteSize,
 
+    #[arg(long)]
+    max_size: Option<ByteSize>,
+
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    min_files: u64,
+
+    #[arg(long, value_name = "N")]
+    max_files: Option<u64>,
+
     #[arg(long)]
     dry_run: bool,
+
+    #[arg(long, default_value_t = 250, value_name = "MS", value_parser = clap::value_parser!(u64).range(50..))]
+    idle_refresh_ms: u64,
+
+    #[arg(long, value_name = "DIR")]
+    report_dir: Option<PathBuf>,
+
+    #[arg(long)]
+    parallel_delete: bool,
+
+    #[arg(long)]
+    clean_all: bool,
+
+    #[arg(long, short = 'y', requires = "clean_all")]
+    yes: bool,
+
+    #[arg(long)]
+    require_clean_worktree: bool,
+
+    #[arg(long)]
+    prune_empty_parents: bool,
+
+    #[arg(long, value_name = "SECS")]
+    auto_exit_after: Option<u64>,
+
+    #[arg(long)]
+    include_empty_repos: bool,
+
+    #[arg(long, value_enum, value_name = "KEY")]
+    group_by: Option<GroupBy>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum GroupBy {
+    /// The top-level dir under the scan root.
+    Parent,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -170,65 +473,932 @@ pub fn run() -> Result<()> {
 }
 
 fn run_with_cli(cli: Cli) -> Result<()> {
-    let scan_root = std::fs::canonicalize(&cli.common.root)
-        .with_context(|| format!("invalid root: {:?}", cli.common.root))?;
-
-    let mut artifact_dir_names: HashSet<OsString> = HashSet::new();
-    if !cli.common.no_default_artifacts {
-        artifact_dir_names.extend(
-            DEFAULT_ARTIFACT_DIR_NAMES
-                .iter()
-                .copied()
-                .map(OsString::from),
-        );
+    let names = effective_artifact_names(&cli.common);
+    if names.is_empty() && cli.common.artifact_regexes.is_empty() {
+        anyhow::bail!(empty_artifact_set_message());
     }
-    artifact_dir_names.extend(cli.common.artifacts.into_iter().map(OsString::from));
-
-    if artifact_dir_names.is_empty() {
-        anyhow::bail!("no artifact directory names configured");
+    // Needs neither the roots nor a scan, so it works from anywhere.
+    if let Some(Command::ListArtifacts(args)) = &cli.command {
+        let patterns = artifact_patterns(&cli.common);
+        let list = format_artifact_list(&names, &patterns, args.format);
+        if !list.is_empty() {
+            println!("{list}");
+        }
+        if args.format == ListFormat::Text {
+            // Kept off stdout, which only holds valid `--artifact` values.
+            for pattern in &patterns {
+                eprintln!("regex: {pattern}");
+            }
+        }
+        return Ok(());
     }
 
+    let scan_roots = ScanRoots::canonicalize(&cli.common.root)?;
+    let display_unit = cli.common.display_unit;
+    let stale_by_access = cli.common.stale_by == StaleBy::Access && {
+        let noatime = noatime_mounts_for(&scan_roots);
+        for mount in &noatime {
+            eprintln!(
+                "warn: {} is mounted noatime; judging staleness by modification time",
+                mount.display()
+            );
+        }
+        noatime.is_empty()
+    };
+    let age_basis = if stale_by_access {
+        AgeBasis::Access
+    } else {
+        AgeBasis::Modified
+    };
+    let mut scan_options = build_scan_options(&cli.common, &scan_roots, names)?;
+
+    #[cfg(not(feature = "tui"))]
+    let Some(command) = cli.command else {
+        anyhow::bail!(
+            "this build has no interactive mode (the `tui` feature is disabled); run `scan`, `clean` or `estimate` instead"
+        );
+    };
+    #[cfg(feature = "tui")]
     let command = cli.command.unwrap_or_else(|| {
         Command::Tui(TuiArgs {
             min_size: ByteSize::from_str("1MiB").unwrap_or(ByteSize(1024 * 1024)),
+            max_size: None,
+            min_files: 0,
+            max_files: None,
             dry_run: false,
+            idle_refresh_ms: 250,
+            report_dir: None,
+            parallel_delete: false,
+            clean_all: false,
+            yes: false,
+            require_clean_worktree: false,
+            prune_empty_parents: false,
+            auto_exit_after: None,
+            include_empty_repos: false,
+            group_by: None,
         })
     });
 
-    match command {
-        Command::Scan => {
-            let run_scan = || -> Result<()> {
-                let reports = collect_reports(&scan_root, &artifact_dir_names);
-                print_scan_report(&scan_root, &reports);
-                Ok(())
-            };
+    // Only the views that show them pay for tracking the largest files.
+    scan_options.largest_files = match &command {
+        Command::Scan(args) if args.verbose => cli.common.largest_files,
+        #[cfg(feature = "tui")]
+        Command::Tui(_) => cli.common.largest_files,
+        _ => 0,
+    };
+    scan_options.verbose = match &command {
+        Command::Scan(args) => args.verbose,
+        Command::Clean(args) => args.verbose,
+        _ => false,
+    };
+    // Access times are never cached: reads move them without changing the
+    // fingerprint.
+    if !cli.common.no_cache && !stale_by_access {
+        scan_options.cache = Some(Arc::new(ScanCache::load(&scan_options, cli.common.refresh)));
+    }
+    let cache = scan_options.cache.clone();
+    // The TUI starts the clock anew for every scan it runs.
+    scan_options.deadline = cli
+        .common
+        .scan_timeout
+        .map(|timeout| std::time::Instant::now() + timeout);
 
-            match cli.common.threads {
-                Some(threads) => {
-                    let pool = rayon::ThreadPoolBuilder::new()
-                        .num_threads(threads)
-                        .build()
-                        .context("failed to build rayon thread pool")?;
-                    pool.install(run_scan)
+    let result = match command {
+        Command::Scan(args) => {
+            let min_size_bytes = args.min_size.as_u64();
+            let max_size_bytes = args.max_size.map(ByteSize::as_u64);
+            // Read the old snapshot first: it may be the file we overwrite.
+            let previous = args.diff.as_deref().map(Snapshot::load).transpose()?;
+            with_thread_pool(cli.common.threads, || {
+                let mut outcome = collect_reports(&scan_roots, &scan_options);
+                outcome.reports.retain(|report| {
+                    is_within_size_range(report, min_size_bytes, max_size_bytes)
+                        && is_within_file_range(report, args.min_files, args.max_files)
+                });
+                let now = SystemTime::now();
+                match args.sort {
+                    ReportSort::Age => {}
+                    ReportSort::Size => outcome.reports.sort_by(|a, b| {
+                        b.total_size_bytes
+                            .cmp(&a.total_size_bytes)
+                            .then_with(|| a.repo_root.cmp(&b.repo_root))
+                    }),
+                    ReportSort::StaleRatio => {
+                        sort_by_stale_ratio(&mut outcome.reports, now, age_basis)
+                    }
                 }
-                None => run_scan(),
-            }
+                for report in &mut outcome.reports {
+                    sort_artifacts(&mut report.artifacts, args.sort.artifact_order(age_basis));
+                }
+                print_scan_report(
+                    &scan_roots,
+                    &outcome,
+                    &scan_options,
+                    now,
+                    age_basis,
+                    display_unit,
+                );
+                let current = Snapshot::from_reports(&outcome.reports, now);
+                if let Some(previous) = &previous {
+                    print_diff(&scan_roots, previous, &current, display_unit);
+                }
+                if let Some(path) = &args.snapshot {
+                    current.save(path)?;
+                    println!("Snapshot saved to {}", path.display());
+                }
+                Ok(())
+            })
         }
+        Command::Clean(args) => {
+            warn_skipped_mounts(&scan_options);
+            with_thread_pool(cli.common.threads, || {
+                run_clean(
+                    &scan_roots,
+                    &scan_options,
+                    &args,
+                    age_basis,
+                    display_unit,
+                    &mut confirm_on_terminal,
+                )
+            })
+        }
+        Command::Delete(args) => with_thread_pool(cli.common.threads, || {
+            run_delete(&scan_roots, &scan_options, &args, display_unit)
+        }),
+        Command::ListArtifacts(_) => Ok(()),
+        Command::Estimate => with_thread_pool(cli.common.threads, || {
+            warn_skipped_mounts(&scan_options);
+            let estimate = estimate_candidates(&scan_roots, &scan_options);
+            for line in failure_lines(&scan_roots, &estimate.failures) {
+                eprintln!("{line}");
+            }
+            println!("Scan root: {scan_roots}");
+            println!(
+                "Upper bound: {} in {} candidate dirs (not checked against .gitignore; run `scan` for the exact figure)",
+                format_bytes_with(estimate.size_bytes, display_unit),
+                estimate.dirs
+            );
+            Ok(())
+        }),
+        #[cfg(feature = "tui")]
         Command::Tui(args) => crate::tui::run(
-            &scan_root,
-            artifact_dir_names,
+            &scan_roots,
+            scan_options,
             cli.common.threads,
             TuiOptions {
                 min_size_bytes: args.min_size.as_u64(),
+                max_size_bytes: args.max_size.map(ByteSize::as_u64),
+                min_files: args.min_files,
+                max_files: args.max_files,
                 dry_run: args.dry_run,
+                idle_poll: std::time::Duration::from_millis(args.idle_refresh_ms),
+                report_dir: args.report_dir,
+                parallel_delete: args.parallel_delete,
+                clean_all: args.clean_all,
+                yes: args.yes,
+                require_clean_worktree: args.require_clean_worktree,
+                prune_empty_parents: args.prune_empty_parents,
+                auto_exit_after: args.auto_exit_after.map(std::time::Duration::from_secs),
+                include_empty_repos: args.include_empty_repos,
+                group_by_parent: args.group_by == Some(GroupBy::Parent),
+                scan_timeout: cli.common.scan_timeout,
+                age_basis,
+                display_unit,
             },
         ),
+    };
+
+    if let Some(cache) = cache
+        && let Err(err) = cache.save()
+    {
+        eprintln!("warn: failed to save scan cache: {err:#}");
+    }
+    result
+}
+
+fn warn_skipped_mounts(scan_options: &ScanOptions) {
+    for mount in &scan_options.skip_mounts {
+        eprintln!(
+            "note: skipping network filesystem {} (use --include-network-fs to scan it)",
+            mount.display()
+        );
+    }
+}
+
+/// A duration like `90`, `90s`, `15m` or `2h`; bare numbers are seconds.
+fn parse_duration(value: &str) -> Result<std::time::Duration, String> {
+    let value = value.trim();
+    let (number, unit_secs) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 60 * 60),
+        _ => (value, 1),
+    };
+    let number = number
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("invalid duration {value:?}; expected e.g. 90s, 15m or 2h"))?;
+    number
+        .checked_mul(unit_secs)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(|| format!("duration {value:?} is too long"))
+}
+
+fn parse_display_unit(value: &str) -> Result<ByteUnit, String> {
+    ByteUnit::parse(value)
+        .ok_or_else(|| format!("unknown unit {value:?}; expected one of B, KiB, MiB, GiB, TiB"))
+}
+
+/// Trims an `--artifact` value, including outer slashes of a trailing path
+/// like `.build/checkouts`; an empty name would silently match nothing.
+fn parse_artifact_name(value: &str) -> Result<String, String> {
+    let name = value.trim().trim_matches('/');
+    if name.is_empty() {
+        return Err("artifact name must not be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// The scan settings the common flags ask for; per-command tweaks such as
+/// `largest_files`, `verbose` and the cache are applied by the caller.
+fn build_scan_options(
+    common: &CommonArgs,
+    scan_roots: &ScanRoots,
+    names: Vec<String>,
+) -> Result<ScanOptions> {
+    let git_concurrency = match common.git_concurrency {
+        Some(limit) => usize::try_from(limit).unwrap_or(usize::MAX),
+        None => common.threads.unwrap_or_else(rayon::current_num_threads),
+    };
+
+    let artifact_dir_names = names
+        .into_iter()
+        .map(OsString::from)
+        .collect::<HashSet<_>>();
+
+    let mut exclude_patterns = common.excludes.clone();
+    for path in &common.exclude_from {
+        exclude_patterns.extend(read_exclude_file(path)?);
+    }
+
+    Ok(ScanOptions {
+        artifacts: ArtifactMatcher {
+            patterns: common
+                .artifact_regexes
+                .iter()
+                .cloned()
+                .map(NamePattern::Regex)
+                .collect(),
+            ..ArtifactMatcher::new(artifact_dir_names)
+        },
+        max_depth: common.max_depth,
+        prune_outside_repos: common.prune_outside_repos,
+        excludes: ExcludeSet::new(&exclude_patterns)?,
+        same_device: common.one_file_system,
+        skip_mounts: if common.include_network_fs {
+            Vec::new()
+        } else {
+            network_mounts_under(scan_roots)
+        },
+        skip_repos: Vec::new(),
+        follow_symlinks: common.follow_symlinks,
+        cache: None,
+        cancel: None,
+        repo_ceilings: if common.root_is_repo {
+            scan_roots.iter().map(Path::to_path_buf).collect()
+        } else {
+            Vec::new()
+        },
+        trust_risky_names: common.no_heuristics,
+        strict_detection: common.strict_detection,
+        largest_files: 0,
+        deadline: None,
+        open_dirs: Arc::new(DirLimit::new(
+            usize::try_from(common.max_open_dirs).unwrap_or(usize::MAX),
+        )),
+        git: GitOptions {
+            limit: Arc::new(GitLimit::new(git_concurrency)),
+            timeout: (common.git_timeout > 0)
+                .then(|| std::time::Duration::from_secs(common.git_timeout)),
+        },
+        verbose: false,
+    })
+}
+
+/// The artifact dir names a scan with these args looks for: the defaults
+/// unless `--no-default-artifacts`, then each `--artifact`, without
+/// duplicates.
+fn effective_artifact_names(common: &CommonArgs) -> Vec<String> {
+    let defaults = if common.no_default_artifacts {
+        &[][..]
+    } else {
+        DEFAULT_ARTIFACT_DIR_NAMES
+    };
+    let mut names: Vec<String> = Vec::new();
+    for name in defaults
+        .iter()
+        .copied()
+        .chain(common.artifacts.iter().map(String::as_str))
+    {
+        if !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+fn artifact_patterns(common: &CommonArgs) -> Vec<String> {
+    common
+        .artifact_regexes
+        .iter()
+        .map(|regex| regex.as_str().to_string())
+        .collect()
+}
+
+/// Names one per line, then each `--artifact-regex` as `regex: PATTERN`.
+fn format_artifact_list(names: &[String], patterns: &[String], format: ListFormat) -> String {
+    match format {
+        ListFormat::Text => names.join("\n"),
+        ListFormat::Json => JsonObject::new()
+            .raw(
+                "names",
+                &json::array(names.iter().map(|name| json::string(name))),
+            )
+            .raw(
+                "patterns",
+                &json::array(patterns.iter().map(|pattern| json::string(pattern))),
+            )
+            .finish(),
+    }
+}
+
+fn empty_artifact_set_message() -> String {
+    format!(
+        "no artifact directory names configured: --no-default-artifacts was given without \
+         --artifact NAME or --artifact-regex PATTERN\n\
+         add e.g. `--artifact target`, or drop --no-default-artifacts to use the defaults: {}",
+        DEFAULT_ARTIFACT_DIR_NAMES.join(", ")
+    )
+}
+
+fn with_thread_pool<F>(threads: Option<usize>, run: F) -> Result<()>
+where
+    F: FnOnce() -> Result<()> + Send,
+{
+    match threads {
+        Some(threads) => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .context("failed to build rayon thread pool")?;
+            pool.install(run)
+        }
+        None => run(),
+    }
+}
+
+/// Non-interactive clean: deletes the artifacts of stale repos (or of every
+/// repo with `--all`) within the size range. Unless `--yes` or
+/// `--dry-run`, `confirm` is asked before anything is deleted.
+fn run_clean(
+    scan_roots: &ScanRoots,
+    scan_options: &ScanOptions,
+    args: &CleanArgs,
+    age_basis: AgeBasis,
+    unit: Option<ByteUnit>,
+    confirm: &mut dyn FnMut(&str) -> Result<bool>,
+) -> Result<()> {
+    let now = SystemTime::now();
+    let min_size_bytes = args.min_size.as_u64();
+    let max_size_bytes = args.max_size.map(ByteSize::as_u64);
+
+    let outcome = collect_reports(scan_roots, scan_options);
+    for line in walk_error_lines(
+        scan_roots,
+        &outcome.walk_errors,
+        outcome.counters.unreadable,
+    ) {
+        eprintln!("{line}");
+    }
+    for line in failure_lines(scan_roots, &outcome.failures) {
+        eprintln!("{line}");
+    }
+    if outcome.counters.timed_out {
+        eprintln!("warn: the scan timed out; only the artifacts it found are planned");
+    }
+    let reports = outcome.reports;
+    let mut targets = plan_delete_targets(reports.iter().map(|report| {
+        let is_selected = is_within_size_range(report, min_size_bytes, max_size_bytes)
+            && is_within_file_range(report, args.min_files, args.max_files)
+            && (args.all || is_stale(report, now, age_basis));
+        (report, is_selected)
+    }));
+    // Nothing is picked by hand here, so unverified artifacts never go.
+    let unverified = reports
+        .iter()
+        .flat_map(|report| report.artifacts.iter())
+        .filter(|artifact| artifact.unverified)
+        .map(|artifact| artifact.path.as_path())
+        .collect::<HashSet<_>>();
+    let planned = targets.len();
+    targets.retain(|target| !unverified.contains(target.path.as_path()));
+    let left_unverified = planned - targets.len();
+
+    // Keep stdout machine-readable when streaming progress.
+    let mut out: Box<dyn Write> = if args.progress_json {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    };
+
+    if left_unverified > 0 {
+        writeln!(
+            out,
+            "Left out {left_unverified} unverified artifact dirs (no ecosystem marker next to them)"
+        )?;
+    }
+    if targets.is_empty() {
+        writeln!(out, "Nothing to delete.")?;
+        if args.progress_json {
+            println!(
+                "{}",
+                summary_json(&DeleteSummary::default(), args.dry_run, false)
+            );
+        }
+        return Ok(());
+    }
+
+    let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
+    writeln!(
+        out,
+        "Plan: delete {} artifact dirs, reclaim {}{}",
+        targets.len(),
+        format_bytes_with(planned_bytes, unit),
+        if args.dry_run { " (dry run)" } else { "" }
+    )?;
+    for group in group_targets_by_repo(&targets) {
+        writeln!(out, "  {}", group.header_line(scan_roots, unit))?;
+        for line in group.target_lines(unit) {
+            writeln!(out, "  {line}")?;
+        }
+    }
+    if let Some(goal) = args.free {
+        writeln!(
+            out,
+            "Stopping once {} is freed, largest dirs first",
+            format_bytes_with(goal.as_u64(), unit)
+        )?;
+    }
+    out.flush()?;
+
+    if !args.dry_run && !args.yes && !confirm("Delete these directories?")? {
+        writeln!(out, "Aborted.")?;
+        return Ok(());
+    }
+
+    let termination = TerminationFlag::register()?;
+    let mut last_processed = 0usize;
+    let mut cleared = Vec::new();
+    let summary = execute_delete_with_progress(
+        &targets,
+        DeleteOptions {
+            dry_run: args.dry_run,
+            parallel: args.parallel_delete,
+            require_clean_worktree: args.require_clean_worktree,
+            prune_empty_parents: args.prune_empty_parents,
+            free_goal: args.free.map(ByteSize::as_u64),
+            scan: scan_options.clone(),
+        },
+        || termination.signal().is_some(),
+        |progress| {
+            last_processed = progress.processed;
+            if progress.target_cleared {
+                cleared.push(progress.target_index);
+            }
+            if args.progress_json {
+                let current = &targets[progress.target_index];
+                println!("{}", progress_json(&progress, current));
+            }
+        },
+    );
+
+    if let Some(path) = &args.emit_script {
+        // Keep the plan order even if parallel deletion finished out of order.
+        cleared.sort_unstable();
+        let cleared = cleared.iter().map(|&i| &targets[i]).collect::<Vec<_>>();
+        std::fs::write(path, delete_script(ScriptShell::native(), &cleared))
+            .with_context(|| format!("failed to write script to {}", path.display()))?;
+        writeln!(out, "Wrote delete script: {}", path.display())?;
+    }
+
+    let signal = termination.signal();
+    let canceled = signal.is_some() && last_processed < targets.len();
+    if args.progress_json {
+        println!("{}", summary_json(&summary, args.dry_run, canceled));
+    } else {
+        writeln!(out)?;
+        for line in format_delete_summary(scan_roots, &summary, args.dry_run, canceled, unit) {
+            writeln!(out, "{line}")?;
+        }
+    }
+
+    match signal {
+        Some(signal) => Err(Terminated { signal }.into()),
+        None => Ok(()),
+    }
+}
+
+/// Deletes the artifact dirs named on the command line, without a scan.
+/// Every path is checked up front, so one bad path deletes nothing.
+fn run_delete(
+    scan_roots: &ScanRoots,
+    scan_options: &ScanOptions,
+    args: &DeleteArgs,
+    unit: Option<ByteUnit>,
+) -> Result<()> {
+    let mut targets = args
+        .paths
+        .iter()
+        .map(|path| resolve_delete_target(path, scan_options))
+        .collect::<Result<Vec<_>>>()?;
+    targets.sort_by(|a, b| a.path.cmp(&b.path));
+    targets.dedup_by(|a, b| a.path == b.path);
+
+    let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
+    println!(
+        "Plan: delete {} artifact dirs, reclaim {}{}",
+        targets.len(),
+        format_bytes_with(planned_bytes, unit),
+        if args.dry_run { " (dry run)" } else { "" }
+    );
+    for group in group_targets_by_repo(&targets) {
+        println!("  {}", group.header_line(scan_roots, unit));
+        for line in group.target_lines(unit) {
+            println!("  {line}");
+        }
+    }
+
+    if !args.dry_run && !args.yes && !confirm_on_terminal("Delete these directories?")? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    let termination = TerminationFlag::register()?;
+    let mut last_processed = 0usize;
+    let summary = execute_delete_with_progress(
+        &targets,
+        DeleteOptions {
+            dry_run: args.dry_run,
+            require_clean_worktree: args.require_clean_worktree,
+            prune_empty_parents: args.prune_empty_parents,
+            scan: scan_options.clone(),
+            ..DeleteOptions::default()
+        },
+        || termination.signal().is_some(),
+        |progress| last_processed = progress.processed,
+    );
+
+    let signal = termination.signal();
+    let canceled = signal.is_some() && last_processed < targets.len();
+    println!();
+    for line in format_delete_summary(scan_roots, &summary, args.dry_run, canceled, unit) {
+        println!("{line}");
+    }
+    match signal {
+        Some(signal) => Err(Terminated { signal }.into()),
+        None => Ok(()),
+    }
+}
+
+fn confirm_on_terminal(prompt: &str) -> Result<bool> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        anyhow::bail!("refusing to delete without --yes when stdin is not a terminal");
+    }
+
+    eprint!("{prompt} [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn progress_json(progress: &DeleteProgress, current: &DeleteTarget) -> String {
+    JsonObject::new()
+        .str("event", "progress")
+        .num("processed", progress.processed as u64)
+        .num("total", progress.total as u64)
+        .num("deleted_paths", progress.deleted_paths as u64)
+        .num("deleted_bytes", progress.deleted_bytes)
+        .num("skipped_paths", progress.skipped_paths as u64)
+        .num("error_count", progress.error_count as u64)
+        .path("repo_root", &current.repo_root)
+        .path("path", &current.path)
+        .finish()
+}
+
+pub(crate) fn summary_json(summary: &DeleteSummary, dry_run: bool, canceled: bool) -> String {
+    let errors = json::array(summary.errors.iter().map(|(path, err)| {
+        JsonObject::new()
+            .path("path", path)
+            .str("error", &format!("{err:#}"))
+            .finish()
+    }));
+
+    let skipped = json::array(summary.skipped.iter().map(|(path, reason)| {
+        JsonObject::new()
+            .path("path", path)
+            .str("reason", reason.as_str())
+            .finish()
+    }));
+
+    let pruned_parents = json::array(
+        summary
+            .pruned_parents
+            .iter()
+            .map(|path| json::string(&path.to_string_lossy())),
+    );
+    // Index-aligned with `pruned_parents`; only present when some path
+    // isn't UTF-8.
+    let pruned_parents_raw = summary
+        .pruned_parents
+        .iter()
+        .any(|path| path.to_str().is_none())
+        .then(|| {
+            json::array(summary.pruned_parents.iter().map(|path| {
+                json::path_raw(path)
+                    .map(|raw| json::string(&raw))
+                    .unwrap_or_else(|| "null".to_string())
+            }))
+        });
+
+    let object = JsonObject::new()
+        .str("event", "summary")
+        .bool("dry_run", dry_run)
+        .bool("canceled", canceled)
+        .num("planned_paths", summary.planned_paths as u64)
+        .num("planned_bytes", summary.planned_bytes)
+        .num("deleted_paths", summary.deleted_paths as u64)
+        .num("deleted_bytes", summary.deleted_bytes)
+        .num("deleted_planned_bytes", summary.deleted_planned_bytes)
+        .num("skipped_paths", summary.skipped_paths as u64)
+        .raw("skipped", &skipped)
+        .raw("errors", &errors)
+        .raw("pruned_parents", &pruned_parents)
+        .bool("free_goal_reached", summary.free_goal_reached)
+        .num("untouched_paths", summary.untouched_paths as u64);
+    match pruned_parents_raw {
+        Some(raw) => object.raw("pruned_parents_raw", &raw).finish(),
+        None => object.finish(),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::DEFAULT_ARTIFACT_DIR_NAMES;
+    use super::{
+        Cli, Command, DEFAULT_ARTIFACT_DIR_NAMES, ListFormat, artifact_patterns,
+        build_scan_options, effective_artifact_names, format_artifact_list, parse_duration,
+        progress_json, run_clean, run_with_cli, summary_json,
+    };
+    use crate::{
+        clean::{DeleteProgress, DeleteSummary, DeleteTarget, SkipReason},
+        report::AgeBasis,
+        roots::ScanRoots,
+        scan::ScanOptions,
+        test_util::{init_repo, make_temp_dir},
+    };
+    use clap::Parser;
+    use std::{collections::HashSet, ffi::OsString, fs, path::PathBuf, time::Duration};
+
+    #[test]
+    fn list_artifacts_prints_the_effective_names() {
+        let cli =
+            Cli::try_parse_from(["clean-my-code", "--artifact", "generated", "list-artifacts"])
+                .unwrap();
+        let names = effective_artifact_names(&cli.common);
+        assert_eq!(names.len(), DEFAULT_ARTIFACT_DIR_NAMES.len() + 1);
+        assert_eq!(names.last().map(String::as_str), Some("generated"));
+
+        let cli = Cli::try_parse_from([
+            "clean-my-code",
+            "--no-default-artifacts",
+            "--artifact",
+            "target",
+            "--artifact",
+            "we\"ird",
+            "--artifact",
+            "target",
+            "list-artifacts",
+            "--format",
+            "json",
+        ])
+        .unwrap();
+        let names = effective_artifact_names(&cli.common);
+        assert_eq!(
+            format_artifact_list(&names, &[], ListFormat::Text),
+            "target\nwe\"ird"
+        );
+        assert_eq!(
+            format_artifact_list(&names, &[], ListFormat::Json),
+            r#"{"names":["target","we\"ird"],"patterns":[]}"#
+        );
+    }
+
+    #[test]
+    fn list_artifacts_prints_the_regex_patterns_and_refuses_an_empty_set() {
+        let cli = Cli::try_parse_from([
+            "clean-my-code",
+            "--no-default-artifacts",
+            "--artifact-regex",
+            "^cmake-build-",
+            "list-artifacts",
+        ])
+        .unwrap();
+        let names = effective_artifact_names(&cli.common);
+        let patterns = artifact_patterns(&cli.common);
+        // Text output is names only; the patterns go to stderr.
+        assert_eq!(
+            format_artifact_list(&names, &patterns, ListFormat::Text),
+            ""
+        );
+        assert_eq!(
+            format_artifact_list(&names, &patterns, ListFormat::Json),
+            r#"{"names":[],"patterns":["^cmake-build-"]}"#
+        );
+
+        let cli =
+            Cli::try_parse_from(["clean-my-code", "--no-default-artifacts", "list-artifacts"])
+                .unwrap();
+        let err = run_with_cli(cli).unwrap_err().to_string();
+        assert!(
+            err.contains("no artifact directory names configured"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn artifact_names_are_trimmed_and_must_not_be_empty() {
+        let cli = Cli::try_parse_from(["clean-my-code", "--artifact", " target ", "scan"]).unwrap();
+        assert_eq!(cli.common.artifacts, ["target"]);
+
+        for empty in ["", "   "] {
+            let err = Cli::try_parse_from(["clean-my-code", "--artifact", empty, "scan"])
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("artifact name must not be empty"), "{err}");
+        }
+    }
+
+    #[test]
+    fn prune_depth_needs_an_equals_sign() {
+        // A bare flag must not swallow the subcommand or a path after it.
+        let cli = Cli::try_parse_from(["clean-my-code", "--prune-outside-repos", "scan"]).unwrap();
+        assert_eq!(cli.common.prune_outside_repos, Some(2));
+        assert!(matches!(cli.command, Some(Command::Scan(_))));
+
+        let cli =
+            Cli::try_parse_from(["clean-my-code", "scan", "--prune-outside-repos=4"]).unwrap();
+        assert_eq!(cli.common.prune_outside_repos, Some(4));
+
+        assert!(
+            Cli::try_parse_from(["clean-my-code", "scan", "--prune-outside-repos", "4"]).is_err()
+        );
+    }
+
+    #[test]
+    fn one_file_system_sets_same_device() {
+        let root = make_temp_dir("clean-my-code-cli");
+        let scan_roots = ScanRoots::canonicalize(std::slice::from_ref(&root)).unwrap();
+        let same_device = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).unwrap();
+            build_scan_options(&cli.common, &scan_roots, Vec::new())
+                .unwrap()
+                .same_device
+        };
+        assert!(!same_device(&["clean-my-code", "scan"]));
+        assert!(same_device(&["clean-my-code", "-x", "scan"]));
+        assert!(same_device(&["clean-my-code", "scan", "-x"]));
+        assert!(same_device(&[
+            "clean-my-code",
+            "clean",
+            "--one-file-system"
+        ]));
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn durations_take_an_optional_unit() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("60s"), Ok(Duration::from_secs(60)));
+        assert_eq!(parse_duration("15m"), Ok(Duration::from_secs(15 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("-5s").is_err());
+    }
+
+    #[test]
+    fn progress_and_summary_events_keep_their_json_shape() {
+        let target = DeleteTarget {
+            repo_root: PathBuf::from("/ws/a"),
+            path: PathBuf::from("/ws/a/target"),
+            planned_bytes: 10,
+        };
+        let progress = DeleteProgress {
+            processed: 1,
+            total: 2,
+            target_index: 0,
+            target_cleared: true,
+            deleted_paths: 1,
+            deleted_bytes: 10,
+            skipped_paths: 0,
+            error_count: 0,
+        };
+        assert_eq!(
+            progress_json(&progress, &target),
+            concat!(
+                r#"{"event":"progress","processed":1,"total":2,"deleted_paths":1,"#,
+                r#""deleted_bytes":10,"skipped_paths":0,"error_count":0,"#,
+                r#""repo_root":"/ws/a","path":"/ws/a/target"}"#
+            )
+        );
+
+        let summary = DeleteSummary {
+            planned_paths: 2,
+            planned_bytes: 30,
+            deleted_paths: 1,
+            deleted_bytes: 10,
+            deleted_planned_bytes: 10,
+            kept_paths: 0,
+            kept_planned_bytes: 0,
+            skipped_paths: 1,
+            skipped: vec![(PathBuf::from("/ws/b/dist"), SkipReason::NotIgnored)],
+            errors: Vec::new(),
+            pruned_parents: vec![PathBuf::from("/ws/a/out")],
+            free_goal_reached: false,
+            untouched_paths: 0,
+        };
+        assert_eq!(
+            summary_json(&summary, true, false),
+            concat!(
+                r#"{"event":"summary","dry_run":true,"canceled":false,"#,
+                r#""planned_paths":2,"planned_bytes":30,"deleted_paths":1,"#,
+                r#""deleted_bytes":10,"deleted_planned_bytes":10,"skipped_paths":1,"#,
+                r#""skipped":[{"path":"/ws/b/dist","reason":"not-ignored"}],"errors":[],"#,
+                r#""pruned_parents":["/ws/a/out"],"free_goal_reached":false,"untouched_paths":0}"#
+            )
+        );
+    }
+
+    #[test]
+    fn clean_deletes_only_after_confirmation_and_never_in_a_dry_run() {
+        let root = make_temp_dir("clean-my-code-cli");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("target/debug")).unwrap();
+        fs::write(repo.join("target/debug/blob"), vec![0u8; 10]).unwrap();
+        fs::write(repo.join("Cargo.toml"), "").unwrap();
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+        init_repo(&repo);
+
+        let scan_roots = ScanRoots::canonicalize(std::slice::from_ref(&root)).unwrap();
+        let scan_options = ScanOptions::new(HashSet::from([OsString::from("target")]));
+        let clean = |extra: &[&str], answer: bool| {
+            let mut argv = vec!["clean-my-code", "clean", "--all", "--min-size", "0"];
+            argv.extend_from_slice(extra);
+            let Some(Command::Clean(args)) = Cli::try_parse_from(argv).unwrap().command else {
+                unreachable!();
+            };
+            let mut asked = 0;
+            let result = run_clean(
+                &scan_roots,
+                &scan_options,
+                &args,
+                AgeBasis::Modified,
+                None,
+                &mut |_| {
+                    asked += 1;
+                    Ok(answer)
+                },
+            );
+            (result, asked)
+        };
+
+        let (result, asked) = clean(&["--dry-run"], false);
+        assert!(result.is_ok());
+        assert_eq!(asked, 0);
+        assert!(repo.join("target").exists());
+
+        let (result, asked) = clean(&[], false);
+        assert!(result.is_ok());
+        assert_eq!(asked, 1);
+        assert!(repo.join("target").exists());
+
+        let (result, asked) = clean(&[], true);
+        assert!(result.is_ok());
+        assert_eq!(asked, 1);
+        let deleted = !repo.join("target").exists();
+        let _ = fs::remove_dir_all(&root);
+        assert!(deleted);
+    }
 
     #[test]
     fn default_artifacts_exclude_stateful_or_user_managed_dirs() {
@@ -0,0 +1,169 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::roots::expand_path;
+
+/// Glob patterns for paths the scan never enters or reports.
+///
+/// A pattern without a `/` matches a directory name at any depth
+/// (`vendor`, `keep-*`). A relative pattern with a `/` matches the trailing
+/// components of a path (`clients/*/legacy`), and an absolute one (after
+/// `~` expansion) the whole path. `*` and `?` stay within one component,
+/// `**` spans any number of them, and `[...]` is a character class.
+#[derive(Debug, Default, Clone)]
+pub struct ExcludeSet {
+    patterns: Vec<Regex>,
+}
+
+impl ExcludeSet {
+    pub fn new<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| compile(pattern.as_ref()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The compiled patterns, one per line, to tell apart results sized
+    /// under different sets.
+    pub(crate) fn key(&self) -> String {
+        self.patterns
+            .iter()
+            .map(Regex::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let text = path.to_string_lossy();
+        let text = if std::path::MAIN_SEPARATOR == '/' {
+            text
+        } else {
+            text.replace(std::path::MAIN_SEPARATOR, "/").into()
+        };
+        self.patterns.iter().any(|re| re.is_match(&text))
+    }
+}
+
+/// Reads an exclude file: one pattern per line, blank lines and lines
+/// starting with `#` ignored, like git's `--exclude-from`.
+pub fn read_exclude_file(path: &Path) -> Result<Vec<String>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read exclude file: {path:?}"))?;
+    Ok(parse_exclude_lines(&text))
+}
+
+fn parse_exclude_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+fn compile(pattern: &str) -> Result<Regex> {
+    let expanded = expand_path(Path::new(pattern))?;
+    let expanded = expanded.to_string_lossy();
+    let trimmed = expanded.trim_end_matches('/');
+    let trimmed = if trimmed.is_empty() { "/" } else { trimmed };
+    let anchor = if trimmed.starts_with('/') {
+        "^"
+    } else {
+        "(?:^|/)"
+    };
+    let source = format!("{anchor}{}$", glob_to_regex(trimmed));
+    Regex::new(&source).with_context(|| format!("invalid exclude pattern: {pattern:?}"))
+}
+
+/// Translates a glob to regex source; `*` and `?` never match a `/`.
+pub(crate) fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:.*/)?");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '[' => {
+                let mut raw = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' && !raw.is_empty() {
+                        closed = true;
+                        break;
+                    }
+                    raw.push(c);
+                }
+                if closed {
+                    let (negate, body) = match raw.strip_prefix(['!', '^']) {
+                        Some(body) => ("^", body),
+                        None => ("", raw.as_str()),
+                    };
+                    out.push('[');
+                    out.push_str(negate);
+                    for c in body.chars() {
+                        if matches!(c, '\\' | '[' | ']' | '&' | '~') {
+                            out.push('\\');
+                        }
+                        out.push(c);
+                    }
+                    out.push(']');
+                } else {
+                    // An unterminated `[` is taken literally.
+                    out.push_str("\\[");
+                    out.push_str(&regex::escape(&raw));
+                }
+            }
+            c => out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_names_trailing_components_and_absolute_paths() {
+        let set =
+            ExcludeSet::new(["keep-*", "clients/*/legacy", "/srv/data/**", "v[0-9]"]).unwrap();
+
+        assert!(set.is_excluded(Path::new("/ws/keep-me")));
+        assert!(!set.is_excluded(Path::new("/ws/keeper")));
+        assert!(set.is_excluded(Path::new("/ws/clients/acme/legacy")));
+        assert!(!set.is_excluded(Path::new("/ws/clients/acme/web/legacy")));
+        assert!(set.is_excluded(Path::new("/srv/data/a/b")));
+        assert!(!set.is_excluded(Path::new("/home/srv/data/a")));
+        assert!(set.is_excluded(Path::new("/ws/v1")));
+        assert!(!set.is_excluded(Path::new("/ws/v10")));
+        assert!(!ExcludeSet::default().is_excluded(Path::new("/ws")));
+    }
+
+    #[test]
+    fn exclude_files_skip_comments_and_blank_lines() {
+        let lines = parse_exclude_lines("# shared list\n\n  vendor  \nclients/*/legacy\n");
+        assert_eq!(lines, ["vendor", "clients/*/legacy"]);
+    }
+}
@@ -1,10 +1,88 @@
+use std::fmt::Write as _;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "tui")]
+use unicode_width::UnicodeWidthChar;
 
 pub fn display_rel_path(base: &Path, path: &Path) -> String {
     match path.strip_prefix(base) {
         Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
-        Ok(rel) => rel.display().to_string(),
-        Err(_) => path.display().to_string(),
+        Ok(rel) => display_path(rel),
+        Err(_) => display_path(path),
+    }
+}
+
+/// Renders `path` for human output. Bytes that aren't UTF-8 show as `\xNN`
+/// and control characters are escaped, so an odd name can neither hide
+/// behind U+FFFD nor break a line or the terminal. Not meant to round-trip;
+/// JSON output carries the raw bytes separately.
+pub fn display_path(path: &Path) -> String {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let mut out = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c.is_control() {
+                out.extend(c.escape_default());
+            } else {
+                out.push(c);
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(out, "\\x{byte:02x}");
+        }
+    }
+    out
+}
+
+/// Displays `path` with a leading `home` replaced by `~`.
+#[cfg(feature = "tui")]
+pub fn abbreviate_home(home: Option<&Path>, path: &Path) -> String {
+    match home.map(|home| path.strip_prefix(home)) {
+        Some(Ok(rel)) if rel.as_os_str().is_empty() => "~".to_string(),
+        Some(Ok(rel)) => format!("~{}{}", std::path::MAIN_SEPARATOR, display_path(rel)),
+        _ => display_path(path),
+    }
+}
+
+/// A binary unit sizes can be pinned to with `--display-unit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteUnit {
+    B,
+    KiB,
+    MiB,
+    GiB,
+    TiB,
+}
+
+impl ByteUnit {
+    const ALL: [ByteUnit; 5] = [
+        ByteUnit::B,
+        ByteUnit::KiB,
+        ByteUnit::MiB,
+        ByteUnit::GiB,
+        ByteUnit::TiB,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ByteUnit::B => "B",
+            ByteUnit::KiB => "KiB",
+            ByteUnit::MiB => "MiB",
+            ByteUnit::GiB => "GiB",
+            ByteUnit::TiB => "TiB",
+        }
+    }
+
+    /// Parses a unit name, ignoring case (`gib`, `GiB`).
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|unit| unit.name().eq_ignore_ascii_case(name))
+    }
+
+    fn bytes(self) -> u64 {
+        1024u64.pow(self as u32)
     }
 }
 
@@ -24,3 +102,221 @@ pub fn format_bytes(bytes: u64) -> String {
 
     format!("{size:.1} {}", UNITS[unit_index])
 }
+
+/// Formats `bytes` in `unit` when one is pinned with `--display-unit`, so
+/// sizes across repos and totals can be compared at a glance; otherwise
+/// like `format_bytes`.
+pub fn format_bytes_with(bytes: u64, unit: Option<ByteUnit>) -> String {
+    match unit {
+        Some(unit) => format_bytes_in(bytes, unit),
+        None => format_bytes(bytes),
+    }
+}
+
+/// Formats `bytes` in `unit` regardless of magnitude, e.g. `0.2 GiB`.
+pub fn format_bytes_in(bytes: u64, unit: ByteUnit) -> String {
+    if unit == ByteUnit::B {
+        return format!("{bytes} B");
+    }
+    let size = bytes as f64 / unit.bytes() as f64;
+    format!("{size:.1} {}", unit.name())
+}
+
+/// Formats a `0.0..=1.0` share as a whole percentage, e.g. `73%`.
+pub fn format_percent(ratio: f64) -> String {
+    format!("{:.0}%", ratio * 100.0)
+}
+
+/// Formats `time` as a `YYYY-MM-DD` date in the local timezone.
+pub fn format_local_date(time: SystemTime) -> String {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    let (y, m, d) = civil_from_days((secs + local_utc_offset(secs)).div_euclid(86_400));
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// Seconds east of UTC in effect at unix time `secs`, from the platforms
+/// whose `struct tm` carries `tm_gmtoff`.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+))]
+fn local_utc_offset(secs: i64) -> i64 {
+    // Out of range for a 32-bit `time_t`: fall back to UTC.
+    let Some(t) = libc::time_t::try_from(secs).ok() else {
+        return 0;
+    };
+    // SAFETY: `localtime_r` only reads `t` and writes into the zeroed `tm`
+    // we own; a null return leaves us with the UTC fallback.
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&t, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+/// Elsewhere times are shown in UTC.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+)))]
+fn local_utc_offset(_secs: i64) -> i64 {
+    0
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian `(year, month,
+/// day)` (Howard Hinnant's `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+/// Shortens `s` to at most `width` terminal columns by replacing its middle
+/// with `…`, so both the start and the (usually more telling) end of a path
+/// stay visible.
+#[cfg(feature = "tui")]
+pub fn truncate_middle(s: &str, width: usize) -> String {
+    let char_width = |c: char| c.width().unwrap_or(0);
+    if s.chars().map(char_width).sum::<usize>() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    // One column goes to the ellipsis; the tail gets the larger half.
+    let budget = width - 1;
+    let head_budget = budget / 2;
+    let tail_budget = budget - head_budget;
+
+    let mut head = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = char_width(c);
+        if used + w > head_budget {
+            break;
+        }
+        used += w;
+        head.push(c);
+    }
+
+    let mut tail = Vec::new();
+    let mut used = 0;
+    for c in s.chars().rev() {
+        let w = char_width(c);
+        if used + w > tail_budget {
+            break;
+        }
+        used += w;
+        tail.push(c);
+    }
+
+    head.push('…');
+    head.extend(tail.into_iter().rev());
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_bytes_in_a_pinned_unit() {
+        assert_eq!(format_bytes_in(200 * 1024 * 1024, ByteUnit::GiB), "0.2 GiB");
+        assert_eq!(format_bytes_in(3 << 30, ByteUnit::MiB), "3072.0 MiB");
+        assert_eq!(format_bytes_in(1536, ByteUnit::B), "1536 B");
+        assert_eq!(ByteUnit::parse("gib"), Some(ByteUnit::GiB));
+        assert_eq!(ByteUnit::parse("GB"), None);
+        assert_eq!(format_bytes_with(1536, Some(ByteUnit::B)), "1536 B");
+        assert_eq!(format_bytes_with(1536, None), "1.5 KiB");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escapes_odd_bytes_in_displayed_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let latin1 = Path::new(OsStr::from_bytes(b"/ws/caf\xe9/target"));
+        assert_eq!(display_path(latin1), "/ws/caf\\xe9/target");
+        assert_eq!(
+            display_rel_path(Path::new("/ws"), latin1),
+            "caf\\xe9/target"
+        );
+        assert_eq!(display_path(Path::new("a\nb\u{1b}[2J")), "a\\nb\\u{1b}[2J");
+        assert_eq!(display_path(Path::new("/ws/café")), "/ws/café");
+    }
+
+    #[test]
+    fn converts_days_to_civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn abbreviates_home() {
+        let home = Path::new("/home/me");
+        assert_eq!(abbreviate_home(Some(home), Path::new("/home/me")), "~");
+        assert_eq!(
+            abbreviate_home(Some(home), Path::new("/home/me/work/app")),
+            format!("~{}work/app", std::path::MAIN_SEPARATOR)
+        );
+        assert_eq!(abbreviate_home(Some(home), Path::new("/opt/x")), "/opt/x");
+        assert_eq!(abbreviate_home(None, Path::new("/home/me")), "/home/me");
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn truncate_middle_keeps_both_ends() {
+        assert_eq!(truncate_middle("short", 10), "short");
+        assert_eq!(
+            truncate_middle("work/clients/acme/webapp", 15),
+            "work/cl…/webapp"
+        );
+        assert_eq!(truncate_middle("abcdef", 1), "…");
+        assert_eq!(truncate_middle("abcdef", 0), "");
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn truncate_middle_respects_display_width_of_multibyte_chars() {
+        use unicode_width::UnicodeWidthStr;
+
+        // Each CJK char is two columns wide; a half-fitting char is dropped
+        // instead of being split.
+        let truncated = truncate_middle("项目/客户/前端应用", 9);
+        assert!(truncated.width() <= 9, "{truncated:?}");
+        assert_eq!(truncated, "项目…应用");
+
+        let truncated = truncate_middle("café/naïve/résumé", 8);
+        assert_eq!(truncated.width(), 8);
+        assert_eq!(truncated, "caf…sumé");
+    }
+}
@@ -1,9 +1,15 @@
 use std::{
+    io::Read,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Output, Stdio},
+    sync::{
+        Arc, Condvar, Mutex,
+        mpsc::{self, RecvTimeoutError},
+    },
+    time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 
 #[derive(Debug, Clone)]
 pub struct GitHead {
@@ -12,6 +18,124 @@ pub struct GitHead {
     pub iso8601: String,
 }
 
+/// How `git` subprocesses are run. Clones share one `limit`.
+#[derive(Debug, Clone, Default)]
+pub struct GitOptions {
+    pub limit: Arc<GitLimit>,
+    /// Kills any `git` subprocess still running after this long, so a hung
+    /// mount fails that one lookup instead of freezing the scan. `None`
+    /// waits forever.
+    pub timeout: Option<Duration>,
+}
+
+/// Caps how many `git` subprocesses run at once across all threads, so a tree
+/// with thousands of repos doesn't fork thousands of processes at the same
+/// time.
+#[derive(Debug, Default)]
+pub struct GitLimit {
+    state: Mutex<GitLimitState>,
+    available: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct GitLimitState {
+    /// `0` means unlimited.
+    limit: usize,
+    running: usize,
+}
+
+impl GitLimit {
+    /// At most `limit` subprocesses at once; `0` means unlimited.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(GitLimitState { limit, running: 0 }),
+            available: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> GitPermit<'_> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while state.limit > 0 && state.running >= state.limit {
+            state = self
+                .available
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        state.running += 1;
+        GitPermit(self)
+    }
+}
+
+/// Longest pause between checks on a running `git` while its timeout has
+/// not passed.
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Runs `command` to completion with stdin and stderr discarded and stdout
+/// captured, killing it once `git.timeout` passes.
+fn run_git(command: Command, what: &str, repo_root: &Path, git: &GitOptions) -> Result<Output> {
+    run_with_timeout(command, git.timeout)
+        .with_context(|| format!("failed to run {what} in {repo_root:?}"))
+}
+
+fn run_with_timeout(mut command: Command, timeout: Option<Duration>) -> Result<Output> {
+    command.stdin(Stdio::null()).stderr(Stdio::null());
+    let Some(timeout) = timeout else {
+        return Ok(command.output()?);
+    };
+
+    let mut child = command.stdout(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take();
+    // Drained on the side so a chatty command can't fill the pipe and stall
+    // while we wait for it to exit. Not scoped, and waited on only until the
+    // deadline: a process the command spawned (a credential helper, say) may
+    // keep the pipe open long after the command itself is gone.
+    let (done, drained) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut stdout) = stdout {
+            let _ = stdout.read_to_end(&mut buf);
+        }
+        let _ = done.send(buf);
+    });
+
+    let deadline = Instant::now() + timeout;
+    let mut pause = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {timeout:?}");
+        }
+        std::thread::sleep(pause.min(deadline - now));
+        pause = (pause * 2).min(MAX_POLL_INTERVAL);
+    };
+    let stdout = match drained.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(stdout) => stdout,
+        Err(RecvTimeoutError::Timeout) => bail!("timed out after {timeout:?}"),
+        Err(RecvTimeoutError::Disconnected) => Vec::new(),
+    };
+    Ok(Output {
+        status,
+        stdout,
+        stderr: Vec::new(),
+    })
+}
+
+/// Held while a `git` subprocess runs; releases its slot on drop.
+struct GitPermit<'a>(&'a GitLimit);
+
+impl Drop for GitPermit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.running -= 1;
+        self.0.available.notify_one();
+    }
+}
+
 pub fn find_git_root(start: &Path) -> Option<PathBuf> {
     let mut current = Some(start);
     while let Some(dir) = current {
@@ -23,18 +147,34 @@ pub fn find_git_root(start: &Path) -> Option<PathBuf> {
     None
 }
 
-pub fn is_git_ignored(repo_root: &Path, path: &Path) -> Result<bool> {
+/// Like `find_git_root`, but never looks above `ceiling`. If nothing at or
+/// below it has a `.git` yet `ceiling` lies inside a repo (a monorepo
+/// subdir), `ceiling` itself stands in for the repo root; git commands run
+/// there work on the enclosing repo.
+pub fn find_git_root_within(start: &Path, ceiling: &Path) -> Option<PathBuf> {
+    let mut current = Some(start);
+    while let Some(dir) = current.filter(|dir| dir.starts_with(ceiling)) {
+        if has_dot_git(dir) {
+            return Some(dir.to_path_buf());
+        }
+        current = dir.parent();
+    }
+    find_git_root(ceiling).map(|_| ceiling.to_path_buf())
+}
+
+pub fn is_git_ignored(repo_root: &Path, path: &Path, git: &GitOptions) -> Result<bool> {
     let rel = path.strip_prefix(repo_root).with_context(|| {
         format!("path is not under repo root: repo={repo_root:?}, path={path:?}")
     })?;
 
-    let status = Command::new("git")
+    let _permit = git.limit.acquire();
+    let mut command = Command::new("git");
+    command
         .arg("-C")
         .arg(repo_root)
         .args(["check-ignore", "--quiet", "--"])
-        .arg(rel)
-        .status()
-        .with_context(|| format!("failed to run git check-ignore in {repo_root:?}"))?;
+        .arg(rel);
+    let status = run_git(command, "git check-ignore", repo_root, git)?.status;
 
     match status.code() {
         Some(0) => Ok(true),
@@ -44,13 +184,15 @@ pub fn is_git_ignored(repo_root: &Path, path: &Path) -> Result<bool> {
     }
 }
 
-pub fn git_head(repo_root: &Path) -> Result<Option<GitHead>> {
-    let output = Command::new("git")
+pub fn git_head(repo_root: &Path, git: &GitOptions) -> Result<Option<GitHead>> {
+    let permit = git.limit.acquire();
+    let mut command = Command::new("git");
+    command
         .arg("-C")
         .arg(repo_root)
-        .args(["log", "-1", "--format=%H%n%ct%n%cI"])
-        .output()
-        .with_context(|| format!("failed to run git log in {repo_root:?}"))?;
+        .args(["log", "-1", "--format=%H%n%ct%n%cI"]);
+    let output = run_git(command, "git log", repo_root, git)?;
+    drop(permit);
 
     if !output.status.success() {
         return Ok(None);
@@ -79,6 +221,157 @@ pub fn git_head(repo_root: &Path) -> Result<Option<GitHead>> {
     }))
 }
 
+/// Whether `git status` reports any uncommitted (staged, unstaged or
+/// untracked) changes. Ignored files, i.e. the artifacts, do not count.
+pub fn has_uncommitted_changes(repo_root: &Path, git: &GitOptions) -> Result<bool> {
+    let _permit = git.limit.acquire();
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_root)
+        .args(["status", "--porcelain"]);
+    let output = run_git(command, "git status", repo_root, git)?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git status failed with exit code {:?}",
+            output.status.code()
+        ));
+    }
+    Ok(!output.stdout.is_empty())
+}
+
+/// Returns `remote.origin.url`, or `None` when the repo has no origin remote.
+#[cfg(feature = "tui")]
+pub fn git_remote_url(repo_root: &Path, git: &GitOptions) -> Result<Option<String>> {
+    let _permit = git.limit.acquire();
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_root)
+        .args(["config", "--get", "remote.origin.url"]);
+    let output = run_git(command, "git config", repo_root, git)?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!url.is_empty()).then_some(url))
+}
+
 fn has_dot_git(dir: &Path) -> bool {
     std::fs::metadata(dir.join(".git")).is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{init_repo, make_temp_dir};
+    use std::fs;
+
+    #[test]
+    fn git_root_lookup_stops_at_the_ceiling() {
+        let root = make_temp_dir("clean-my-code-git");
+        let app = root.join("mono/apps/web");
+        fs::create_dir_all(root.join("mono/.git")).unwrap();
+        fs::create_dir_all(app.join("target")).unwrap();
+        fs::create_dir_all(app.join("vendor/lib/.git")).unwrap();
+        fs::create_dir_all(root.join("plain/target")).unwrap();
+
+        let target = app.join("target");
+        assert_eq!(find_git_root(&target), Some(root.join("mono")));
+        assert_eq!(find_git_root_within(&target, &app), Some(app.clone()));
+        assert_eq!(
+            find_git_root_within(&app.join("vendor/lib/out"), &app),
+            Some(app.join("vendor/lib"))
+        );
+        assert_eq!(
+            find_git_root_within(&target, &root.join("mono")),
+            Some(root.join("mono"))
+        );
+        // Outside any repo, so there is no repo to stand in for.
+        let plain = root.join("plain");
+        assert_eq!(find_git_root_within(&plain.join("target"), &plain), None);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_ignore_gets_the_exact_bytes_of_non_utf8_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let repo = make_temp_dir("clean-my-code-git");
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), b"/out-caf\xe9/\n").unwrap();
+        let ignored = repo.join(OsStr::from_bytes(b"out-caf\xe9"));
+        // Same name once lossily decoded, but a different dir on disk.
+        let lookalike = repo.join(OsStr::from_bytes(b"out-caf\xff"));
+        fs::create_dir_all(&ignored).unwrap();
+        fs::create_dir_all(&lookalike).unwrap();
+
+        let results = (
+            is_git_ignored(&repo, &ignored, &GitOptions::default()).unwrap(),
+            is_git_ignored(&repo, &lookalike, &GitOptions::default()).unwrap(),
+        );
+        let _ = fs::remove_dir_all(repo);
+
+        assert_eq!(results, (true, false));
+    }
+
+    #[test]
+    fn git_permits_respect_the_concurrency_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let limit = GitLimit::new(2);
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _permit = limit.acquire();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_past_the_timeout_are_killed() {
+        let started = Instant::now();
+        let mut sleep = Command::new("sleep");
+        sleep.arg("10");
+        let err = run_with_timeout(sleep, Some(Duration::from_millis(100))).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // A leftover grandchild holding stdout open must not hold us up.
+        let started = Instant::now();
+        let mut sh = Command::new("sh");
+        sh.args(["-c", "sleep 5 & exec sleep 10"]);
+        let err = run_with_timeout(sh, Some(Duration::from_millis(100))).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        // Same once the command itself exits in time but leaves one behind.
+        let started = Instant::now();
+        let mut sh = Command::new("sh");
+        sh.args(["-c", "sleep 5 & echo started"]);
+        let err = run_with_timeout(sh, Some(Duration::from_millis(100))).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        let mut echo = Command::new("echo");
+        echo.arg("done");
+        let output = run_with_timeout(echo, Some(Duration::from_secs(5))).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"done\n");
+    }
+}
@@ -0,0 +1,251 @@
+use std::{ffi::OsStr, fs, path::Path};
+
+/// Artifact names that are also common names for real source dirs (Go's
+/// `bin`, embedded projects' `build`, a Java `out` package), so a gitignore
+/// rule alone is not enough: a candidate with one of these names is only
+/// reported when something next to or inside it shows a build system made
+/// it.
+pub(crate) const RISKY_NAMES: &[MarkerRule] = &[
+    MarkerRule {
+        name: "build",
+        evidence: &[
+            Evidence::Inside("CMakeCache.txt"),
+            Evidence::Inside("build.ninja"),
+            Evidence::Sibling("CMakeLists.txt"),
+            Evidence::Sibling("meson.build"),
+            Evidence::Sibling("build.gradle"),
+            Evidence::Sibling("build.gradle.kts"),
+            Evidence::Sibling("settings.gradle"),
+            Evidence::Sibling("settings.gradle.kts"),
+            Evidence::Sibling("package.json"),
+            Evidence::Sibling("pyproject.toml"),
+            Evidence::Sibling("setup.py"),
+            Evidence::Sibling("pubspec.yaml"),
+            Evidence::Sibling("build.zig"),
+        ],
+    },
+    MarkerRule {
+        name: "out",
+        evidence: &[
+            Evidence::Inside("production"),
+            Evidence::Sibling(".idea"),
+            Evidence::SiblingExtension("iml"),
+            Evidence::Sibling("build.gradle"),
+            Evidence::Sibling("build.gradle.kts"),
+            Evidence::Sibling("package.json"),
+        ],
+    },
+    MarkerRule {
+        name: "obj",
+        evidence: &[
+            Evidence::Inside("project.assets.json"),
+            Evidence::SiblingExtension("csproj"),
+            Evidence::SiblingExtension("fsproj"),
+            Evidence::SiblingExtension("vbproj"),
+            Evidence::SiblingExtension("vcxproj"),
+            Evidence::SiblingExtension("sln"),
+        ],
+    },
+    MarkerRule {
+        name: "bin",
+        evidence: &[
+            Evidence::SiblingExtension("csproj"),
+            Evidence::SiblingExtension("fsproj"),
+            Evidence::SiblingExtension("vbproj"),
+            Evidence::SiblingExtension("sln"),
+        ],
+    },
+];
+
+/// Markers of the ecosystem that produces each well-known artifact name.
+/// A candidate without its marker is reported as unverified and is never
+/// picked for cleaning by a policy, only by hand.
+pub(crate) const ECOSYSTEM_MARKERS: &[MarkerRule] = &[
+    MarkerRule {
+        name: "target",
+        evidence: &[
+            Evidence::Sibling("Cargo.toml"),
+            Evidence::Sibling("pom.xml"),
+            Evidence::Sibling("build.sbt"),
+        ],
+    },
+    MarkerRule {
+        name: "node_modules",
+        evidence: &[Evidence::Sibling("package.json")],
+    },
+    MarkerRule {
+        name: ".venv",
+        evidence: &[Evidence::Inside("pyvenv.cfg")],
+    },
+    MarkerRule {
+        name: "venv",
+        evidence: &[Evidence::Inside("pyvenv.cfg")],
+    },
+    MarkerRule {
+        name: "_build",
+        evidence: &[
+            Evidence::Sibling("mix.exs"),
+            Evidence::Sibling("rebar.config"),
+            Evidence::Sibling("dune-project"),
+        ],
+    },
+    MarkerRule {
+        name: "dist-newstyle",
+        evidence: &[
+            Evidence::Sibling("cabal.project"),
+            Evidence::SiblingExtension("cabal"),
+        ],
+    },
+    MarkerRule {
+        name: ".stack-work",
+        evidence: &[Evidence::Sibling("stack.yaml")],
+    },
+    MarkerRule {
+        name: ".dart_tool",
+        evidence: &[Evidence::Sibling("pubspec.yaml")],
+    },
+    MarkerRule {
+        name: "Pods",
+        evidence: &[Evidence::Sibling("Podfile")],
+    },
+];
+
+#[derive(Debug)]
+pub(crate) struct MarkerRule {
+    pub(crate) name: &'static str,
+    /// Any one of these confirms the dir as a build output.
+    pub(crate) evidence: &'static [Evidence],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Evidence {
+    /// An entry with this name in the candidate's parent.
+    Sibling(&'static str),
+    /// A file in the candidate's parent with this extension.
+    SiblingExtension(&'static str),
+    /// An entry with this name directly in the candidate.
+    Inside(&'static str),
+}
+
+impl MarkerRule {
+    /// Whether `dir`, a candidate with this name, shows any of the
+    /// evidence.
+    pub(crate) fn is_confirmed(&self, dir: &Path) -> bool {
+        let parent = dir.parent();
+        self.evidence.iter().any(|evidence| match *evidence {
+            Evidence::Sibling(name) => parent.is_some_and(|p| exists(&p.join(name))),
+            Evidence::SiblingExtension(ext) => parent.is_some_and(|p| has_extension(p, ext)),
+            Evidence::Inside(name) => exists(&dir.join(name)),
+        })
+    }
+}
+
+/// The rule among `rules` that `dir` falls under by its name.
+fn rule_for(rules: &'static [MarkerRule], dir: &Path) -> Option<&'static MarkerRule> {
+    let name = dir.file_name()?;
+    rules.iter().find(|rule| name == OsStr::new(rule.name))
+}
+
+/// Whether `dir` has a risky name and nothing around it shows a build
+/// system made it.
+pub(crate) fn is_unconfirmed(dir: &Path) -> bool {
+    rule_for(RISKY_NAMES, dir).is_some_and(|rule| !rule.is_confirmed(dir))
+}
+
+/// Whether `dir` has a well-known artifact name but lacks the marker of
+/// the ecosystem behind it, e.g. a `target` without a `Cargo.toml`.
+pub(crate) fn is_unverified(dir: &Path) -> bool {
+    rule_for(ECOSYSTEM_MARKERS, dir).is_some_and(|rule| !rule.is_confirmed(dir))
+}
+
+fn exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+fn has_extension(dir: &Path, ext: &str) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        Path::new(&entry.file_name())
+            .extension()
+            .is_some_and(|e| e == OsStr::new(ext))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::make_temp_dir;
+
+    #[test]
+    fn risky_names_need_a_build_system_marker() {
+        let root = make_temp_dir("clean-my-code-heuristics");
+        let dir = |path: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(&path).unwrap();
+            path
+        };
+
+        let go_bin = dir("go/bin");
+        let cmake_build = dir("cmake/build");
+        fs::write(cmake_build.join("CMakeCache.txt"), "").unwrap();
+        let gradle_build = dir("gradle/build");
+        fs::write(root.join("gradle/build.gradle.kts"), "").unwrap();
+        let plain_build = dir("plain/build");
+        let dotnet_obj = dir("dotnet/obj");
+        let dotnet_bin = dir("dotnet/bin");
+        fs::write(root.join("dotnet/App.csproj"), "").unwrap();
+        let idea_out = dir("idea/out");
+        fs::write(root.join("idea/app.iml"), "").unwrap();
+        let java_out = dir("java/src/com/example/out");
+        let target = dir("plain/target");
+
+        assert!(is_unconfirmed(&go_bin));
+        assert!(is_unconfirmed(&plain_build));
+        assert!(is_unconfirmed(&java_out));
+        assert!(!is_unconfirmed(&cmake_build));
+        assert!(!is_unconfirmed(&gradle_build));
+        assert!(!is_unconfirmed(&dotnet_obj));
+        assert!(!is_unconfirmed(&dotnet_bin));
+        assert!(!is_unconfirmed(&idea_out));
+        assert!(rule_for(RISKY_NAMES, &target).is_none());
+        assert!(!is_unconfirmed(&target));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn ecosystem_markers_verify_well_known_names() {
+        let root = make_temp_dir("clean-my-code-ecosystems");
+        let dir = |path: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(&path).unwrap();
+            path
+        };
+
+        let cargo_target = dir("rust/target");
+        fs::write(root.join("rust/Cargo.toml"), "").unwrap();
+        let stray_target = dir("docs/target");
+        let node_modules = dir("web/node_modules");
+        fs::write(root.join("web/package.json"), "{}").unwrap();
+        let orphan_modules = dir("old/node_modules");
+        let venv = dir("py/.venv");
+        fs::write(venv.join("pyvenv.cfg"), "").unwrap();
+        let not_a_venv = dir("notes/.venv");
+        let mix_build = dir("ex/_build");
+        fs::write(root.join("ex/mix.exs"), "").unwrap();
+        let dist = dir("web/dist");
+
+        assert!(!is_unverified(&cargo_target));
+        assert!(is_unverified(&stray_target));
+        assert!(!is_unverified(&node_modules));
+        assert!(is_unverified(&orphan_modules));
+        assert!(!is_unverified(&venv));
+        assert!(is_unverified(&not_a_venv));
+        assert!(!is_unverified(&mix_build));
+        assert!(!is_unverified(&dist));
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,396 @@
+use std::{fmt::Write as _, path::Path};
+
+use anyhow::{Result, anyhow, bail};
+
+/// Minimal builder for single-line JSON objects, used for machine-readable
+/// output without pulling in a serialization framework.
+#[derive(Debug, Default)]
+pub struct JsonObject {
+    buf: String,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn str(mut self, key: &str, value: &str) -> Self {
+        self.key(key);
+        write_escaped(&mut self.buf, value);
+        self
+    }
+
+    /// Writes `path` under `key` as a (lossy) string. A path that isn't
+    /// valid UTF-8 also gets `<key>_raw`, its exact bytes percent-encoded,
+    /// so consumers can still address it.
+    pub fn path(self, key: &str, path: &Path) -> Self {
+        let this = self.str(key, &path.to_string_lossy());
+        match path_raw(path) {
+            Some(raw) => this.str(&format!("{key}_raw"), &raw),
+            None => this,
+        }
+    }
+
+    pub fn num(mut self, key: &str, value: u64) -> Self {
+        self.key(key);
+        let _ = write!(self.buf, "{value}");
+        self
+    }
+
+    pub fn bool(mut self, key: &str, value: bool) -> Self {
+        self.key(key);
+        self.buf.push_str(if value { "true" } else { "false" });
+        self
+    }
+
+    /// Inserts `value` verbatim; it must already be valid JSON.
+    pub fn raw(mut self, key: &str, value: &str) -> Self {
+        self.key(key);
+        self.buf.push_str(value);
+        self
+    }
+
+    pub fn finish(self) -> String {
+        format!("{{{}}}", self.buf)
+    }
+
+    fn key(&mut self, key: &str) {
+        if !self.buf.is_empty() {
+            self.buf.push(',');
+        }
+        write_escaped(&mut self.buf, key);
+        self.buf.push(':');
+    }
+}
+
+/// Serializes `value` as a JSON string.
+pub fn string(value: &str) -> String {
+    let mut buf = String::new();
+    write_escaped(&mut buf, value);
+    buf
+}
+
+/// Percent-encodes the bytes of `path` when it isn't valid UTF-8: `%`,
+/// controls and every non-ASCII byte become `%XX`. `None` for UTF-8 paths,
+/// whose string form is already exact.
+pub fn path_raw(path: &Path) -> Option<String> {
+    if path.to_str().is_some() {
+        return None;
+    }
+    let mut raw = String::new();
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        if byte == b'%' || !byte.is_ascii() || byte.is_ascii_control() {
+            let _ = write!(raw, "%{byte:02X}");
+        } else {
+            raw.push(byte as char);
+        }
+    }
+    Some(raw)
+}
+
+/// Joins already-serialized JSON values into an array.
+pub fn array<I>(values: I) -> String
+where
+    I: IntoIterator<Item = String>,
+{
+    let values = values.into_iter().collect::<Vec<_>>();
+    format!("[{}]", values.join(","))
+}
+
+/// A parsed JSON document. Numbers keep their source text so large integers
+/// round-trip without going through `f64`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a complete JSON document, the counterpart of [`JsonObject`] for
+/// reading our own files back.
+pub fn parse(text: &str) -> Result<JsonValue> {
+    let mut parser = Parser {
+        chars: text.char_indices().peekable(),
+    };
+    let value = parser.value()?;
+    parser.skip_ws();
+    if let Some((at, _)) = parser.chars.peek() {
+        bail!("trailing characters at byte {at}");
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| c.is_ascii_whitespace())
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, want: char) -> Result<()> {
+        match self.chars.next() {
+            Some((_, c)) if c == want => Ok(()),
+            Some((at, c)) => bail!("expected {want:?} at byte {at}, found {c:?}"),
+            None => bail!("expected {want:?}, found end of input"),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: JsonValue) -> Result<JsonValue> {
+        for want in word.chars() {
+            self.expect(want)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<JsonValue> {
+        self.skip_ws();
+        let Some(&(at, c)) = self.chars.peek() else {
+            bail!("unexpected end of input");
+        };
+        match c {
+            '{' => self.object(),
+            '[' => self.array(),
+            '"' => Ok(JsonValue::String(self.string()?)),
+            't' => self.literal("true", JsonValue::Bool(true)),
+            'f' => self.literal("false", JsonValue::Bool(false)),
+            'n' => self.literal("null", JsonValue::Null),
+            '-' | '0'..='9' => Ok(self.number()),
+            c => bail!("unexpected {c:?} at byte {at}"),
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_ws();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(JsonValue::Object(fields));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.skip_ws();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_ws();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, '}')) => return Ok(JsonValue::Object(fields)),
+                Some((at, c)) => bail!("expected ',' or '}}' at byte {at}, found {c:?}"),
+                None => bail!("unterminated object"),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_ws();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(JsonValue::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_ws();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, ']')) => return Ok(JsonValue::Array(values)),
+                Some((at, c)) => bail!("expected ',' or ']' at byte {at}, found {c:?}"),
+                None => bail!("unterminated array"),
+            }
+        }
+    }
+
+    fn number(&mut self) -> JsonValue {
+        let mut text = String::new();
+        while let Some((_, c)) = self
+            .chars
+            .next_if(|(_, c)| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            text.push(c);
+        }
+        JsonValue::Number(text)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(out),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => out.push('"'),
+                    Some((_, '\\')) => out.push('\\'),
+                    Some((_, '/')) => out.push('/'),
+                    Some((_, 'b')) => out.push('\u{8}'),
+                    Some((_, 'f')) => out.push('\u{c}'),
+                    Some((_, 'n')) => out.push('\n'),
+                    Some((_, 'r')) => out.push('\r'),
+                    Some((_, 't')) => out.push('\t'),
+                    Some((_, 'u')) => out.push(self.unicode_escape()?),
+                    Some((at, c)) => bail!("invalid escape \\{c} at byte {at}"),
+                    None => bail!("unterminated string"),
+                },
+                Some((_, c)) => out.push(c),
+                None => bail!("unterminated string"),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| anyhow!("invalid \\u escape"));
+        }
+        self.expect('\\')?;
+        self.expect('u')?;
+        let low = self.hex4()?;
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+        char::from_u32(code).ok_or_else(|| anyhow!("invalid surrogate pair"))
+    }
+
+    fn hex4(&mut self) -> Result<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|(_, c)| c.to_digit(16))
+                .ok_or_else(|| anyhow!("invalid \\u escape"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+}
+
+fn write_escaped(buf: &mut String, value: &str) {
+    buf.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(buf, "\\u{:04x}", c as u32);
+            }
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_strings_and_builds_objects() {
+        let line = JsonObject::new()
+            .str("path", "a\"b\\c\n\u{1}")
+            .num("bytes", 42)
+            .bool("ok", true)
+            .raw("items", &array(["1".to_string(), "2".to_string()]))
+            .finish();
+
+        assert_eq!(
+            line,
+            r#"{"path":"a\"b\\c\n\u0001","bytes":42,"ok":true,"items":[1,2]}"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_get_a_raw_side_channel() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let latin1 = Path::new(OsStr::from_bytes(b"/ws/caf\xe9 100%/target"));
+        let line = JsonObject::new()
+            .path("path", latin1)
+            .path("repo_root", Path::new("/ws/café"))
+            .finish();
+
+        assert_eq!(
+            line,
+            r#"{"path":"/ws/caf� 100%/target","path_raw":"/ws/caf%E9 100%25/target","repo_root":"/ws/café"}"#
+        );
+        assert!(parse(&line).is_ok());
+    }
+
+    #[test]
+    fn parses_what_it_writes() {
+        let line = JsonObject::new()
+            .str("path", "a\"b\\c\n\u{1}é")
+            .num("bytes", u64::MAX)
+            .bool("ok", false)
+            .raw("items", &array(["1".to_string(), "null".to_string()]))
+            .finish();
+
+        let value = parse(&line).unwrap();
+        assert_eq!(
+            value.get("path").and_then(JsonValue::as_str),
+            Some("a\"b\\c\n\u{1}é")
+        );
+        assert_eq!(
+            value.get("bytes").and_then(JsonValue::as_u64),
+            Some(u64::MAX)
+        );
+        assert_eq!(value.get("ok"), Some(&JsonValue::Bool(false)));
+        assert_eq!(
+            value
+                .get("items")
+                .and_then(JsonValue::as_array)
+                .map(<[_]>::len),
+            Some(2)
+        );
+        assert_eq!(
+            parse(r#" ["\ud83d\ude00", {}] "#).unwrap(),
+            JsonValue::Array(vec![
+                JsonValue::String("😀".to_string()),
+                JsonValue::Object(Vec::new())
+            ])
+        );
+        assert!(parse("{\"a\":1,}").is_err());
+        assert!(parse("[1] x").is_err());
+    }
+}
@@ -1,9 +1,161 @@
+//! Finds gitignored build artifacts (`target/`, `node_modules/`, ...) grouped
+//! by Git repo, and deletes them.
+//!
+//! The `clean-my-code` binary is a thin wrapper around [`run`]. To embed the
+//! scanner instead, call [`scan`] to get per-repo reports, [`plan`] to turn
+//! them into delete targets, and [`execute`] to remove those targets. Every
+//! target is re-checked with `git check-ignore` right before it is deleted.
+//!
+//! The interactive terminal UI sits behind the default `tui` feature; build
+//! with `default-features = false` for just the scanner and cleaner.
+
+mod atomic_write;
+mod cache;
 mod clean;
 mod cli;
+mod exclude;
 mod format;
 mod git;
+mod heuristics;
+mod json;
+mod mounts;
 mod report;
+mod roots;
 mod scan;
+mod script;
+mod signal;
+mod snapshot;
+#[cfg(feature = "tui")]
+mod state;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "tui")]
 mod tui;
 
-pub use cli::run;
+use std::path::Path;
+
+use anyhow::Result;
+
+pub use cache::ScanCache;
+pub use clean::{DeleteOptions, DeleteSummary, DeleteTarget, SkipReason};
+pub use cli::{DEFAULT_ARTIFACT_DIR_NAMES, run};
+pub use exclude::ExcludeSet;
+pub use git::{GitHead, GitLimit, GitOptions};
+pub use report::{ArtifactRecord, RepoReport};
+pub use scan::{ArtifactMatcher, DirStats, NamePattern, ScanOptions, dir_stats};
+pub use signal::Terminated;
+
+/// Scans `root` for artifact dirs that their repo ignores, grouped by repo
+/// and ordered by head commit time, oldest first.
+pub fn scan(root: &Path, options: &ScanOptions) -> Result<Vec<RepoReport>> {
+    let roots = roots::ScanRoots::canonicalize(&[root.to_path_buf()])?;
+    Ok(report::collect_reports(&roots, options).reports)
+}
+
+/// Like [`scan`], but reuses `previous`, an earlier result for the same
+/// root: repos whose root dir is unchanged are not walked again, and their
+/// known artifacts are only re-sized when something in them changed.
+pub fn collect_reports_incremental(
+    root: &Path,
+    options: &ScanOptions,
+    previous: &[RepoReport],
+) -> Result<Vec<RepoReport>> {
+    let roots = roots::ScanRoots::canonicalize(&[root.to_path_buf()])?;
+    Ok(report::collect_reports_incremental(&roots, options, previous).reports)
+}
+
+/// Plans the deletion of every artifact in `reports`.
+pub fn plan(reports: &[RepoReport]) -> Vec<DeleteTarget> {
+    clean::plan_delete_targets(reports.iter().map(|report| (report, true)))
+}
+
+/// Deletes `targets`, skipping any that are no longer ignored or that
+/// resolve outside their repo. With `options.dry_run` nothing is removed.
+pub fn execute(targets: &[DeleteTarget], options: DeleteOptions) -> DeleteSummary {
+    clean::execute_delete_with_progress(targets, options, || false, |_| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, fs};
+
+    use super::*;
+    use crate::test_util::{init_repo, make_temp_dir};
+
+    #[test]
+    fn scan_plan_execute_round_trip() {
+        let root = make_temp_dir("clean-my-code-lib");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("target")).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+        fs::write(repo.join("target/blob"), vec![0u8; 10]).unwrap();
+
+        let options = ScanOptions::new(
+            DEFAULT_ARTIFACT_DIR_NAMES
+                .iter()
+                .copied()
+                .map(OsString::from)
+                .collect(),
+        );
+        let reports = scan(&root, &options).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].total_size_bytes, 10);
+
+        let targets = plan(&reports);
+        assert_eq!(targets.len(), 1);
+        let dry = execute(
+            &targets,
+            DeleteOptions {
+                dry_run: true,
+                ..DeleteOptions::default()
+            },
+        );
+        assert_eq!(dry.deleted_paths, 0);
+        assert_eq!((dry.kept_paths, dry.kept_planned_bytes), (1, 10));
+        assert!(targets[0].path.exists());
+
+        let summary = execute(&targets, DeleteOptions::default());
+        assert_eq!(summary.deleted_paths, 1);
+        assert!(!targets[0].path.exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn execute_keeps_planned_targets_that_hold_a_repo() {
+        let root = make_temp_dir("clean-my-code-lib-nested");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("vendor/lib")).unwrap();
+        for dir in [&repo, &repo.join("vendor/lib")] {
+            init_repo(dir);
+        }
+        fs::write(repo.join(".gitignore"), "vendor/\n").unwrap();
+        fs::write(repo.join("vendor/lib/work.rs"), "fn main() {}").unwrap();
+        let targets = [DeleteTarget {
+            repo_root: repo.clone(),
+            path: repo.join("vendor"),
+            planned_bytes: 12,
+        }];
+
+        let dry = execute(
+            &targets,
+            DeleteOptions {
+                dry_run: true,
+                ..DeleteOptions::default()
+            },
+        );
+        let summary = execute(&targets, DeleteOptions::default());
+        let kept = repo.join("vendor/lib/work.rs").exists();
+        let _ = fs::remove_dir_all(root);
+
+        for summary in [dry, summary] {
+            assert_eq!((summary.deleted_paths, summary.kept_paths), (0, 0));
+            assert_eq!(
+                summary.skipped,
+                vec![(repo.join("vendor"), SkipReason::NestedRepo)]
+            );
+        }
+        assert!(kept);
+    }
+}
@@ -1,3 +1,19 @@
-fn main() -> anyhow::Result<()> {
-    clean_my_code::run()
+use std::process::ExitCode;
+
+use clean_my_code::Terminated;
+
+fn main() -> ExitCode {
+    match clean_my_code::run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => match err.downcast_ref::<Terminated>() {
+            Some(terminated) => {
+                eprintln!("{terminated}");
+                ExitCode::from(terminated.exit_code())
+            }
+            None => {
+                eprintln!("Error: {err:?}");
+                ExitCode::FAILURE
+            }
+        },
+    }
 }
@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use crate::roots::ScanRoots;
+
+/// Filesystem types served over the network. Walking them can stall for
+/// minutes when a share is slow or an automount has to come up first.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smbfs",
+    "smb3",
+    "afs",
+    "ncpfs",
+    "9p",
+    "ceph",
+    "glusterfs",
+    "lustre",
+    "gpfs",
+    "webdav",
+    "fuse.sshfs",
+    "fuse.davfs2",
+    "fuse.glusterfs",
+    "fuse.rclone",
+    "fuse.s3fs",
+    "osxfuse",
+    "macfuse",
+];
+
+fn is_network_fs(fs_type: &str) -> bool {
+    NETWORK_FS_TYPES.contains(&fs_type)
+}
+
+/// Network filesystem mount points strictly below one of `roots`. A root
+/// that is itself on such a mount was asked for explicitly and is kept.
+pub fn network_mounts_under(roots: &ScanRoots) -> Vec<PathBuf> {
+    let mut mounts = mounted_filesystems()
+        .into_iter()
+        .filter(|(_, fs_type)| is_network_fs(fs_type))
+        .map(|(mount_point, _)| mount_point)
+        .filter(|mount_point| {
+            roots
+                .iter()
+                .any(|root| mount_point.starts_with(root) && mount_point != root)
+        })
+        .collect::<Vec<_>>();
+    mounts.sort();
+    mounts.dedup();
+    mounts
+}
+
+/// Mounts that do not record access times and contain, or lie below, one of
+/// `roots`; atimes read there say nothing about use.
+pub fn noatime_mounts_for(roots: &ScanRoots) -> Vec<PathBuf> {
+    let mut mounts = noatime_mount_points()
+        .into_iter()
+        .filter(|mount_point| {
+            roots
+                .iter()
+                .any(|root| root.starts_with(mount_point) || mount_point.starts_with(root))
+        })
+        .collect::<Vec<_>>();
+    mounts.sort();
+    mounts.dedup();
+    mounts
+}
+
+#[cfg(target_os = "linux")]
+fn noatime_mount_points() -> Vec<PathBuf> {
+    std::fs::read_to_string("/proc/self/mountinfo")
+        .map(|text| parse_noatime_mounts(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn noatime_mount_points() -> Vec<PathBuf> {
+    use std::ffi::CStr;
+
+    let mut buf: *mut libc::statfs = std::ptr::null_mut();
+    // SAFETY: as in `mounted_filesystems`.
+    unsafe {
+        let count = libc::getmntinfo(&mut buf, libc::MNT_NOWAIT);
+        if count <= 0 || buf.is_null() {
+            return Vec::new();
+        }
+        std::slice::from_raw_parts(buf, count as usize)
+            .iter()
+            .filter(|fs| u64::from(fs.f_flags) & libc::MNT_NOATIME as u64 != 0)
+            .map(|fs| {
+                let mount_point = CStr::from_ptr(fs.f_mntonname.as_ptr());
+                PathBuf::from(mount_point.to_string_lossy().into_owned())
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+fn noatime_mount_points() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// `(mount point, filesystem type)` for every mounted filesystem we can see.
+#[cfg(target_os = "linux")]
+fn mounted_filesystems() -> Vec<(PathBuf, String)> {
+    std::fs::read_to_string("/proc/self/mountinfo")
+        .map(|text| parse_mountinfo(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn mounted_filesystems() -> Vec<(PathBuf, String)> {
+    use std::ffi::CStr;
+
+    let mut buf: *mut libc::statfs = std::ptr::null_mut();
+    // SAFETY: `getmntinfo` points `buf` at `count` statfs entries in storage
+    // it owns and reuses, which we only read before returning. MNT_NOWAIT
+    // keeps it from touching (and possibly hanging on) the mounts.
+    unsafe {
+        let count = libc::getmntinfo(&mut buf, libc::MNT_NOWAIT);
+        if count <= 0 || buf.is_null() {
+            return Vec::new();
+        }
+        std::slice::from_raw_parts(buf, count as usize)
+            .iter()
+            .map(|fs| {
+                let mount_point = CStr::from_ptr(fs.f_mntonname.as_ptr());
+                let fs_type = CStr::from_ptr(fs.f_fstypename.as_ptr());
+                (
+                    PathBuf::from(mount_point.to_string_lossy().into_owned()),
+                    fs_type.to_string_lossy().into_owned(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+fn mounted_filesystems() -> Vec<(PathBuf, String)> {
+    Vec::new()
+}
+
+/// Parses `/proc/self/mountinfo`: the mount point is the fifth field and the
+/// filesystem type follows the `-` separator after the optional fields.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_mountinfo(text: &str) -> Vec<(PathBuf, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let mount_point = fields.nth(4)?;
+            let fs_type = fields.skip_while(|field| *field != "-").nth(1)?;
+            Some((
+                PathBuf::from(unescape_mountinfo(mount_point)),
+                fs_type.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Mount points in `/proc/self/mountinfo` whose per-mount options, the
+/// sixth field, include `noatime`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_noatime_mounts(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let mount_point = fields.nth(4)?;
+            let options = fields.next()?;
+            options
+                .split(',')
+                .any(|option| option == "noatime")
+                .then(|| PathBuf::from(unescape_mountinfo(mount_point)))
+        })
+        .collect()
+}
+
+/// Undoes the `\ooo` octal escapes mountinfo uses for spaces and the like.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn unescape_mountinfo(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(code) = field
+                .get(i + 1..i + 4)
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok())
+        {
+            out.push(code);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Whether `path` is one of the mount points the walks must not enter.
+pub fn is_skipped_mount(skip_mounts: &[PathBuf], path: &Path) -> bool {
+    !skip_mounts.is_empty() && skip_mounts.iter().any(|mount| mount == path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mountinfo_and_keeps_network_types() {
+        let text = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+40 22 0:45 / /home/me/nas\\040share rw,relatime shared:20 - nfs4 nas:/export rw
+41 22 0:46 / /mnt/box rw - fuse.sshfs me@box: rw
+";
+        let mounts = parse_mountinfo(text);
+        assert_eq!(
+            mounts,
+            vec![
+                (PathBuf::from("/"), "ext4".to_string()),
+                (PathBuf::from("/home/me/nas share"), "nfs4".to_string()),
+                (PathBuf::from("/mnt/box"), "fuse.sshfs".to_string()),
+            ]
+        );
+        let network = mounts
+            .iter()
+            .filter(|(_, fs_type)| is_network_fs(fs_type))
+            .count();
+        assert_eq!(network, 2);
+    }
+
+    #[test]
+    fn parses_noatime_mounts_from_mountinfo() {
+        let text = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+40 22 8:2 / /data rw,noatime shared:20 - xfs /dev/sdb1 rw
+41 22 8:3 / /srv/build\\040cache ro,nosuid,noatime - ext4 /dev/sdc1 ro
+";
+        assert_eq!(
+            parse_noatime_mounts(text),
+            vec![PathBuf::from("/data"), PathBuf::from("/srv/build cache")]
+        );
+    }
+}
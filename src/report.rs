@@ -2,22 +2,51 @@ use std::{
     collections::{HashMap, HashSet},
     ffi::OsString,
     path::{Path, PathBuf},
-    time::SystemTime,
+    sync::{Mutex, atomic::Ordering},
+    time::{Duration, SystemTime},
 };
 
+use anyhow::bail;
 use rayon::prelude::*;
 
 use crate::{
-    format::{display_rel_path, format_bytes},
-    git::{GitHead, git_head, is_git_ignored},
-    scan::{DirStats, dir_stats, scan_artifact_dirs},
+    format::{
+        ByteUnit, display_path, display_rel_path, format_bytes_with, format_local_date,
+        format_percent,
+    },
+    git::{GitHead, GitOptions, git_head, is_git_ignored},
+    heuristics::{is_unconfirmed, is_unverified},
+    roots::ScanRoots,
+    scan::{
+        DirStats, ScanOptions, WalkError, WalkErrors, WalkTally, dir_stats,
+        dir_stats_with_progress, scan_artifact_dirs, scan_artifact_dirs_streaming,
+    },
 };
 
+/// Logs a candidate's ignore decision to stderr when `options.verbose` asks
+/// for it.
+fn log_decision(options: &ScanOptions, path: &Path, decision: &str) {
+    if options.verbose {
+        eprintln!("verbose: {}: {decision}", path.display());
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ArtifactRecord {
     pub repo_root: PathBuf,
     pub path: PathBuf,
     pub stats: DirStats,
+    /// The marker of the ecosystem behind its name is missing, so it is
+    /// reported but only ever cleaned when picked by hand.
+    pub unverified: bool,
+}
+
+impl ArtifactRecord {
+    /// Whether cleaning may remove it: not if `--exclude` kept paths inside
+    /// it, since removing the dir would take them along.
+    pub fn is_deletable(&self) -> bool {
+        self.stats.excluded == 0
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,43 +55,294 @@ pub struct RepoReport {
     pub head: Option<GitHead>,
     pub artifacts: Vec<ArtifactRecord>,
     pub total_size_bytes: u64,
+    pub total_file_count: u64,
     pub newest_mtime: Option<SystemTime>,
+    /// Oldest file mtime across the artifacts.
+    pub oldest_mtime: Option<SystemTime>,
+    /// Newest file access time across the artifacts, if the platform
+    /// reports atimes.
+    pub newest_atime: Option<SystemTime>,
+    /// The repo root dir's own mtime when the report was made, so a rescan
+    /// can tell whether entries were added or removed at its top level.
+    pub root_mtime: Option<SystemTime>,
+}
+
+/// What the scan saw besides the reported artifacts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanCounters {
+    /// Distinct repos that contained at least one candidate dir.
+    pub repos_scanned: usize,
+    /// Candidate dirs with no enclosing git repo; never reported or cleaned.
+    pub outside_repo_dirs: usize,
+    pub outside_repo_bytes: u64,
+    /// Unreadable entries plus candidates dropped after a failed git or
+    /// stat lookup.
+    pub warnings: usize,
+    /// Dirs left out for living on another device (`same_device`).
+    pub other_device_dirs: usize,
+    /// Directories listed while looking for candidates.
+    pub dirs_listed: usize,
+    /// Directories or entries the walks could not read.
+    pub unreadable: usize,
+    /// The scan ran out of `ScanOptions::deadline`, so repos and artifacts
+    /// may be missing.
+    pub timed_out: bool,
+}
+
+impl ScanCounters {
+    pub fn record_outside_repo(&mut self, stats: &DirStats) {
+        self.outside_repo_dirs += 1;
+        self.outside_repo_bytes = self.outside_repo_bytes.saturating_add(stats.size_bytes);
+    }
+
+    pub fn outside_repo_summary(&self, unit: Option<ByteUnit>) -> Option<String> {
+        (self.outside_repo_dirs > 0).then(|| {
+            format!(
+                "{} artifact dirs outside any repo ({} reclaimable)",
+                self.outside_repo_dirs,
+                format_bytes_with(self.outside_repo_bytes, unit)
+            )
+        })
+    }
 }
 
-pub fn collect_reports(
-    scan_root: &Path,
-    artifact_dir_names: &HashSet<OsString>,
-) -> Vec<RepoReport> {
-    let candidates = scan_artifact_dirs(scan_root, artifact_dir_names);
-    let records = candidates
+#[derive(Debug)]
+pub struct ScanOutcome {
+    pub reports: Vec<RepoReport>,
+    pub counters: ScanCounters,
+    /// The first unreadable paths of the discovery and sizing walks, each
+    /// list capped; `counters.unreadable` has the full count.
+    pub walk_errors: Vec<WalkError>,
+    /// Candidates that could not be checked or sized, sorted by path.
+    pub failures: Vec<CandidateError>,
+}
+
+pub fn collect_reports(scan_roots: &ScanRoots, options: &ScanOptions) -> ScanOutcome {
+    let tally = WalkTally::default();
+    let sizing_errors = WalkErrors::default();
+    let outcomes = Mutex::new(Vec::new());
+    // Candidates are sized as discovery finds them, so a scan that runs out
+    // of time still has sizes for what it found.
+    rayon::scope(|scope| {
+        let (outcomes, sizing_errors) = (&outcomes, &sizing_errors);
+        for root in scan_roots.iter() {
+            scan_artifact_dirs_streaming(root, options, &tally, |path| {
+                scope.spawn(move |_| {
+                    let outcome = process_candidate(&path, options, sizing_errors);
+                    outcomes
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(outcome);
+                });
+            });
+        }
+    });
+    let outcomes = outcomes.into_inner().unwrap_or_else(|e| e.into_inner());
+    build_outcome(outcomes, tally, sizing_errors, &options.git)
+}
+
+/// Like `collect_reports`, but reuses `previous` where it can. A repo whose
+/// root dir mtime is unchanged is not walked again: its known artifacts are
+/// re-verified in place (still there, still ignored, nothing newer at their
+/// top level) and only re-sized when that check fails. Discovery runs
+/// everywhere else, so new and changed repos are found as usual; a new
+/// artifact deep inside an otherwise unchanged repo is not.
+///
+/// With `max_depth` set this is a plain `collect_reports`, since walks
+/// that start below a scan root cannot tell how deep they are.
+pub fn collect_reports_incremental(
+    scan_roots: &ScanRoots,
+    options: &ScanOptions,
+    previous: &[RepoReport],
+) -> ScanOutcome {
+    if options.max_depth.is_some() {
+        return collect_reports(scan_roots, options);
+    }
+
+    let (unchanged, changed): (Vec<&RepoReport>, Vec<&RepoReport>) = previous
+        .iter()
+        .filter(|report| {
+            scan_roots
+                .iter()
+                .any(|root| report.repo_root.starts_with(root))
+        })
+        .partition(|report| {
+            report.root_mtime.is_some() && report.root_mtime == modified(&report.repo_root)
+        });
+
+    // Only the walks skip unchanged repos; sizing still sees everything.
+    let mut discovery = options.clone();
+    discovery
+        .skip_repos
+        .extend(unchanged.iter().map(|report| report.repo_root.clone()));
+    // Changed repos nested in unchanged ones are cut off from the scan
+    // roots, so they are walked on their own.
+    let nested_changed = changed
+        .iter()
+        .map(|report| &report.repo_root)
+        .filter(|repo_root| {
+            unchanged
+                .iter()
+                .any(|outer| repo_root.starts_with(&outer.repo_root))
+        });
+
+    let tally = WalkTally::default();
+    let mut candidates = scan_roots
+        .iter()
+        .chain(nested_changed.map(PathBuf::as_path))
+        .flat_map(|root| scan_artifact_dirs(root, &discovery, &tally))
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+
+    let known = unchanged
+        .iter()
+        .flat_map(|report| report.artifacts.iter())
+        .collect::<Vec<_>>();
+    // A scan root that is itself an unchanged repo is still walked, so its
+    // known artifacts turn up again as candidates; re-verifying covers them.
+    let known_paths = known
+        .iter()
+        .map(|record| record.path.as_path())
+        .collect::<HashSet<_>>();
+    candidates.retain(|path| !known_paths.contains(path.as_path()));
+    let sizing_errors = WalkErrors::default();
+    let outcomes = candidates
         .par_iter()
-        .filter_map(|path| process_candidate(path))
+        .map(|path| process_candidate(path, options, &sizing_errors))
+        .chain(
+            known
+                .par_iter()
+                .map(|record| reverify_artifact(record, options, &tally, &sizing_errors)),
+        )
         .collect::<Vec<_>>();
+    build_outcome(outcomes, tally, sizing_errors, &options.git)
+}
+
+/// Re-checks an artifact of an unchanged repo, walking it again only if
+/// something at its top level is newer than its last walk saw.
+fn reverify_artifact(
+    record: &ArtifactRecord,
+    options: &ScanOptions,
+    tally: &WalkTally,
+    sizing_errors: &WalkErrors,
+) -> CandidateOutcome {
+    if !std::fs::symlink_metadata(&record.path).is_ok_and(|meta| meta.is_dir()) {
+        log_decision(options, &record.path, "gone since the last scan");
+        return CandidateOutcome::Skipped;
+    }
+    match is_git_ignored(&record.repo_root, &record.path, &options.git) {
+        Ok(true) => {}
+        Ok(false) => {
+            return CandidateOutcome::NotIgnored {
+                repo_root: record.repo_root.clone(),
+            };
+        }
+        Err(err) => {
+            return CandidateOutcome::Failed(CandidateError::new(
+                &record.path,
+                CandidateErrorKind::GitCheckIgnore,
+                &err,
+            ));
+        }
+    }
+    if looks_unchanged(&record.path, record.stats.newest_mtime, tally) {
+        return CandidateOutcome::Artifact(record.clone());
+    }
+    process_candidate(&record.path, options, sizing_errors)
+}
+
+/// Whether neither `path` nor anything directly in it is newer than
+/// `newest_mtime`, the newest mtime the last walk of `path` saw.
+fn looks_unchanged(path: &Path, newest_mtime: Option<SystemTime>, tally: &WalkTally) -> bool {
+    let Some(newest_mtime) = newest_mtime else {
+        return false;
+    };
+    let not_newer = |path: &Path| modified(path).is_some_and(|mtime| mtime <= newest_mtime);
+    if !not_newer(path) {
+        return false;
+    }
+    tally.dirs_listed.fetch_add(1, Ordering::Relaxed);
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return false;
+    };
+    entries
+        .into_iter()
+        .all(|entry| entry.is_ok_and(|entry| not_newer(&entry.path())))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::symlink_metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
 
+/// Groups candidate outcomes into per-repo reports, oldest head first.
+fn build_outcome(
+    outcomes: Vec<CandidateOutcome>,
+    tally: WalkTally,
+    sizing_errors: WalkErrors,
+    git: &GitOptions,
+) -> ScanOutcome {
+    let mut counters = ScanCounters {
+        warnings: tally.errors.total(),
+        other_device_dirs: tally.other_device.into_inner(),
+        dirs_listed: tally.dirs_listed.into_inner(),
+        unreadable: tally.errors.total() + sizing_errors.total(),
+        timed_out: tally.timed_out.into_inner(),
+        ..ScanCounters::default()
+    };
+    let mut walk_errors = tally.errors.into_kept();
+    walk_errors.extend(sizing_errors.into_kept());
+    walk_errors.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut seen_repos: HashSet<PathBuf> = HashSet::new();
     let mut by_repo: HashMap<PathBuf, Vec<ArtifactRecord>> = HashMap::new();
-    for record in records {
-        by_repo
-            .entry(record.repo_root.clone())
-            .or_default()
-            .push(record);
+    let mut failures = Vec::new();
+    for outcome in outcomes {
+        counters.warnings += outcome.warnings();
+        counters.other_device_dirs += outcome.other_device_dirs();
+        match outcome {
+            CandidateOutcome::Artifact(record) => {
+                seen_repos.insert(record.repo_root.clone());
+                by_repo
+                    .entry(record.repo_root.clone())
+                    .or_default()
+                    .push(record);
+            }
+            CandidateOutcome::NotIgnored { repo_root } => {
+                seen_repos.insert(repo_root);
+            }
+            CandidateOutcome::OutsideRepo(stats) => counters.record_outside_repo(&stats),
+            CandidateOutcome::Failed(failure) => {
+                counters.warnings += 1;
+                failures.push(failure);
+            }
+            CandidateOutcome::TimedOut => counters.timed_out = true,
+            CandidateOutcome::Skipped => {}
+        }
     }
+    counters.repos_scanned = seen_repos.len();
 
     let mut reports: Vec<RepoReport> = by_repo
         .into_iter()
         .map(|(repo_root, mut artifacts)| {
-            artifacts.sort_by(|a, b| {
-                b.stats
-                    .size_bytes
-                    .cmp(&a.stats.size_bytes)
-                    .then_with(|| a.path.cmp(&b.path))
-            });
+            sort_artifacts(&mut artifacts, ArtifactOrder::Size);
             let total_size_bytes = artifacts.iter().map(|a| a.stats.size_bytes).sum::<u64>();
+            let total_file_count = artifacts.iter().map(|a| a.stats.file_count).sum::<u64>();
             let newest_mtime = artifacts.iter().filter_map(|a| a.stats.newest_mtime).max();
+            let oldest_mtime = artifacts.iter().filter_map(|a| a.stats.oldest_mtime).min();
+            let newest_atime = artifacts.iter().filter_map(|a| a.stats.newest_atime).max();
 
-            let head = match git_head(&repo_root) {
+            let root_mtime = modified(&repo_root);
+            let head = match git_head(&repo_root, git) {
                 Ok(head) => head,
                 Err(err) => {
-                    eprintln!("warn: git head lookup failed: repo={repo_root:?} err={err:#}");
+                    counters.warnings += 1;
+                    failures.push(CandidateError::new(
+                        &repo_root,
+                        CandidateErrorKind::GitHead,
+                        &err,
+                    ));
                     None
                 }
             };
@@ -72,7 +352,11 @@ pub fn collect_reports(
                 head,
                 artifacts,
                 total_size_bytes,
+                total_file_count,
                 newest_mtime,
+                oldest_mtime,
+                newest_atime,
+                root_mtime,
             }
         })
         .collect();
@@ -83,23 +367,357 @@ pub fn collect_reports(
 
         a_ts.cmp(&b_ts).then_with(|| a.repo_root.cmp(&b.repo_root))
     });
+    failures.sort_by(|a, b| a.path.cmp(&b.path));
 
-    reports
+    ScanOutcome {
+        reports,
+        counters,
+        walk_errors,
+        failures,
+    }
+}
+
+/// Totals for one artifact dir name (e.g. `node_modules`) across repos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactNameTotal {
+    pub name: OsString,
+    pub dirs: usize,
+    pub size_bytes: u64,
+}
+
+/// Groups artifacts by dir name, largest total first.
+pub fn totals_by_artifact_name<'a, I>(artifacts: I) -> Vec<ArtifactNameTotal>
+where
+    I: IntoIterator<Item = &'a ArtifactRecord>,
+{
+    let mut by_name: HashMap<OsString, ArtifactNameTotal> = HashMap::new();
+    for artifact in artifacts {
+        let Some(name) = artifact.path.file_name() else {
+            continue;
+        };
+        let total = by_name
+            .entry(name.to_os_string())
+            .or_insert_with(|| ArtifactNameTotal {
+                name: name.to_os_string(),
+                dirs: 0,
+                size_bytes: 0,
+            });
+        total.dirs += 1;
+        total.size_bytes = total.size_bytes.saturating_add(artifact.stats.size_bytes);
+    }
+
+    let mut totals = by_name.into_values().collect::<Vec<_>>();
+    totals.sort_by(|a, b| {
+        b.size_bytes
+            .cmp(&a.size_bytes)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    totals
+}
+
+/// Repos whose newest artifact is at least this many days old are stale and
+/// picked by default for cleaning.
+pub const STALE_AFTER_DAYS: u64 = 180;
+
+/// How far past `now` an mtime may lie before it is worth a warning;
+/// files written while the scan runs are a little ahead of its start.
+const FUTURE_MTIME_TOLERANCE: Duration = Duration::from_secs(60 * 60);
+
+/// Which file times tell when an artifact was last used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AgeBasis {
+    /// The newest mtime: when anything in it was last written.
+    #[default]
+    Modified,
+    /// The later of the newest mtime and newest atime, so reads count too.
+    /// Artifacts without atimes are still judged by mtime alone.
+    Access,
+}
+
+impl AgeBasis {
+    fn last_used(
+        self,
+        newest_mtime: Option<SystemTime>,
+        newest_atime: Option<SystemTime>,
+    ) -> Option<SystemTime> {
+        match self {
+            AgeBasis::Modified => newest_mtime,
+            AgeBasis::Access => newest_mtime.max(newest_atime),
+        }
+    }
 }
 
-pub fn print_scan_report(scan_root: &Path, reports: &[RepoReport]) {
+/// Days since the repo's artifacts were last used, per `basis`. A time in
+/// the future (clock skew, files copied from a machine with a wrong clock)
+/// counts as just touched, age 0, so the repo is fresh rather than of
+/// unknown age.
+pub fn repo_age_days(report: &RepoReport, now: SystemTime, basis: AgeBasis) -> Option<u64> {
+    basis
+        .last_used(report.newest_mtime, report.newest_atime)
+        .map(|newest| age_days(newest, now))
+}
+
+fn age_days(mtime: SystemTime, now: SystemTime) -> u64 {
+    now.duration_since(mtime).unwrap_or_default().as_secs() / (24 * 60 * 60)
+}
+
+/// Whether the repo's newest artifact mtime lies clearly in the future.
+pub fn has_future_mtime(report: &RepoReport, now: SystemTime) -> bool {
+    report
+        .newest_mtime
+        .is_some_and(|newest| newest > now + FUTURE_MTIME_TOLERANCE)
+}
+
+pub fn is_stale(report: &RepoReport, now: SystemTime, basis: AgeBasis) -> bool {
+    repo_age_days(report, now, basis).is_some_and(|age_days| age_days >= STALE_AFTER_DAYS)
+}
+
+fn is_artifact_stale(artifact: &ArtifactRecord, now: SystemTime, basis: AgeBasis) -> bool {
+    basis
+        .last_used(artifact.stats.newest_mtime, artifact.stats.newest_atime)
+        .is_some_and(|time| age_days(time, now) >= STALE_AFTER_DAYS)
+}
+
+/// Bytes in artifacts that are stale on their own, even if the repo has a
+/// newer artifact elsewhere.
+pub fn stale_size_bytes(report: &RepoReport, now: SystemTime, basis: AgeBasis) -> u64 {
+    report
+        .artifacts
+        .iter()
+        .filter(|artifact| is_artifact_stale(artifact, now, basis))
+        .map(|artifact| artifact.stats.size_bytes)
+        .sum()
+}
+
+/// Share of a repo's artifact bytes that are stale, `0.0` for an empty repo.
+pub fn stale_ratio(report: &RepoReport, now: SystemTime, basis: AgeBasis) -> f64 {
+    if report.total_size_bytes == 0 {
+        return 0.0;
+    }
+    stale_size_bytes(report, now, basis) as f64 / report.total_size_bytes as f64
+}
+
+/// How the artifacts within a repo are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArtifactOrder {
+    /// Largest first.
+    #[default]
+    Size,
+    /// Least recently used first; never-dated ones last.
+    Age(AgeBasis),
+}
+
+/// Orders a repo's artifacts by `order`, ties by path.
+pub fn sort_artifacts(artifacts: &mut [ArtifactRecord], order: ArtifactOrder) {
+    artifacts.sort_by(|a, b| {
+        let by_key = match order {
+            ArtifactOrder::Size => b.stats.size_bytes.cmp(&a.stats.size_bytes),
+            ArtifactOrder::Age(basis) => {
+                let a_used = basis.last_used(a.stats.newest_mtime, a.stats.newest_atime);
+                let b_used = basis.last_used(b.stats.newest_mtime, b.stats.newest_atime);
+                match (a_used, b_used) {
+                    (Some(a_used), Some(b_used)) => a_used.cmp(&b_used),
+                    (a_used, b_used) => b_used.is_some().cmp(&a_used.is_some()),
+                }
+            }
+        };
+        by_key.then_with(|| a.path.cmp(&b.path))
+    });
+}
+
+/// Mostly-stale repos first, then larger repos first.
+pub fn sort_by_stale_ratio(reports: &mut [RepoReport], now: SystemTime, basis: AgeBasis) {
+    reports.sort_by(|a, b| {
+        stale_ratio(b, now, basis)
+            .total_cmp(&stale_ratio(a, now, basis))
+            .then_with(|| b.total_size_bytes.cmp(&a.total_size_bytes))
+            .then_with(|| a.repo_root.cmp(&b.repo_root))
+    });
+}
+
+/// Whether a repo's total artifact size falls within `[min, max]`; an unset
+/// `max` means no upper bound.
+pub fn is_within_size_range(report: &RepoReport, min_bytes: u64, max_bytes: Option<u64>) -> bool {
+    report.total_size_bytes >= min_bytes
+        && max_bytes.is_none_or(|max_bytes| report.total_size_bytes <= max_bytes)
+}
+
+/// Whether the repo's artifacts hold between `min_files` and `max_files`
+/// files (inclusive); `max` means no upper bound.
+pub fn is_within_file_range(report: &RepoReport, min_files: u64, max_files: Option<u64>) -> bool {
+    report.total_file_count >= min_files
+        && max_files.is_none_or(|max_files| report.total_file_count <= max_files)
+}
+
+/// Unreadable paths shown in a summary; the rest are only counted.
+const WALK_ERRORS_SHOWN: usize = 20;
+
+/// Headline naming the largest repo and the artifact name taking the
+/// biggest share, e.g. `Largest: ~/work/foo (42 GiB); node_modules
+/// dominates (60% of total)`. Nothing when no artifact has any size.
+pub fn largest_consumers_line(
+    scan_roots: &ScanRoots,
+    reports: &[RepoReport],
+    unit: Option<ByteUnit>,
+) -> Option<String> {
     let total_bytes = reports.iter().map(|r| r.total_size_bytes).sum::<u64>();
+    if total_bytes == 0 {
+        return None;
+    }
+    let largest = reports.iter().max_by(|a, b| {
+        a.total_size_bytes
+            .cmp(&b.total_size_bytes)
+            .then_with(|| b.repo_root.cmp(&a.repo_root))
+    })?;
+    let top_name = totals_by_artifact_name(reports.iter().flat_map(|r| r.artifacts.iter()))
+        .into_iter()
+        .next()?;
+    Some(format!(
+        "Largest: {} ({}); {} dominates ({} of total)",
+        scan_roots.display_rel(&largest.repo_root),
+        format_bytes_with(largest.total_size_bytes, unit),
+        display_path(Path::new(&top_name.name)),
+        format_percent(top_name.size_bytes as f64 / total_bytes as f64)
+    ))
+}
 
-    println!("Scan root: {}", scan_root.display());
+/// Lists the first walk errors for a summary, or nothing if there were
+/// none. `total` is the full count, which may exceed `errors.len()`.
+pub fn walk_error_lines(scan_roots: &ScanRoots, errors: &[WalkError], total: usize) -> Vec<String> {
+    if total == 0 {
+        return Vec::new();
+    }
+    let mut lines = vec![format!(
+        "Unreadable: {total} (sizes below them are lower bounds)"
+    )];
+    for error in errors.iter().take(WALK_ERRORS_SHOWN) {
+        lines.push(format!(
+            "  {}: {}",
+            scan_roots.display_rel(&error.path),
+            error.kind
+        ));
+    }
+    let shown = errors.len().min(WALK_ERRORS_SHOWN);
+    if total > shown {
+        lines.push(format!("  ... and {} more", total - shown));
+    }
+    lines
+}
+
+/// Lists the candidates that failed, the first few with their error, or
+/// nothing if none did.
+pub fn failure_lines(scan_roots: &ScanRoots, failures: &[CandidateError]) -> Vec<String> {
+    let (undated, left_out): (Vec<_>, Vec<_>) = failures
+        .iter()
+        .partition(|failure| failure.kind == CandidateErrorKind::GitHead);
+    let mut lines = Vec::new();
+    if !left_out.is_empty() {
+        lines.push(format!(
+            "Failed: {} candidates (left out, never cleaned)",
+            left_out.len()
+        ));
+        push_failure_lines(&mut lines, scan_roots, &left_out);
+    }
+    if !undated.is_empty() {
+        lines.push(format!(
+            "Undated: {} repos (still listed, sorted last)",
+            undated.len()
+        ));
+        push_failure_lines(&mut lines, scan_roots, &undated);
+    }
+    lines
+}
+
+fn push_failure_lines(
+    lines: &mut Vec<String>,
+    scan_roots: &ScanRoots,
+    failures: &[&CandidateError],
+) {
+    for failure in failures.iter().take(WALK_ERRORS_SHOWN) {
+        lines.push(format!(
+            "  {}: {}: {}",
+            scan_roots.display_rel(&failure.path),
+            failure.kind.as_str(),
+            failure.message
+        ));
+    }
+    if failures.len() > WALK_ERRORS_SHOWN {
+        lines.push(format!(
+            "  ... and {} more",
+            failures.len() - WALK_ERRORS_SHOWN
+        ));
+    }
+}
+
+pub fn print_scan_report(
+    scan_roots: &ScanRoots,
+    outcome: &ScanOutcome,
+    options: &ScanOptions,
+    now: SystemTime,
+    basis: AgeBasis,
+    unit: Option<ByteUnit>,
+) {
+    let ScanOutcome {
+        reports,
+        counters,
+        walk_errors,
+        failures,
+    } = outcome;
+    let total_bytes = reports.iter().map(|r| r.total_size_bytes).sum::<u64>();
+
+    println!("Scan root: {}", scan_roots);
+    println!("Repos scanned: {}", counters.repos_scanned);
+    if options.verbose {
+        println!("Dirs listed: {}", counters.dirs_listed);
+    }
     println!(
         "Repos with gitignored artifacts: {}  Total: {}",
         reports.len(),
-        format_bytes(total_bytes)
+        format_bytes_with(total_bytes, unit)
     );
+    if let Some(line) = largest_consumers_line(scan_roots, reports, unit) {
+        println!("{line}");
+    }
+    if counters.timed_out {
+        println!("Status: partial (timed out); repos past the time limit are missing");
+    }
+    if let Some(summary) = counters.outside_repo_summary(unit) {
+        println!("Skipped {summary}");
+    }
+    let future = reports
+        .iter()
+        .filter(|report| has_future_mtime(report, now))
+        .count();
+    if future > 0 {
+        println!(
+            "Warning: {future} repos have artifacts dated in the future (clock skew?); treated as just touched"
+        );
+    }
+    for mount in &options.skip_mounts {
+        println!(
+            "Skipped network filesystem {} (use --include-network-fs to scan it)",
+            mount.display()
+        );
+    }
+    if counters.other_device_dirs > 0 {
+        println!(
+            "Skipped {} dirs on other filesystems",
+            counters.other_device_dirs
+        );
+    }
+    if counters.warnings > 0 {
+        println!("Warnings: {}", counters.warnings);
+    }
+    for line in walk_error_lines(scan_roots, walk_errors, counters.unreadable) {
+        println!("{line}");
+    }
+    for line in failure_lines(scan_roots, failures) {
+        println!("{line}");
+    }
     println!();
 
     for report in reports {
-        let repo_display = display_rel_path(scan_root, &report.repo_root);
+        let repo_display = scan_roots.display_rel(&report.repo_root);
         let head_display = report
             .head
             .as_ref()
@@ -109,44 +727,898 @@ pub fn print_scan_report(scan_root: &Path, reports: &[RepoReport]) {
             })
             .unwrap_or_else(|| "no commits".to_string());
 
+        let touched = report
+            .newest_mtime
+            .map(|mtime| format!("  touched {}", format_local_date(mtime)))
+            .unwrap_or_default();
+        let at_least = if report.artifacts.iter().any(|a| a.stats.unreadable > 0) {
+            ">="
+        } else {
+            ""
+        };
         println!(
-            "{repo_display}  {head_display}  total {}",
-            format_bytes(report.total_size_bytes)
+            "{repo_display}  {head_display}  total {at_least}{}  stale {}{touched}",
+            format_bytes_with(report.total_size_bytes, unit),
+            format_percent(stale_ratio(report, now, basis))
         );
         for artifact in &report.artifacts {
             let rel = display_rel_path(&report.repo_root, &artifact.path);
-            println!("  {}  {}", format_bytes(artifact.stats.size_bytes), rel);
+            let lower_bound = match artifact.stats.unreadable {
+                0 => String::new(),
+                n => format!("  (lower bound: {n} unreadable)"),
+            };
+            let excluded = match artifact.stats.excluded {
+                0 => String::new(),
+                n => format!("  (partially excluded: {n} entries; not cleaned)"),
+            };
+            let unverified = if artifact.unverified {
+                "  (unverified: no ecosystem marker; not cleaned automatically)"
+            } else {
+                ""
+            };
+            println!(
+                "  {}  {}{lower_bound}{excluded}{unverified}",
+                format_bytes_with(artifact.stats.size_bytes, unit),
+                rel
+            );
+            // Only filled in when `scan --verbose` asked for them.
+            for (file, size) in &artifact.stats.largest_files {
+                println!(
+                    "      {}  {}",
+                    format_bytes_with(*size, unit),
+                    display_rel_path(&artifact.path, file)
+                );
+            }
         }
         println!();
     }
 }
 
-pub fn process_candidate(path: &Path) -> Option<ArtifactRecord> {
-    let repo_root = crate::git::find_git_root(path)?;
-    let is_ignored = match is_git_ignored(&repo_root, path) {
+#[derive(Debug, Clone, Default)]
+pub struct CandidateEstimate {
+    pub dirs: usize,
+    pub size_bytes: u64,
+    /// Candidates whose size walk failed, left out of `size_bytes`.
+    pub failures: Vec<CandidateError>,
+}
+
+/// Sizes every candidate dir without asking git whether it is ignored, so
+/// the result is an upper bound on what `collect_reports` would find.
+pub fn estimate_candidates(scan_roots: &ScanRoots, options: &ScanOptions) -> CandidateEstimate {
+    let tally = WalkTally::default();
+    let candidates = scan_roots
+        .iter()
+        .flat_map(|root| scan_artifact_dirs(root, options, &tally))
+        .collect::<Vec<_>>();
+    let sized = candidates
+        .par_iter()
+        .map(|path| {
+            dir_stats(path, options)
+                .map(|stats| stats.size_bytes)
+                .map_err(|err| CandidateError::new(path, CandidateErrorKind::Stats, &err))
+        })
+        .collect::<Vec<_>>();
+    let mut estimate = CandidateEstimate {
+        dirs: candidates.len(),
+        ..CandidateEstimate::default()
+    };
+    for result in sized {
+        match result {
+            Ok(size_bytes) => estimate.size_bytes += size_bytes,
+            Err(failure) => estimate.failures.push(failure),
+        }
+    }
+    estimate.failures.sort_by(|a, b| a.path.cmp(&b.path));
+    estimate
+}
+
+#[derive(Debug)]
+pub enum CandidateOutcome {
+    Artifact(ArtifactRecord),
+    /// No enclosing git repo, so there is no ignore rule to trust.
+    OutsideRepo(DirStats),
+    /// Not an artifact of its repo: git does not ignore it, or it has a
+    /// risky name and nothing shows a build system made it.
+    NotIgnored {
+        repo_root: PathBuf,
+    },
+    /// A git or stat lookup failed; neither reported nor cleaned.
+    Failed(CandidateError),
+    /// The scan was canceled mid-walk, or the dir is gone since the last
+    /// scan.
+    Skipped,
+    /// Found but left unchecked: the scan's deadline had passed.
+    TimedOut,
+}
+
+impl CandidateOutcome {
+    /// Unreadable entries the candidate's size walk skipped over. A failed
+    /// candidate is one more warning, counted by whoever handles it.
+    pub fn warnings(&self) -> usize {
+        match self {
+            CandidateOutcome::Artifact(record) => record.stats.unreadable,
+            CandidateOutcome::OutsideRepo(stats) => stats.unreadable,
+            CandidateOutcome::NotIgnored { .. }
+            | CandidateOutcome::Failed(_)
+            | CandidateOutcome::Skipped
+            | CandidateOutcome::TimedOut => 0,
+        }
+    }
+
+    /// Subdirectories the size walk left out for living on another device.
+    pub fn other_device_dirs(&self) -> usize {
+        match self {
+            CandidateOutcome::Artifact(record) => record.stats.other_device,
+            CandidateOutcome::OutsideRepo(stats) => stats.other_device,
+            CandidateOutcome::NotIgnored { .. }
+            | CandidateOutcome::Failed(_)
+            | CandidateOutcome::Skipped
+            | CandidateOutcome::TimedOut => 0,
+        }
+    }
+}
+
+/// The step a candidate failed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateErrorKind {
+    /// `git check-ignore` failed, so whether git ignores it is unknown.
+    GitCheckIgnore,
+    /// Walking it for its size failed.
+    Stats,
+    /// Reading the repo's head commit failed; its artifacts are still
+    /// listed, only undated by commit.
+    GitHead,
+}
+
+impl CandidateErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CandidateErrorKind::GitCheckIgnore => "git check-ignore failed",
+            CandidateErrorKind::Stats => "stats calculation failed",
+            CandidateErrorKind::GitHead => "git head lookup failed",
+        }
+    }
+}
+
+/// A candidate that could not be checked or sized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateError {
+    pub path: PathBuf,
+    pub kind: CandidateErrorKind,
+    pub message: String,
+}
+
+impl CandidateError {
+    pub(crate) fn new(path: &Path, kind: CandidateErrorKind, err: &anyhow::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            kind,
+            message: format!("{err:#}"),
+        }
+    }
+}
+
+pub fn process_candidate(
+    path: &Path,
+    options: &ScanOptions,
+    errors: &WalkErrors,
+) -> CandidateOutcome {
+    process_candidate_with(path, options, errors, |_| {}, |_, _| {})
+}
+
+/// Like `process_candidate`, calling `on_sizing` right before an ignored
+/// candidate is walked for its size, usually the slow part, and
+/// `on_progress` with running totals while that walk takes a while.
+pub fn process_candidate_with<F, P>(
+    path: &Path,
+    options: &ScanOptions,
+    errors: &WalkErrors,
+    on_sizing: F,
+    on_progress: P,
+) -> CandidateOutcome
+where
+    F: Fn(&Path),
+    P: Fn(&Path, &DirStats) + Sync,
+{
+    if options.is_past_deadline() {
+        log_decision(
+            options,
+            path,
+            "found after the scan's deadline; not checked",
+        );
+        return CandidateOutcome::TimedOut;
+    }
+    let repo_root = match options.repo_ceilings.iter().find(|c| path.starts_with(c)) {
+        Some(ceiling) => crate::git::find_git_root_within(path, ceiling),
+        None => crate::git::find_git_root(path),
+    };
+    let Some(repo_root) = repo_root else {
+        log_decision(options, path, "outside any git repo; never cleaned");
+        return match dir_stats_with_progress(path, options, errors, |_| {}) {
+            Ok(stats) => CandidateOutcome::OutsideRepo(stats),
+            Err(err) => {
+                CandidateOutcome::Failed(CandidateError::new(path, CandidateErrorKind::Stats, &err))
+            }
+        };
+    };
+    if !options.trust_risky_names && is_unconfirmed(path) {
+        log_decision(
+            options,
+            path,
+            &format!(
+                "no build-system marker next to or inside it in repo {}; skipped",
+                repo_root.display()
+            ),
+        );
+        return CandidateOutcome::NotIgnored { repo_root };
+    }
+    let unverified = is_unverified(path);
+    if unverified && options.strict_detection {
+        log_decision(
+            options,
+            path,
+            &format!(
+                "no ecosystem marker for its name in repo {}; dropped (strict detection)",
+                repo_root.display()
+            ),
+        );
+        return CandidateOutcome::NotIgnored { repo_root };
+    }
+    let is_ignored = match is_git_ignored(&repo_root, path, &options.git) {
         Ok(is_ignored) => is_ignored,
         Err(err) => {
-            eprintln!(
-                "warn: git check-ignore failed: repo={repo_root:?} path={path:?} err={err:#}"
+            log_decision(
+                options,
+                path,
+                &format!(
+                    "git check-ignore failed in repo {}; skipped",
+                    repo_root.display()
+                ),
             );
-            return None;
+            return CandidateOutcome::Failed(CandidateError::new(
+                path,
+                CandidateErrorKind::GitCheckIgnore,
+                &err,
+            ));
         }
     };
     if !is_ignored {
-        return None;
+        log_decision(
+            options,
+            path,
+            &format!(
+                "not ignored (tracked or not matched by .gitignore) in repo {}; skipped",
+                repo_root.display()
+            ),
+        );
+        return CandidateOutcome::NotIgnored { repo_root };
     }
+    log_decision(
+        options,
+        path,
+        &format!("ignored in repo {}", repo_root.display()),
+    );
 
-    let stats = match dir_stats(path) {
+    on_sizing(path);
+    let walk = || {
+        let stats =
+            dir_stats_with_progress(path, options, errors, |stats| on_progress(path, stats))?;
+        // A partial size must not be cached or reported.
+        if options.is_cancelled() {
+            bail!("canceled");
+        }
+        Ok(stats)
+    };
+    let stats = match &options.cache {
+        Some(cache) => cache.dir_stats(path, walk),
+        None => walk(),
+    };
+    let stats = match stats {
         Ok(stats) => stats,
+        Err(_) if options.is_cancelled() => return CandidateOutcome::Skipped,
         Err(err) => {
-            eprintln!("warn: stats calculation failed: path={path:?} err={err:#}");
-            return None;
+            return CandidateOutcome::Failed(CandidateError::new(
+                path,
+                CandidateErrorKind::Stats,
+                &err,
+            ));
         }
     };
 
-    Some(ArtifactRecord {
+    CandidateOutcome::Artifact(ArtifactRecord {
         repo_root,
         path: path.to_path_buf(),
         stats,
+        unverified,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{git, init_repo, make_temp_dir};
+    use std::{ffi::OsString, fs, time::UNIX_EPOCH};
+
+    #[test]
+    fn counts_artifacts_outside_any_repo() {
+        let root = make_temp_dir("clean-my-code-report");
+        fs::create_dir_all(root.join("plain/target")).unwrap();
+        fs::write(root.join("plain/target/blob"), vec![0u8; 100]).unwrap();
+
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("target")).unwrap();
+        fs::create_dir_all(repo.join("dist")).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+        fs::write(repo.join("target/blob"), vec![0u8; 10]).unwrap();
+        fs::write(repo.join("dist/blob"), vec![0u8; 10]).unwrap();
+
+        let names = ScanOptions::new(["target", "dist"].into_iter().map(OsString::from).collect());
+        let roots = ScanRoots::canonicalize(std::slice::from_ref(&root)).unwrap();
+        let outcome = collect_reports(&roots, &names);
+
+        assert_eq!(outcome.reports.len(), 1);
+        assert_eq!(outcome.reports[0].artifacts.len(), 1);
+        assert_eq!(outcome.counters.repos_scanned, 1);
+        assert_eq!(outcome.counters.outside_repo_dirs, 1);
+        assert_eq!(outcome.counters.outside_repo_bytes, 100);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn estimate_sums_every_candidate_whether_ignored_or_not() {
+        let root = make_temp_dir("clean-my-code-report");
+        fs::create_dir_all(root.join("plain/target")).unwrap();
+        fs::write(root.join("plain/target/blob"), vec![0u8; 100]).unwrap();
+
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("target/debug")).unwrap();
+        fs::create_dir_all(repo.join("dist")).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+        fs::write(repo.join("target/blob"), vec![0u8; 10]).unwrap();
+        fs::write(repo.join("target/debug/blob"), vec![0u8; 20]).unwrap();
+        fs::write(repo.join("dist/blob"), vec![0u8; 5]).unwrap();
+
+        let names = ScanOptions::new(["target", "dist"].into_iter().map(OsString::from).collect());
+        let roots = ScanRoots::canonicalize(std::slice::from_ref(&root)).unwrap();
+        let estimate = estimate_candidates(&roots, &names);
+
+        // The unignored `dist` counts too: no git checks run.
+        assert_eq!(estimate.dirs, 3);
+        assert_eq!(estimate.size_bytes, 135);
+        assert!(estimate.failures.is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn repo_ceilings_keep_artifacts_attributed_within_the_root() {
+        let root = make_temp_dir("clean-my-code-report");
+        let mono = root.join("mono");
+        let app = mono.join("apps/web");
+        fs::create_dir_all(app.join("target")).unwrap();
+        init_repo(&mono);
+        fs::write(mono.join(".gitignore"), "target/\n").unwrap();
+        fs::write(app.join("target/blob"), vec![0u8; 10]).unwrap();
+
+        let roots = ScanRoots::canonicalize(std::slice::from_ref(&app)).unwrap();
+        let app = roots.iter().next().unwrap().to_path_buf();
+        let mut options = ScanOptions::new(HashSet::from([OsString::from("target")]));
+        let unclamped = collect_reports(&roots, &options);
+        options.repo_ceilings = vec![app.clone()];
+        let clamped = collect_reports(&roots, &options);
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(unclamped.reports.len(), 1);
+        assert_ne!(unclamped.reports[0].repo_root, app);
+        assert_eq!(clamped.reports.len(), 1);
+        assert_eq!(clamped.reports[0].repo_root, app);
+        assert_eq!(clamped.reports[0].total_size_bytes, 10);
+    }
+
+    #[test]
+    fn repos_without_commits_are_reported_and_aged_by_artifact_mtime() {
+        let root = make_temp_dir("clean-my-code-report");
+        let old = SystemTime::now()
+            - std::time::Duration::from_secs((STALE_AFTER_DAYS + 10) * 24 * 60 * 60);
+        for repo in ["committed", "fresh"] {
+            let repo = root.join(repo);
+            fs::create_dir_all(repo.join("target/deep")).unwrap();
+            init_repo(&repo);
+            fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+            fs::write(repo.join("target/deep/blob"), vec![0u8; 10]).unwrap();
+        }
+        git(
+            &root.join("committed"),
+            &["commit", "-q", "--allow-empty", "-m", "init"],
+        );
+        let fresh_target = root.join("fresh/target");
+        for path in [
+            fresh_target.join("deep/blob"),
+            fresh_target.join("deep"),
+            fresh_target.clone(),
+        ] {
+            fs::File::open(&path).unwrap().set_modified(old).unwrap();
+        }
+
+        let names = ScanOptions::new(HashSet::from([OsString::from("target")]));
+        let roots = ScanRoots::canonicalize(std::slice::from_ref(&root)).unwrap();
+        let outcome = collect_reports(&roots, &names);
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(outcome.counters.repos_scanned, 2);
+        let [committed, fresh] = outcome.reports.as_slice() else {
+            panic!("expected two reports: {:?}", outcome.reports);
+        };
+        assert!(committed.head.is_some());
+        // No commit to date the repo by, so it sorts last.
+        assert!(fresh.head.is_none());
+        assert!(fresh.repo_root.ends_with("fresh"));
+        assert_eq!(fresh.artifacts.len(), 1);
+        assert_eq!((fresh.total_size_bytes, fresh.total_file_count), (10, 1));
+        assert_eq!(fresh.newest_mtime, Some(old));
+        let now = SystemTime::now();
+        assert_eq!(
+            repo_age_days(fresh, now, AgeBasis::Modified),
+            Some(STALE_AFTER_DAYS + 10)
+        );
+        assert!(is_stale(fresh, now, AgeBasis::Modified));
+        assert!(!is_stale(committed, now, AgeBasis::Modified));
+    }
+
+    #[test]
+    fn scans_past_their_deadline_report_partial_results() {
+        let root = make_temp_dir("clean-my-code-report");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("target")).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+
+        let mut options = ScanOptions::new(HashSet::from([OsString::from("target")]));
+        let roots = ScanRoots::canonicalize(std::slice::from_ref(&root)).unwrap();
+        let full = collect_reports(&roots, &options);
+        options.deadline = Some(std::time::Instant::now());
+        let partial = collect_reports(&roots, &options);
+        let _ = fs::remove_dir_all(&root);
+
+        assert!(!full.counters.timed_out);
+        assert_eq!(full.reports.len(), 1);
+        assert!(partial.counters.timed_out);
+        assert!(partial.reports.is_empty());
+    }
+
+    #[test]
+    fn future_mtimes_count_as_just_touched() {
+        let now = SystemTime::now();
+        let future = now + Duration::from_secs(3 * 24 * 60 * 60);
+        let report = RepoReport {
+            repo_root: PathBuf::from("/ws/a"),
+            head: None,
+            artifacts: vec![ArtifactRecord {
+                repo_root: PathBuf::from("/ws/a"),
+                path: PathBuf::from("/ws/a/target"),
+                stats: DirStats {
+                    size_bytes: 10,
+                    newest_mtime: Some(future),
+                    ..DirStats::default()
+                },
+                unverified: false,
+            }],
+            total_size_bytes: 10,
+            total_file_count: 0,
+            newest_mtime: Some(future),
+            oldest_mtime: None,
+            newest_atime: None,
+            root_mtime: None,
+        };
+
+        assert_eq!(repo_age_days(&report, now, AgeBasis::Modified), Some(0));
+        assert!(!is_stale(&report, now, AgeBasis::Modified));
+        assert_eq!(stale_ratio(&report, now, AgeBasis::Modified), 0.0);
+        assert!(has_future_mtime(&report, now));
+        assert!(!has_future_mtime(&report, future));
+    }
+
+    #[test]
+    fn access_basis_counts_reads_and_falls_back_to_mtime() {
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs((STALE_AFTER_DAYS + 10) * 24 * 60 * 60);
+        let read = now - Duration::from_secs(2 * 24 * 60 * 60);
+        let mut report = RepoReport {
+            repo_root: PathBuf::from("/ws/a"),
+            head: None,
+            artifacts: vec![ArtifactRecord {
+                repo_root: PathBuf::from("/ws/a"),
+                path: PathBuf::from("/ws/a/target"),
+                stats: DirStats {
+                    size_bytes: 10,
+                    newest_mtime: Some(old),
+                    newest_atime: Some(read),
+                    ..DirStats::default()
+                },
+                unverified: false,
+            }],
+            total_size_bytes: 10,
+            total_file_count: 0,
+            newest_mtime: Some(old),
+            oldest_mtime: None,
+            newest_atime: Some(read),
+            root_mtime: None,
+        };
+
+        // Read two days ago, last written long before.
+        assert_eq!(
+            repo_age_days(&report, now, AgeBasis::Modified),
+            Some(STALE_AFTER_DAYS + 10)
+        );
+        assert!(is_stale(&report, now, AgeBasis::Modified));
+        assert_eq!(repo_age_days(&report, now, AgeBasis::Access), Some(2));
+        assert!(!is_stale(&report, now, AgeBasis::Access));
+        assert_eq!(stale_ratio(&report, now, AgeBasis::Access), 0.0);
+
+        // Without atimes, access is judged by mtime alone.
+        report.newest_atime = None;
+        report.artifacts[0].stats.newest_atime = None;
+        assert_eq!(
+            repo_age_days(&report, now, AgeBasis::Access),
+            Some(STALE_AFTER_DAYS + 10)
+        );
+        assert!(is_stale(&report, now, AgeBasis::Access));
+        assert_eq!(stale_ratio(&report, now, AgeBasis::Access), 1.0);
+    }
+
+    #[test]
+    fn incremental_rescan_reuses_unchanged_repos() {
+        let root = make_temp_dir("clean-my-code-report");
+        let repo = root.join("repo");
+        for i in 0..300 {
+            fs::create_dir_all(repo.join(format!("src/mod{i}"))).unwrap();
+        }
+        fs::create_dir_all(repo.join("target/debug")).unwrap();
+        fs::write(repo.join("target/debug/blob"), vec![0u8; 10]).unwrap();
+        let other = root.join("other");
+        fs::create_dir_all(other.join("dist")).unwrap();
+        for repo in [&repo, &other] {
+            init_repo(repo);
+            fs::write(repo.join(".gitignore"), "target/\ndist/\n").unwrap();
+        }
+
+        let options =
+            ScanOptions::new(["target", "dist"].into_iter().map(OsString::from).collect());
+        let roots = ScanRoots::canonicalize(std::slice::from_ref(&root)).unwrap();
+        let full = collect_reports(&roots, &options);
+        let again = collect_reports_incremental(&roots, &options, &full.reports);
+        let sizes = |outcome: &ScanOutcome| {
+            outcome
+                .reports
+                .iter()
+                .map(|r| {
+                    (
+                        r.repo_root.file_name().unwrap().to_owned(),
+                        r.total_size_bytes,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sizes(&again), sizes(&full));
+        assert!(full.counters.dirs_listed >= 300);
+        assert!(
+            again.counters.dirs_listed * 50 <= full.counters.dirs_listed,
+            "full: {}, incremental: {}",
+            full.counters.dirs_listed,
+            again.counters.dirs_listed
+        );
+
+        // A grown artifact is re-sized and a new repo is found.
+        fs::write(repo.join("target/more"), vec![0u8; 5]).unwrap();
+        let fresh = root.join("fresh");
+        fs::create_dir_all(fresh.join("target")).unwrap();
+        init_repo(&fresh);
+        fs::write(fresh.join(".gitignore"), "target/\n").unwrap();
+        fs::write(fresh.join("target/blob"), vec![0u8; 1]).unwrap();
+        fs::remove_dir_all(other.join("dist")).unwrap();
+        let later = collect_reports_incremental(&roots, &options, &again.reports);
+        let _ = fs::remove_dir_all(&root);
+
+        let mut later = sizes(&later);
+        later.sort();
+        assert_eq!(
+            later,
+            vec![(OsString::from("fresh"), 1), (OsString::from("repo"), 15)]
+        );
+    }
+
+    #[test]
+    fn incremental_rescan_of_a_repo_root_counts_artifacts_once() {
+        let repo = make_temp_dir("clean-my-code-report");
+        fs::create_dir_all(repo.join("target")).unwrap();
+        fs::write(repo.join("target/blob"), vec![0u8; 10]).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+
+        let options = ScanOptions::new(HashSet::from([OsString::from("target")]));
+        let roots = ScanRoots::canonicalize(std::slice::from_ref(&repo)).unwrap();
+        let full = collect_reports(&roots, &options);
+        let again = collect_reports_incremental(&roots, &options, &full.reports);
+        let _ = fs::remove_dir_all(&repo);
+
+        let totals = |outcome: &ScanOutcome| {
+            outcome
+                .reports
+                .iter()
+                .map(|r| (r.artifacts.len(), r.total_size_bytes))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(totals(&full), [(1, 10)]);
+        assert_eq!(totals(&again), [(1, 10)]);
+    }
+
+    #[test]
+    fn stale_ratio_counts_stale_artifact_bytes() {
+        let now = SystemTime::now();
+        let old = now - std::time::Duration::from_secs((STALE_AFTER_DAYS + 1) * 24 * 60 * 60);
+        let artifact = |name: &str, size_bytes, mtime| ArtifactRecord {
+            repo_root: PathBuf::from("/ws/a"),
+            path: PathBuf::from("/ws/a").join(name),
+            stats: DirStats {
+                size_bytes,
+                newest_mtime: Some(mtime),
+                file_count: 0,
+                unreadable: 0,
+                other_device: 0,
+                excluded: 0,
+                oldest_mtime: None,
+                newest_atime: None,
+                largest_files: Vec::new(),
+            },
+            unverified: false,
+        };
+        let mut report = RepoReport {
+            repo_root: PathBuf::from("/ws/a"),
+            head: None,
+            artifacts: vec![artifact("target", 75, old), artifact("dist", 25, now)],
+            total_size_bytes: 100,
+            total_file_count: 0,
+            newest_mtime: Some(now),
+            oldest_mtime: None,
+            newest_atime: None,
+            root_mtime: None,
+        };
+        assert_eq!(stale_size_bytes(&report, now, AgeBasis::Modified), 75);
+        assert_eq!(
+            format_percent(stale_ratio(&report, now, AgeBasis::Modified)),
+            "75%"
+        );
+
+        report.artifacts.clear();
+        report.total_size_bytes = 0;
+        assert_eq!(stale_ratio(&report, now, AgeBasis::Modified), 0.0);
+    }
+
+    #[test]
+    fn largest_consumers_line_names_the_top_repo_and_artifact_name() {
+        let artifact = |repo: &str, name: &str, size_bytes| ArtifactRecord {
+            repo_root: PathBuf::from(repo),
+            path: PathBuf::from(repo).join(name),
+            stats: DirStats {
+                size_bytes,
+                ..DirStats::default()
+            },
+            unverified: false,
+        };
+        let report = |repo: &str, artifacts: Vec<ArtifactRecord>| RepoReport {
+            repo_root: PathBuf::from(repo),
+            head: None,
+            total_size_bytes: artifacts.iter().map(|a| a.stats.size_bytes).sum(),
+            artifacts,
+            total_file_count: 0,
+            newest_mtime: None,
+            oldest_mtime: None,
+            newest_atime: None,
+            root_mtime: None,
+        };
+        let reports = vec![
+            report(
+                "/ws/a",
+                vec![
+                    artifact("/ws/a", "target", 40),
+                    artifact("/ws/a", "dist", 5),
+                ],
+            ),
+            report(
+                "/ws/b",
+                vec![
+                    artifact("/ws/b", "node_modules", 30),
+                    artifact("/ws/b/web", "node_modules", 30),
+                ],
+            ),
+        ];
+        let roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+
+        assert_eq!(
+            largest_consumers_line(&roots, &reports, None).as_deref(),
+            Some("Largest: b (60 B); node_modules dominates (57% of total)")
+        );
+        assert_eq!(largest_consumers_line(&roots, &[], None), None);
+    }
+
+    #[test]
+    fn walk_error_lines_cap_the_listed_paths() {
+        let roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let errors = (0..25)
+            .map(|i| WalkError {
+                path: PathBuf::from(format!("/ws/a/locked{i:02}")),
+                kind: std::io::ErrorKind::PermissionDenied,
+            })
+            .collect::<Vec<_>>();
+
+        assert!(walk_error_lines(&roots, &[], 0).is_empty());
+        let lines = walk_error_lines(&roots, &errors, 30);
+        assert_eq!(
+            lines[0],
+            "Unreadable: 30 (sizes below them are lower bounds)"
+        );
+        assert_eq!(lines[1], "  a/locked00: permission denied");
+        assert_eq!(lines.len(), 1 + WALK_ERRORS_SHOWN + 1);
+        assert_eq!(lines.last().unwrap(), "  ... and 10 more");
+    }
+
+    #[test]
+    fn failed_candidates_carry_their_path_and_kind() {
+        let root = make_temp_dir("clean-my-code-report");
+        // `.git` is there but is no repo, so git check-ignore fails.
+        let broken = root.join("broken");
+        fs::create_dir_all(broken.join(".git")).unwrap();
+        fs::create_dir_all(broken.join("target")).unwrap();
+        let repo = root.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        init_repo(&repo);
+        fs::write(repo.join(".gitignore"), "cache-out\n").unwrap();
+
+        let options = ScanOptions::default();
+        let errors = WalkErrors::default();
+        let kind = |outcome: CandidateOutcome| match outcome {
+            CandidateOutcome::Failed(failure) => Some((failure.path, failure.kind)),
+            _ => None,
+        };
+        // Ignored by git, but gone before it could be sized.
+        let in_repo = kind(process_candidate(
+            &repo.join("cache-out"),
+            &options,
+            &errors,
+        ));
+        let outside = kind(process_candidate(
+            &root.join("plain/target"),
+            &options,
+            &errors,
+        ));
+        let check_ignore = kind(process_candidate(&broken.join("target"), &options, &errors));
+        let record = |repo_root: &Path, path: PathBuf| ArtifactRecord {
+            repo_root: repo_root.to_path_buf(),
+            path,
+            stats: DirStats::default(),
+            unverified: false,
+        };
+        let reverified = reverify_artifact(
+            &record(&broken, broken.join("target")),
+            &options,
+            &WalkTally::default(),
+            &errors,
+        );
+        let gone = reverify_artifact(
+            &record(&repo, repo.join("cache-out")),
+            &options,
+            &WalkTally::default(),
+            &errors,
+        );
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(
+            in_repo,
+            Some((repo.join("cache-out"), CandidateErrorKind::Stats))
+        );
+        assert_eq!(
+            outside,
+            Some((root.join("plain/target"), CandidateErrorKind::Stats))
+        );
+        assert_eq!(
+            check_ignore,
+            Some((broken.join("target"), CandidateErrorKind::GitCheckIgnore))
+        );
+        assert!(matches!(
+            &reverified,
+            CandidateOutcome::Failed(failure) if failure.kind == CandidateErrorKind::GitCheckIgnore
+        ));
+        assert!(matches!(gone, CandidateOutcome::Skipped));
+
+        let outcome = build_outcome(
+            vec![reverified, gone],
+            WalkTally::default(),
+            WalkErrors::default(),
+            &GitOptions::default(),
+        );
+        assert_eq!(outcome.counters.warnings, 1);
+        assert_eq!(outcome.failures.len(), 1);
+        assert!(!outcome.failures[0].message.is_empty());
+    }
+
+    #[test]
+    fn failure_lines_name_each_path_and_step() {
+        let roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let failures = [
+            CandidateError {
+                path: PathBuf::from("/ws/a/target"),
+                kind: CandidateErrorKind::GitCheckIgnore,
+                message: "exit code 128".to_string(),
+            },
+            CandidateError {
+                path: PathBuf::from("/ws/b"),
+                kind: CandidateErrorKind::GitHead,
+                message: "bad object HEAD".to_string(),
+            },
+        ];
+
+        assert!(failure_lines(&roots, &[]).is_empty());
+        assert_eq!(
+            failure_lines(&roots, &failures),
+            [
+                "Failed: 1 candidates (left out, never cleaned)",
+                "  a/target: git check-ignore failed: exit code 128",
+                "Undated: 1 repos (still listed, sorted last)",
+                "  b: git head lookup failed: bad object HEAD",
+            ]
+        );
+    }
+
+    #[test]
+    fn artifacts_sort_by_size_or_by_last_use() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let record = |name: &str, size_bytes, newest_mtime| ArtifactRecord {
+            repo_root: PathBuf::from("/ws/a"),
+            path: PathBuf::from("/ws/a").join(name),
+            stats: DirStats {
+                size_bytes,
+                newest_mtime,
+                ..DirStats::default()
+            },
+            unverified: false,
+        };
+        let mut artifacts = vec![
+            record("undated", 30, None),
+            record("fresh", 10, Some(UNIX_EPOCH + 9 * day)),
+            record("old", 20, Some(UNIX_EPOCH + day)),
+        ];
+        let names = |artifacts: &[ArtifactRecord]| {
+            artifacts
+                .iter()
+                .map(|a| a.path.file_name().unwrap().to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        sort_artifacts(&mut artifacts, ArtifactOrder::Age(AgeBasis::Modified));
+        assert_eq!(names(&artifacts), ["old", "fresh", "undated"]);
+        sort_artifacts(&mut artifacts, ArtifactOrder::Size);
+        assert_eq!(names(&artifacts), ["undated", "old", "fresh"]);
+    }
+
+    #[test]
+    fn file_range_bounds_are_inclusive() {
+        let report = RepoReport {
+            repo_root: PathBuf::from("/ws/a"),
+            head: None,
+            artifacts: Vec::new(),
+            total_size_bytes: 0,
+            total_file_count: 500,
+            newest_mtime: None,
+            oldest_mtime: None,
+            newest_atime: None,
+            root_mtime: None,
+        };
+        assert!(is_within_file_range(&report, 0, None));
+        assert!(is_within_file_range(&report, 500, Some(500)));
+        assert!(!is_within_file_range(&report, 501, None));
+        assert!(!is_within_file_range(&report, 0, Some(499)));
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    ffi::OsString,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+
+use crate::format::{display_path, display_rel_path};
+
+/// The canonical roots being scanned, with no root nested inside another so
+/// nothing is counted twice.
+#[derive(Debug, Clone)]
+pub struct ScanRoots {
+    roots: Vec<PathBuf>,
+}
+
+impl ScanRoots {
+    pub fn canonicalize(paths: &[PathBuf]) -> Result<Self> {
+        let roots = paths
+            .iter()
+            .map(|path| canonicalize_root(path))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::from_canonical(roots))
+    }
+
+    pub fn from_canonical(mut roots: Vec<PathBuf>) -> Self {
+        // Path ordering is component-wise, so a root sorts before anything
+        // nested inside it.
+        roots.sort();
+        let mut kept: Vec<PathBuf> = Vec::new();
+        for root in roots {
+            if !kept.iter().any(|k| root.starts_with(k)) {
+                kept.push(root);
+            }
+        }
+        Self { roots: kept }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Path> {
+        self.roots.iter().map(PathBuf::as_path)
+    }
+
+    /// The dir directly below the root containing `path` that `path` lies
+    /// in, or that root itself when `path` is at most one level down.
+    #[cfg(feature = "tui")]
+    pub fn top_level_parent(&self, path: &Path) -> PathBuf {
+        let Some(root) = self.roots.iter().find(|root| path.starts_with(root)) else {
+            return path.parent().unwrap_or(path).to_path_buf();
+        };
+        let mut below = path
+            .strip_prefix(root)
+            .unwrap_or(Path::new(""))
+            .components();
+        match (below.next(), below.next()) {
+            (Some(first), Some(_)) => root.join(first),
+            _ => root.clone(),
+        }
+    }
+
+    /// Displays `path` relative to the root containing it. With several
+    /// roots, the root's own name is kept as a prefix to tell them apart.
+    pub fn display_rel(&self, path: &Path) -> String {
+        match self.roots.as_slice() {
+            [root] => display_rel_path(root, path),
+            roots => match roots.iter().find(|root| path.starts_with(root)) {
+                Some(root) => display_rel_path(root.parent().unwrap_or(root), path),
+                None => display_path(path),
+            },
+        }
+    }
+}
+
+/// Resolves a user-supplied root, explaining the common ways it can be wrong
+/// instead of surfacing a bare IO error.
+fn canonicalize_root(path: &Path) -> Result<PathBuf> {
+    let expanded = expand_path(path)?;
+    let path = expanded.as_path();
+    let link_meta = match std::fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            bail!("scan root does not exist: {}", path.display())
+        }
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("cannot access scan root: {}", path.display()));
+        }
+    };
+    if link_meta.file_type().is_symlink() && std::fs::metadata(path).is_err() {
+        match std::fs::read_link(path) {
+            Ok(target) => bail!(
+                "scan root is a broken symlink: {} -> {}",
+                path.display(),
+                target.display()
+            ),
+            Err(_) => bail!("scan root is a broken symlink: {}", path.display()),
+        }
+    }
+
+    let canonical = std::fs::canonicalize(path)
+        .with_context(|| format!("cannot resolve scan root: {}", path.display()))?;
+    if !canonical.is_dir() {
+        bail!("scan root is not a directory: {}", path.display());
+    }
+    Ok(canonical)
+}
+
+/// Expands a leading `~` / `~user` and `$VAR` / `${VAR}` references, for
+/// paths that reached us quoted and so were never expanded by the shell.
+/// Like the shell, anything that can't be expanded — an unset variable, an
+/// unknown user, `~` without `HOME` — is kept as written, since `~` and `$`
+/// are legal name bytes; only an unterminated `${` is an error.
+pub fn expand_path(path: &Path) -> Result<PathBuf> {
+    // Non-UTF-8 paths can't contain anything we'd expand in a portable way.
+    let Some(text) = path.to_str() else {
+        return Ok(path.to_path_buf());
+    };
+    expand_with(text, |name| std::env::var_os(name), user_home)
+}
+
+fn expand_with(
+    text: &str,
+    var: impl Fn(&str) -> Option<OsString>,
+    user_home: impl Fn(&str) -> Option<PathBuf>,
+) -> Result<PathBuf> {
+    let mut out = OsString::new();
+    let mut rest = text;
+
+    if let Some(after) = rest.strip_prefix('~') {
+        let end = after.find(['/', '\\']).unwrap_or(after.len());
+        let user = &after[..end];
+        let home = if user.is_empty() {
+            var("HOME")
+                .or_else(|| var("USERPROFILE"))
+                .map(PathBuf::from)
+        } else {
+            user_home(user)
+        };
+        // An unknown user or unset HOME is kept as written.
+        if let Some(home) = home {
+            out.push(home);
+            rest = &after[end..];
+        }
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        out.push(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, next) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(close) => (&braced[..close], &braced[close + 1..]),
+                None => bail!("unterminated `${{` in path: {text}"),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        match (!name.is_empty()).then(|| var(name)).flatten() {
+            Some(value) => out.push(value),
+            // A lone `$` or an unset variable is kept as written.
+            None => out.push(&rest[dollar..rest.len() - next.len()]),
+        }
+        rest = next;
+    }
+    out.push(rest);
+    Ok(PathBuf::from(out))
+}
+
+#[cfg(unix)]
+fn user_home(user: &str) -> Option<PathBuf> {
+    use std::{
+        ffi::{CStr, CString},
+        os::unix::ffi::OsStrExt,
+    };
+
+    let name = CString::new(user).ok()?;
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut found: *mut libc::passwd = std::ptr::null_mut();
+        // SAFETY: every pointer refers to memory we own for the duration of
+        // the call; on success `pwd`'s strings point into `buf`.
+        let (rc, pwd) = unsafe {
+            let mut pwd: libc::passwd = std::mem::zeroed();
+            let rc = libc::getpwnam_r(
+                name.as_ptr(),
+                &mut pwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut found,
+            );
+            (rc, pwd)
+        };
+        if rc == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || found.is_null() || pwd.pw_dir.is_null() {
+            return None;
+        }
+        // SAFETY: `pw_dir` is a NUL-terminated string inside `buf`.
+        let dir = unsafe { CStr::from_ptr(pwd.pw_dir) };
+        return Some(PathBuf::from(std::ffi::OsStr::from_bytes(dir.to_bytes())));
+    }
+}
+
+#[cfg(not(unix))]
+fn user_home(_user: &str) -> Option<PathBuf> {
+    None
+}
+
+impl fmt::Display for ScanRoots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, root) in self.roots.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", root.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::make_temp_dir;
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn top_level_parent_is_the_first_dir_below_the_root() {
+        let roots =
+            ScanRoots::from_canonical(vec![PathBuf::from("/home/me"), PathBuf::from("/srv")]);
+        let parent = |path: &str| roots.top_level_parent(Path::new(path));
+        assert_eq!(parent("/home/me/work/a/b"), PathBuf::from("/home/me/work"));
+        assert_eq!(parent("/home/me/work/a"), PathBuf::from("/home/me/work"));
+        assert_eq!(parent("/home/me/dotfiles"), PathBuf::from("/home/me"));
+        assert_eq!(parent("/home/me"), PathBuf::from("/home/me"));
+        assert_eq!(parent("/srv/oss/x"), PathBuf::from("/srv/oss"));
+    }
+
+    #[test]
+    fn drops_nested_roots_and_prefixes_displays() {
+        let roots = ScanRoots::from_canonical(vec![
+            PathBuf::from("/home/me/work/app"),
+            PathBuf::from("/home/me/personal"),
+            PathBuf::from("/home/me/work"),
+            PathBuf::from("/home/me/work"),
+        ]);
+        assert_eq!(
+            roots.iter().collect::<Vec<_>>(),
+            vec![Path::new("/home/me/personal"), Path::new("/home/me/work")]
+        );
+        assert_eq!(roots.to_string(), "/home/me/personal, /home/me/work");
+        assert_eq!(
+            roots.display_rel(Path::new("/home/me/work/app/target")),
+            "work/app/target"
+        );
+
+        let single = ScanRoots::from_canonical(vec![PathBuf::from("/home/me/work")]);
+        assert_eq!(single.display_rel(Path::new("/home/me/work/app")), "app");
+    }
+
+    #[test]
+    fn expands_tilde_and_env_vars() {
+        let var = |name: &str| match name {
+            "HOME" => Some(OsString::from("/home/me")),
+            "WS" => Some(OsString::from("work")),
+            _ => None,
+        };
+        let user_home = |user: &str| (user == "bob").then(|| PathBuf::from("/home/bob"));
+        let expand = |text| expand_with(text, var, user_home);
+
+        assert_eq!(expand("~").unwrap(), Path::new("/home/me"));
+        assert_eq!(expand("~/$WS/app").unwrap(), Path::new("/home/me/work/app"));
+        assert_eq!(expand("~bob/${WS}x").unwrap(), Path::new("/home/bob/workx"));
+        assert_eq!(expand("a~/b$").unwrap(), Path::new("a~/b$"));
+        assert_eq!(expand("~alice/x").unwrap(), Path::new("~alice/x"));
+        assert_eq!(expand("$MISSING/x").unwrap(), Path::new("$MISSING/x"));
+        assert_eq!(
+            expand("a/${MISSING}-$WS").unwrap(),
+            Path::new("a/${MISSING}-work")
+        );
+        assert!(expand("${WS").is_err());
+
+        let no_home = expand_with("~/x", |_: &str| None, user_home);
+        assert_eq!(no_home.unwrap(), Path::new("~/x"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn looks_up_home_dirs_in_the_user_database() {
+        assert!(user_home("root").is_some());
+        assert_eq!(user_home("no-such-user-clean-my-code"), None);
+    }
+
+    #[test]
+    fn explains_unusable_roots() {
+        let dir = make_temp_dir("clean-my-code-roots");
+        let file = dir.join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        let err = ScanRoots::canonicalize(&[dir.join("missing")]).unwrap_err();
+        assert!(
+            err.to_string().starts_with("scan root does not exist: "),
+            "{err}"
+        );
+        let err = ScanRoots::canonicalize(std::slice::from_ref(&file)).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("scan root is not a directory: "),
+            "{err}"
+        );
+
+        #[cfg(unix)]
+        {
+            let link = dir.join("dangling");
+            std::os::unix::fs::symlink(dir.join("gone"), &link).unwrap();
+            let err = ScanRoots::canonicalize(&[link]).unwrap_err();
+            assert!(
+                err.to_string()
+                    .starts_with("scan root is a broken symlink: "),
+                "{err}"
+            );
+            assert!(err.to_string().ends_with("gone"), "{err}");
+        }
+
+        assert!(ScanRoots::canonicalize(std::slice::from_ref(&dir)).is_ok());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
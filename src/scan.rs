@@ -1,92 +1,702 @@
 use std::{
-    collections::HashSet,
-    ffi::OsString,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    ffi::{OsStr, OsString},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
-    time::SystemTime,
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use regex::Regex;
 
-#[derive(Debug, Default, Clone, Copy)]
+use crate::{
+    cache::ScanCache,
+    exclude::{ExcludeSet, glob_to_regex},
+    git::GitOptions,
+    mounts::is_skipped_mount,
+};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct DirStats {
     pub size_bytes: u64,
     pub newest_mtime: Option<SystemTime>,
+    /// The oldest mtime among the files; dirs are left out since adding or
+    /// removing an entry bumps theirs.
+    pub oldest_mtime: Option<SystemTime>,
+    /// The newest access time among the files, where the platform reports
+    /// one. Only as good as the mount's atime updates.
+    pub newest_atime: Option<SystemTime>,
+    /// Regular files counted in `size_bytes`.
+    pub file_count: u64,
+    /// Entries that could not be read and are missing from the totals.
+    pub unreadable: usize,
+    /// Subdirectories left out for living on another device.
+    pub other_device: usize,
+    /// Entries left out of the totals because an exclude pattern matched
+    /// them; deleting the dir would take them with it.
+    pub excluded: usize,
+    /// The largest files inside, biggest first, when
+    /// `ScanOptions::largest_files` asked for any. Only `dir_stats` fills
+    /// this in; merged totals leave it alone.
+    pub largest_files: Vec<(PathBuf, u64)>,
 }
 
-pub fn scan_artifact_dirs(root: &Path, artifact_dir_names: &HashSet<OsString>) -> Vec<PathBuf> {
-    let results: Arc<Mutex<Vec<PathBuf>>> = Arc::new(Mutex::new(Vec::new()));
-    let root_is_git = has_dot_git(root);
+/// What a scan looks for. Filters that shape the traversal live here so they
+/// reach every walker without changing its signature.
+#[derive(Debug, Default, Clone)]
+pub struct ScanOptions {
+    /// Which directory names are artifact candidates.
+    pub artifacts: ArtifactMatcher,
+    /// How many levels below a scan root candidates may sit; a root's
+    /// children are at depth 1. `None` means unlimited.
+    pub max_depth: Option<usize>,
+    /// Paths the scan neither reports nor descends into.
+    pub excludes: ExcludeSet,
+    /// Stay on the device of the scan root (or of the artifact being
+    /// sized), like `du -x`. Bind mounts of the same device are still
+    /// entered. A no-op where `device_id` knows no devices.
+    pub same_device: bool,
+    /// Mount points neither walk enters, e.g. network filesystems.
+    pub skip_mounts: Vec<PathBuf>,
+    /// Repo roots the discovery walk does not enter, such as the unchanged
+    /// repos an incremental rescan re-verifies in place. Sizing still walks
+    /// them, and a scan root is walked even when listed here.
+    pub skip_repos: Vec<PathBuf>,
+    /// Enter symlinked directories (and size symlinked files) instead of
+    /// skipping them. Each directory is still walked at most once.
+    pub follow_symlinks: bool,
+    /// Sizes from earlier runs to reuse for unchanged artifact dirs.
+    pub cache: Option<Arc<ScanCache>>,
+    /// Once set, the walks stop where they are and return what they have,
+    /// so a giant dir can't hold up a canceled scan.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Scan roots that count as repos: a candidate below one is attributed
+    /// to a repo no higher than that root, and to the root itself when the
+    /// root is a subdir of a bigger repo.
+    pub repo_ceilings: Vec<PathBuf>,
+    /// Report `build`, `out`, `obj` and `bin` candidates on the ignore
+    /// check alone, without looking for a build-system marker next to or
+    /// inside them.
+    pub trust_risky_names: bool,
+    /// Drop candidates lacking the marker of the ecosystem behind their
+    /// name (a `target` without a `Cargo.toml`) instead of reporting them
+    /// as unverified.
+    pub strict_detection: bool,
+    /// Skip a dir outside any repo once no `.git` turns up within this many
+    /// levels below it, instead of walking the whole tree. `None` walks
+    /// such trees in full, so repos nested deeper are still found.
+    pub prune_outside_repos: Option<usize>,
+    /// How many of its largest files `dir_stats` keeps for each dir it
+    /// sizes. `0` keeps none and skips the bookkeeping entirely.
+    pub largest_files: usize,
+    /// Past this point the scan lists no new dirs and starts sizing no new
+    /// candidates; size walks already running still finish, so every
+    /// reported size is complete even when the set of artifacts is not.
+    pub deadline: Option<Instant>,
+    /// Shared by every walker of every scan run with these options (or a
+    /// clone of them).
+    pub open_dirs: Arc<DirLimit>,
+    /// How the scan (and a clean run with these options) calls `git`.
+    pub git: GitOptions,
+    /// Log every candidate's ignore decision to stderr. Only for the
+    /// line-oriented commands; the TUI owns the terminal.
+    pub verbose: bool,
+}
 
-    rayon::scope(|scope| {
-        scan_dir(
-            scope,
-            root.to_path_buf(),
-            artifact_dir_names,
-            Arc::clone(&results),
-            root_is_git,
-        );
-    });
+impl ScanOptions {
+    pub fn new(artifact_dir_names: HashSet<OsString>) -> Self {
+        Self {
+            artifacts: ArtifactMatcher::new(artifact_dir_names),
+            max_depth: None,
+            excludes: ExcludeSet::default(),
+            same_device: false,
+            skip_mounts: Vec::new(),
+            skip_repos: Vec::new(),
+            follow_symlinks: false,
+            cache: None,
+            cancel: None,
+            repo_ceilings: Vec::new(),
+            trust_risky_names: false,
+            strict_detection: false,
+            prune_outside_repos: None,
+            largest_files: 0,
+            deadline: None,
+            open_dirs: Arc::default(),
+            git: GitOptions::default(),
+            verbose: false,
+        }
+    }
 
-    let mut results = match Arc::try_unwrap(results) {
-        Ok(mutex) => mutex.into_inner().unwrap_or_default(),
-        Err(arc) => match arc.lock() {
-            Ok(guard) => (*guard).clone(),
-            Err(poisoned) => (*poisoned.into_inner()).clone(),
-        },
-    };
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    pub fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Whether discovery stays out of `dir`: a skipped mount or repo.
+    fn skips_dir(&self, dir: &Path) -> bool {
+        is_skipped_mount(&self.skip_mounts, dir) || self.skip_repos.iter().any(|repo| repo == dir)
+    }
+}
+
+/// Decides which directories are artifact candidates: an exact-name set,
+/// checked first, plus patterns and trailing paths tried only when the set
+/// misses. With neither that is a single hash lookup, and no check
+/// allocates.
+///
+/// The walk stops at the first match on the way down, so an artifact is
+/// never reported together with anything inside it: with both `.build`
+/// and `.build/checkouts` configured only `.build` is.
+#[derive(Debug, Default, Clone)]
+pub struct ArtifactMatcher {
+    pub names: HashSet<OsString>,
+    /// Matched against a directory's own name, never its path.
+    pub patterns: Vec<NamePattern>,
+    /// Multi-component entries like `.build/checkouts`, matched against the
+    /// last components of a directory's path.
+    pub trailing: Vec<PathBuf>,
+}
+
+/// A pattern for `ArtifactMatcher`.
+#[derive(Debug, Clone)]
+pub enum NamePattern {
+    /// A shell-style glob over the whole name, matched on its raw bytes so
+    /// names that aren't UTF-8 can match too; see `NamePattern::glob`.
+    Glob(regex::bytes::Regex),
+    /// Matches anywhere in the name unless anchored. Names that aren't
+    /// valid UTF-8 never match.
+    Regex(Regex),
+}
+
+impl ArtifactMatcher {
+    /// Entries containing a `/` become `trailing` paths; the rest are
+    /// exact names.
+    pub fn new(names: HashSet<OsString>) -> Self {
+        let (trailing, names): (HashSet<_>, HashSet<_>) = names
+            .into_iter()
+            .partition(|name| name.as_encoded_bytes().contains(&b'/'));
+        let mut trailing = trailing
+            .into_iter()
+            .map(PathBuf::from)
+            .filter(|path| path.components().next().is_some())
+            .collect::<Vec<_>>();
+        trailing.sort();
+        Self {
+            names,
+            patterns: Vec::new(),
+            trailing,
+        }
+    }
+
+    /// Whether a directory called `name` matches by name alone.
+    pub fn matches(&self, name: &OsStr) -> bool {
+        if self.names.contains(name) {
+            return true;
+        }
+        self.patterns.iter().any(|pattern| pattern.matches(name))
+    }
+
+    /// Whether the directory at `path` matches, by name or trailing path.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        path.file_name().is_some_and(|name| self.matches(name))
+            || self
+                .trailing
+                .iter()
+                .any(|trailing| path.ends_with(trailing))
+    }
+
+    /// Whether anything at all would be matched.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.patterns.is_empty() && self.trailing.is_empty()
+    }
+
+    /// Names and trailing paths together, as `new` takes them.
+    pub fn entries(&self) -> HashSet<OsString> {
+        self.names
+            .iter()
+            .cloned()
+            .chain(
+                self.trailing
+                    .iter()
+                    .map(|path| path.clone().into_os_string()),
+            )
+            .collect()
+    }
+}
+
+impl NamePattern {
+    /// Compiles a glob over one directory name: `*` matches any run of
+    /// characters, `?` any single one and `[...]` a character class.
+    pub fn glob(pattern: &str) -> Result<Self> {
+        if pattern.contains('/') {
+            bail!("artifact name pattern must not contain `/`: {pattern:?}");
+        }
+        // Within one name `**` is just `*`. A wildcard takes a whole
+        // character where the name is UTF-8 and a single byte where not.
+        let source = glob_to_regex(&pattern.replace("**", "*"))
+            .replace("[^/]", r"(?:[^/]|(?-u:[\x80-\xFF]))");
+        let source = format!("^{source}$");
+        let re = regex::bytes::Regex::new(&source)
+            .with_context(|| format!("invalid artifact name pattern: {pattern:?}"))?;
+        Ok(Self::Glob(re))
+    }
+
+    pub fn matches(&self, name: &OsStr) -> bool {
+        match self {
+            Self::Glob(re) => re.is_match(name.as_encoded_bytes()),
+            Self::Regex(re) => name.to_str().is_some_and(|name| re.is_match(name)),
+        }
+    }
+}
+
+/// Most walk errors kept with their path; later ones are only counted.
+const MAX_WALK_ERRORS: usize = 100;
+
+/// A directory or entry a walker could not read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkError {
+    pub path: PathBuf,
+    pub kind: std::io::ErrorKind,
+}
+
+/// Errors hit by the walkers: the first `MAX_WALK_ERRORS` with their path,
+/// plus how many there were in all.
+#[derive(Debug, Default)]
+pub struct WalkErrors {
+    kept: Mutex<Vec<WalkError>>,
+    total: AtomicUsize,
+}
+
+impl WalkErrors {
+    pub fn record(&self, path: &Path, err: &std::io::Error) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        let mut kept = match self.kept.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if kept.len() < MAX_WALK_ERRORS {
+            kept.push(WalkError {
+                path: path.to_path_buf(),
+                kind: err.kind(),
+            });
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// The kept errors, sorted by path.
+    pub fn into_kept(self) -> Vec<WalkError> {
+        let mut kept = self.kept.into_inner().unwrap_or_else(|p| p.into_inner());
+        kept.sort_by(|a, b| a.path.cmp(&b.path));
+        kept
+    }
+}
+
+/// What the walkers of one `scan_artifact_dirs` call skipped over.
+#[derive(Debug, Default)]
+pub struct WalkTally {
+    /// Directories or entries that could not be read.
+    pub errors: WalkErrors,
+    /// Directories left out for living on another device.
+    pub other_device: AtomicUsize,
+    /// Directories listed, readable or not.
+    pub dirs_listed: AtomicUsize,
+    /// The walk stopped at `ScanOptions::deadline` with dirs left unlisted.
+    pub timed_out: AtomicBool,
+}
+
+/// Finds candidate dirs under `root`. Directories or entries that cannot be
+/// read, or that `options` keeps us out of, are skipped and counted in
+/// `tally`.
+pub fn scan_artifact_dirs(root: &Path, options: &ScanOptions, tally: &WalkTally) -> Vec<PathBuf> {
+    let results = Mutex::new(Vec::new());
+    scan_artifact_dirs_streaming(root, options, tally, |path| {
+        lock_paths(&results).push(path);
+    });
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
     results.sort();
     results.dedup();
     results
 }
 
-pub fn dir_stats(root: &Path) -> Result<DirStats> {
-    let meta = std::fs::symlink_metadata(root)
-        .with_context(|| format!("failed to read metadata: {root:?}"))?;
+/// Like `scan_artifact_dirs`, but hands each candidate to `sink` as soon as
+/// it is found, from whichever walker thread found it, in no particular
+/// order. Returns once the walk is done.
+pub fn scan_artifact_dirs_streaming<F>(
+    root: &Path,
+    options: &ScanOptions,
+    tally: &WalkTally,
+    sink: F,
+) where
+    F: Fn(PathBuf) + Sync,
+{
+    // Pruning must not mistake a root inside a repo for one outside any.
+    let root_is_git = has_dot_git(root)
+        || options.repo_ceilings.iter().any(|c| c == root)
+        || (options.prune_outside_repos.is_some() && crate::git::find_git_root(root).is_some());
+    let walk = ScanWalk {
+        options,
+        tally,
+        sink: &sink,
+        root_device: options.same_device.then(|| device_id(root)).flatten(),
+        follow: options.follow_symlinks.then(FollowLinks::default),
+    };
+    let walk = &walk;
+    if let Some(follow) = &walk.follow {
+        follow.first_visit(root);
+    }
+
+    rayon::scope(|scope| {
+        scan_dir(scope, root.to_path_buf(), walk, root_is_git, 0);
+    });
+
+    // Links are entered only after the plain walk, so a directory reachable
+    // both ways is reported under its real path.
+    if let Some(follow) = &walk.follow {
+        loop {
+            let links = follow.take_pending();
+            if links.is_empty() || options.is_cancelled() || walk.out_of_time() {
+                break;
+            }
+            rayon::scope(|scope| {
+                for link in links {
+                    if !follow.first_visit(&link.path) {
+                        continue;
+                    }
+                    let Some(file_name) = link.path.file_name().map(OsStr::to_os_string) else {
+                        continue;
+                    };
+                    visit_dir(
+                        scope,
+                        walk,
+                        link.path,
+                        &file_name,
+                        link.in_git_repo,
+                        link.depth,
+                    );
+                }
+            });
+        }
+    }
+}
+
+fn lock_paths(paths: &Mutex<Vec<PathBuf>>) -> std::sync::MutexGuard<'_, Vec<PathBuf>> {
+    match paths.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Caps how many directories the walkers list at once across all threads,
+/// so a wide tree can't exhaust a low open-file limit.
+#[derive(Debug, Default)]
+pub struct DirLimit {
+    state: Mutex<DirLimitState>,
+    available: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct DirLimitState {
+    /// `0` means unlimited.
+    limit: usize,
+    open: usize,
+}
+
+impl DirLimit {
+    /// At most `limit` dirs open at once; `0` means unlimited.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(DirLimitState { limit, open: 0 }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Waits for a free slot. Never acquire a permit while holding another.
+    fn acquire(&self) -> DirPermit<'_> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while state.limit > 0 && state.open >= state.limit {
+            state = self
+                .available
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        state.open += 1;
+        DirPermit(self)
+    }
+}
+
+/// Held while a directory handle is open; releases its slot on drop.
+struct DirPermit<'a>(&'a DirLimit);
+
+impl Drop for DirPermit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.open -= 1;
+        self.0.available.notify_one();
+    }
+}
+
+/// How often `dir_stats_with_progress` reports running totals at most.
+const SIZING_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Files a walker sizes before publishing its partial totals, so a single
+/// huge directory still shows progress.
+const SIZING_FLUSH_FILES: u64 = 4096;
+
+/// Total size, file count and newest mtime of everything under `root`,
+/// walked in parallel on the current rayon pool.
+pub fn dir_stats(root: &Path, options: &ScanOptions) -> Result<DirStats> {
+    dir_stats_with_progress(root, options, &WalkErrors::default(), |_| {})
+}
+
+/// Like `dir_stats`, recording what could not be read in `errors` and
+/// calling `on_progress` with the running totals every
+/// `SIZING_PROGRESS_INTERVAL` or so while the walk is in flight.
+pub fn dir_stats_with_progress<F>(
+    root: &Path,
+    options: &ScanOptions,
+    errors: &WalkErrors,
+    on_progress: F,
+) -> Result<DirStats>
+where
+    F: Fn(&DirStats) + Sync,
+{
+    let meta = if options.follow_symlinks {
+        std::fs::metadata(root)
+    } else {
+        std::fs::symlink_metadata(root)
+    }
+    .with_context(|| format!("failed to read metadata: {root:?}"))?;
 
     if meta.file_type().is_symlink() {
         return Ok(DirStats::default());
     }
 
     if meta.is_file() {
-        return Ok(DirStats {
-            size_bytes: meta.len(),
-            newest_mtime: meta.modified().ok(),
-        });
+        let mut stats = DirStats::default();
+        stats.add_file(&meta);
+        return Ok(stats);
     }
 
     if !meta.is_dir() {
         return Ok(DirStats::default());
     }
 
-    let global: Arc<Mutex<DirStats>> = Arc::new(Mutex::new(DirStats {
-        size_bytes: 0,
+    let totals = ThreadTotals::new();
+    totals.add(DirStats {
         newest_mtime: meta.modified().ok(),
-    }));
-
-    rayon::scope(|scope| walk_dir_stats(scope, root.to_path_buf(), Arc::clone(&global)));
+        ..DirStats::default()
+    });
 
-    let stats = match global.lock() {
-        Ok(guard) => *guard,
-        Err(poisoned) => *poisoned.into_inner(),
+    let progress = SizingProgress {
+        totals,
+        on_progress: &on_progress,
+        last: Mutex::new(Instant::now()),
+        root_device: options.same_device.then(|| device_id(root)).flatten(),
+        skip_mounts: &options.skip_mounts,
+        excludes: &options.excludes,
+        follow: options.follow_symlinks.then(FollowLinks::default),
+        largest: (options.largest_files > 0)
+            .then(|| Mutex::new(LargestFiles::new(options.largest_files))),
+        errors,
+        cancel: options.cancel.as_deref(),
+        open_dirs: &options.open_dirs,
     };
+    let progress = &progress;
+    if let Some(follow) = &progress.follow {
+        follow.first_visit(root);
+    }
+    rayon::scope(|scope| walk_dir_stats(scope, root.to_path_buf(), progress));
+
+    if let Some(follow) = &progress.follow {
+        loop {
+            let links = follow.take_pending();
+            if links.is_empty() || progress.is_cancelled() {
+                break;
+            }
+            rayon::scope(|scope| {
+                for link in links {
+                    if follow.first_visit(&link) {
+                        scope.spawn(move |scope| walk_dir_stats(scope, link, progress));
+                    }
+                }
+            });
+        }
+    }
 
+    let mut stats = progress.totals.sum();
+    if let Some(largest) = &progress.largest {
+        stats.largest_files = lock_largest(largest).take_sorted();
+    }
     Ok(stats)
 }
 
+/// The `k` largest files seen so far, smallest on top of the heap so it is
+/// the one to drop.
+struct LargestFiles {
+    k: usize,
+    heap: BinaryHeap<Reverse<(u64, PathBuf)>>,
+}
+
+impl LargestFiles {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    /// Keeps `path` if it is among the `k` largest; the path is only
+    /// copied when it is.
+    fn offer(&mut self, size: u64, path: &Path) {
+        if self.heap.len() == self.k
+            && self
+                .heap
+                .peek()
+                .is_some_and(|Reverse((smallest, _))| size <= *smallest)
+        {
+            return;
+        }
+        self.heap.push(Reverse((size, path.to_path_buf())));
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    fn merge(&mut self, other: LargestFiles) {
+        for Reverse((size, path)) in other.heap {
+            self.offer(size, &path);
+        }
+    }
+
+    /// Biggest first, ties by path.
+    fn take_sorted(&mut self) -> Vec<(PathBuf, u64)> {
+        let mut files = std::mem::take(&mut self.heap)
+            .into_iter()
+            .map(|Reverse((size, path))| (path, size))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        files
+    }
+}
+
+fn lock_largest(largest: &Mutex<LargestFiles>) -> std::sync::MutexGuard<'_, LargestFiles> {
+    largest.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Shared by every walker of one `dir_stats_with_progress` call: the
+/// running totals, the progress rate limiter, the device to stay on and
+/// the links to follow.
+struct SizingProgress<'a> {
+    totals: ThreadTotals,
+    on_progress: &'a (dyn Fn(&DirStats) + Sync),
+    last: Mutex<Instant>,
+    root_device: Option<u64>,
+    skip_mounts: &'a [PathBuf],
+    excludes: &'a ExcludeSet,
+    follow: Option<FollowLinks<PathBuf>>,
+    /// Shared by the walkers, each of which merges in its own dir's
+    /// largest files once done with it. `None` when none are wanted.
+    largest: Option<Mutex<LargestFiles>>,
+    errors: &'a WalkErrors,
+    cancel: Option<&'a AtomicBool>,
+    open_dirs: &'a DirLimit,
+}
+
+impl SizingProgress<'_> {
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Adds `local` to the totals and reports them if the last report is
+    /// old enough. Walkers that lose the race just skip reporting.
+    fn flush(&self, local: DirStats) {
+        self.totals.add(local);
+        let Ok(mut last) = self.last.try_lock() else {
+            return;
+        };
+        if last.elapsed() < SIZING_PROGRESS_INTERVAL {
+            return;
+        }
+        *last = Instant::now();
+        drop(last);
+        (self.on_progress)(&self.totals.sum());
+    }
+}
+
+/// Running totals with one slot per rayon worker, so a walker only ever
+/// locks its own thread's slot; a single shared total was a hot lock on
+/// many cores. Slots are summed for progress reports and at the end.
+struct ThreadTotals {
+    /// Indexed by `rayon::current_thread_index`; the extra last slot takes
+    /// adds from outside the pool.
+    slots: Vec<TotalsSlot>,
+}
+
+/// Padded to its own cache line so neighbouring slots don't false-share.
+#[derive(Default)]
+#[repr(align(128))]
+struct TotalsSlot(Mutex<DirStats>);
+
+impl ThreadTotals {
+    fn new() -> Self {
+        Self {
+            slots: (0..=rayon::current_num_threads())
+                .map(|_| TotalsSlot::default())
+                .collect(),
+        }
+    }
+
+    fn add(&self, stats: DirStats) {
+        let outside = self.slots.len() - 1;
+        let index = rayon::current_thread_index().map_or(outside, |i| i.min(outside));
+        lock_stats(&self.slots[index].0).merge(stats);
+    }
+
+    fn sum(&self) -> DirStats {
+        let mut total = DirStats::default();
+        for slot in &self.slots {
+            total.merge(lock_stats(&slot.0).clone());
+        }
+        total
+    }
+}
+
 fn walk_dir_stats<'scope>(
     scope: &rayon::Scope<'scope>,
     dir: PathBuf,
-    global: Arc<Mutex<DirStats>>,
+    progress: &'scope SizingProgress<'scope>,
 ) {
+    if progress.is_cancelled() {
+        return;
+    }
+    let mut local = DirStats::default();
+    let mut largest = progress
+        .largest
+        .as_ref()
+        .map(|shared| LargestFiles::new(lock_largest(shared).k));
+    // Held for the whole listing; the loop below only spawns subdirs, so
+    // it never waits on another permit.
+    let permit = progress.open_dirs.acquire();
     let entries = match std::fs::read_dir(&dir) {
         Ok(entries) => entries,
-        Err(_) => return,
-    };
-
-    let mut local = DirStats {
-        size_bytes: 0,
-        newest_mtime: None,
+        Err(err) => {
+            progress.errors.record(&dir, &err);
+            local.unreadable = 1;
+            progress.flush(local);
+            return;
+        }
     };
 
     if let Ok(meta) = std::fs::symlink_metadata(&dir)
@@ -96,116 +706,366 @@ fn walk_dir_stats<'scope>(
     }
 
     for entry in entries {
+        if progress.is_cancelled() {
+            break;
+        }
         let entry = match entry {
             Ok(entry) => entry,
-            Err(_) => continue,
+            Err(err) => {
+                progress.errors.record(&dir, &err);
+                local.unreadable += 1;
+                continue;
+            }
         };
         let file_type = match entry.file_type() {
             Ok(file_type) => file_type,
-            Err(_) => continue,
+            Err(err) => {
+                progress.errors.record(&entry.path(), &err);
+                local.unreadable += 1;
+                continue;
+            }
         };
 
+        let path = entry.path();
+        if progress.excludes.is_excluded(&path) {
+            local.excluded += 1;
+            continue;
+        }
         if file_type.is_symlink() {
+            let Some(follow) = &progress.follow else {
+                continue;
+            };
+            match std::fs::metadata(&path) {
+                Ok(meta) if meta.is_dir() => follow.defer(path),
+                Ok(meta) if meta.is_file() => {
+                    local.add_file(&meta);
+                    if let Some(largest) = &mut largest {
+                        largest.offer(meta.len(), &path);
+                    }
+                }
+                // Dangling links have nothing to count.
+                _ => {}
+            }
             continue;
         }
 
-        let path = entry.path();
         if file_type.is_dir() {
-            let global = Arc::clone(&global);
-            scope.spawn(move |scope| walk_dir_stats(scope, path, global));
+            if is_skipped_mount(progress.skip_mounts, &path) {
+                continue;
+            }
+            if is_on_other_device(progress.root_device, &path) {
+                local.other_device += 1;
+                continue;
+            }
+            if let Some(follow) = &progress.follow
+                && !follow.first_visit(&path)
+            {
+                continue;
+            }
+            scope.spawn(move |scope| walk_dir_stats(scope, path, progress));
             continue;
         }
 
         if file_type.is_file() {
             let meta = match entry.metadata() {
                 Ok(meta) => meta,
-                Err(_) => continue,
+                Err(err) => {
+                    progress.errors.record(&path, &err);
+                    local.unreadable += 1;
+                    continue;
+                }
             };
-            local.size_bytes = local.size_bytes.saturating_add(meta.len());
-            local.merge_mtime(meta.modified().ok());
+            local.add_file(&meta);
+            if let Some(largest) = &mut largest {
+                largest.offer(meta.len(), &path);
+            }
+            if local.file_count >= SIZING_FLUSH_FILES {
+                progress.flush(std::mem::take(&mut local));
+            }
         }
     }
+    drop(permit);
+
+    if let (Some(largest), Some(shared)) = (largest, &progress.largest)
+        && !largest.heap.is_empty()
+    {
+        lock_largest(shared).merge(largest);
+    }
+    progress.flush(local);
+}
 
-    let mut global_guard = match global.lock() {
+fn lock_stats(global: &Mutex<DirStats>) -> std::sync::MutexGuard<'_, DirStats> {
+    match global.lock() {
         Ok(guard) => guard,
         Err(poisoned) => poisoned.into_inner(),
-    };
-    global_guard.merge(local);
+    }
+}
+
+/// Shared by every walker of one `scan_artifact_dirs` call.
+struct ScanWalk<'a> {
+    options: &'a ScanOptions,
+    tally: &'a WalkTally,
+    sink: &'a (dyn Fn(PathBuf) + Sync),
+    root_device: Option<u64>,
+    follow: Option<FollowLinks<PendingLink>>,
+}
+
+impl ScanWalk<'_> {
+    /// Whether the deadline has passed, noting in the tally that the walk
+    /// is cut short.
+    fn out_of_time(&self) -> bool {
+        let past = self.options.is_past_deadline();
+        if past {
+            self.tally.timed_out.store(true, Ordering::Relaxed);
+        }
+        past
+    }
+}
+
+/// A symlinked directory found by `scan_dir`, with the walk state of the
+/// directory it was found in.
+struct PendingLink {
+    path: PathBuf,
+    in_git_repo: bool,
+    depth: usize,
+}
+
+/// Identifies a directory independently of the path it was reached by.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::metadata(path)
+        .ok()
+        .map(|meta| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> Option<DirId> {
+    std::fs::canonicalize(path).ok()
+}
+
+/// Symlink-following state of one walk. Walkers set symlinked directories
+/// aside in `pending` and the walk enters them in rounds once the plain
+/// traversal is done; `visited` keeps every directory, and so every link
+/// cycle, from being entered twice.
+struct FollowLinks<T> {
+    visited: Mutex<HashSet<DirId>>,
+    pending: Mutex<Vec<T>>,
+}
+
+impl<T> Default for FollowLinks<T> {
+    fn default() -> Self {
+        Self {
+            visited: Mutex::new(HashSet::new()),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<T> FollowLinks<T> {
+    /// Records `path` as visited; false if its directory already was.
+    /// Directories we cannot identify are always entered.
+    fn first_visit(&self, path: &Path) -> bool {
+        let Some(id) = dir_id(path) else {
+            return true;
+        };
+        let mut visited = match self.visited.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        visited.insert(id)
+    }
+
+    fn defer(&self, link: T) {
+        match self.pending.lock() {
+            Ok(mut guard) => guard.push(link),
+            Err(poisoned) => poisoned.into_inner().push(link),
+        }
+    }
+
+    fn take_pending(&self) -> Vec<T> {
+        match self.pending.lock() {
+            Ok(mut guard) => std::mem::take(&mut *guard),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        }
+    }
 }
 
 fn scan_dir<'scope>(
     scope: &rayon::Scope<'scope>,
     dir: PathBuf,
-    artifact_dir_names: &'scope HashSet<OsString>,
-    results: Arc<Mutex<Vec<PathBuf>>>,
+    walk: &'scope ScanWalk<'scope>,
     in_git_repo: bool,
+    depth: usize,
 ) {
+    let options = walk.options;
+    // Entries of `dir` sit at `depth + 1`; past the limit there is nothing
+    // left to check.
+    if options
+        .max_depth
+        .is_some_and(|max_depth| depth >= max_depth)
+    {
+        return;
+    }
+    walk.tally.dirs_listed.fetch_add(1, Ordering::Relaxed);
+    // Subdirs are collected and the handle closed before any of them is
+    // visited: the nested-repo probe lists dirs too and must not wait for
+    // a permit while holding one.
+    let permit = walk.options.open_dirs.acquire();
     let entries = match std::fs::read_dir(&dir) {
         Ok(entries) => entries,
-        Err(_) => return,
+        Err(err) => {
+            walk.tally.errors.record(&dir, &err);
+            return;
+        }
     };
 
+    let mut subdirs = Vec::new();
     for entry in entries {
+        if options.is_cancelled() || walk.out_of_time() {
+            return;
+        }
         let entry = match entry {
             Ok(entry) => entry,
-            Err(_) => continue,
+            Err(err) => {
+                walk.tally.errors.record(&dir, &err);
+                continue;
+            }
         };
         let file_type = match entry.file_type() {
             Ok(file_type) => file_type,
-            Err(_) => continue,
+            Err(err) => {
+                walk.tally.errors.record(&entry.path(), &err);
+                continue;
+            }
         };
 
-        if !file_type.is_dir() {
+        if file_type.is_symlink()
+            && let Some(follow) = &walk.follow
+        {
+            let path = entry.path();
+            if std::fs::metadata(&path).is_ok_and(|meta| meta.is_dir()) {
+                follow.defer(PendingLink {
+                    path,
+                    in_git_repo,
+                    depth,
+                });
+            }
             continue;
         }
 
-        let file_name = entry.file_name();
-        if file_name == ".git" {
-            continue;
+        if file_type.is_dir() {
+            subdirs.push((entry.path(), entry.file_name()));
         }
+    }
+    drop(permit);
 
-        let path = entry.path();
-        if artifact_dir_names.contains(&file_name) {
-            let mut results = match results.lock() {
-                Ok(guard) => guard,
-                Err(poisoned) => poisoned.into_inner(),
-            };
-            results.push(path);
-            continue;
+    for (path, file_name) in subdirs {
+        if options.is_cancelled() || walk.out_of_time() {
+            return;
         }
-
-        if in_git_repo {
-            let results = Arc::clone(&results);
-            scope.spawn(move |scope| scan_dir(scope, path, artifact_dir_names, results, true));
+        if let Some(follow) = &walk.follow
+            && !follow.first_visit(&path)
+        {
             continue;
         }
+        visit_dir(scope, walk, path, &file_name, in_git_repo, depth);
+    }
+}
 
-        if has_dot_git(&path) {
-            let results = Arc::clone(&results);
-            scope.spawn(move |scope| scan_dir(scope, path, artifact_dir_names, results, true));
-            continue;
+/// Handles one subdirectory of a directory at `depth`: reports it, walks
+/// into it, or probes below it for nested repos.
+fn visit_dir<'scope>(
+    scope: &rayon::Scope<'scope>,
+    walk: &'scope ScanWalk<'scope>,
+    path: PathBuf,
+    file_name: &OsStr,
+    in_git_repo: bool,
+    depth: usize,
+) {
+    let options = walk.options;
+    if file_name == ".git" {
+        return;
+    }
+    if options.excludes.is_excluded(&path) {
+        return;
+    }
+    if options.skips_dir(&path) {
+        return;
+    }
+    if is_on_other_device(walk.root_device, &path) {
+        walk.tally.other_device.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+    if options.artifacts.matches_path(&path) {
+        (walk.sink)(path);
+        return;
+    }
+
+    if in_git_repo || has_dot_git(&path) {
+        scope.spawn(move |scope| scan_dir(scope, path, walk, true, depth + 1));
+        return;
+    }
+
+    // Generic multi-level layout support:
+    // if a directory is not a repo itself, probe 1-2 levels below for nested repos.
+    let probe_depth = options.prune_outside_repos.unwrap_or(2);
+    let nested_git_roots = find_nested_git_roots(&path, probe_depth, &options.open_dirs);
+    if nested_git_roots.is_empty() {
+        if options.prune_outside_repos.is_none() {
+            scope.spawn(move |scope| scan_dir(scope, path, walk, false, depth + 1));
         }
+        return;
+    }
 
-        // Generic multi-level layout support:
-        // if a directory is not a repo itself, probe 1-2 levels below for nested repos.
-        let nested_git_roots = find_nested_git_roots(&path, 2);
-        if nested_git_roots.is_empty() {
-            let results = Arc::clone(&results);
-            scope.spawn(move |scope| scan_dir(scope, path, artifact_dir_names, results, false));
+    for repo_root in nested_git_roots {
+        // The probe looks past dirs we must not enter.
+        if repo_root
+            .ancestors()
+            .take_while(|dir| *dir != path)
+            .any(|dir| options.excludes.is_excluded(dir) || options.skips_dir(dir))
+        {
             continue;
         }
-
-        for repo_root in nested_git_roots {
-            let results = Arc::clone(&results);
-            scope.spawn(move |scope| scan_dir(scope, repo_root, artifact_dir_names, results, true));
+        if let Some(follow) = &walk.follow
+            && !follow.first_visit(&repo_root)
+        {
+            continue;
         }
+        let repo_depth = depth
+            + 1
+            + repo_root
+                .strip_prefix(&path)
+                .map_or(0, |rel| rel.components().count());
+        scope.spawn(move |scope| scan_dir(scope, repo_root, walk, true, repo_depth));
     }
 }
 
 impl DirStats {
     fn merge(&mut self, other: DirStats) {
         self.size_bytes = self.size_bytes.saturating_add(other.size_bytes);
+        self.file_count = self.file_count.saturating_add(other.file_count);
+        self.unreadable += other.unreadable;
+        self.other_device += other.other_device;
+        self.excluded += other.excluded;
         self.merge_mtime(other.newest_mtime);
+        self.oldest_mtime = earliest(self.oldest_mtime, other.oldest_mtime);
+        self.newest_atime = self.newest_atime.max(other.newest_atime);
+    }
+
+    fn add_file(&mut self, meta: &std::fs::Metadata) {
+        self.size_bytes = self.size_bytes.saturating_add(meta.len());
+        self.file_count += 1;
+        let mtime = meta.modified().ok();
+        self.merge_mtime(mtime);
+        self.oldest_mtime = earliest(self.oldest_mtime, mtime);
+        self.newest_atime = self.newest_atime.max(meta.accessed().ok());
     }
 
     fn merge_mtime(&mut self, other: Option<SystemTime>) {
@@ -220,15 +1080,57 @@ impl DirStats {
     }
 }
 
+/// The earlier of two optional times, ignoring a missing one.
+fn earliest(a: Option<SystemTime>, b: Option<SystemTime>) -> Option<SystemTime> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Whether `path` sits on a different device than `root_device`; always
+/// false when either device is unknown.
+fn is_on_other_device(root_device: Option<u64>, path: &Path) -> bool {
+    root_device.is_some_and(|root| device_id(path).is_some_and(|device| device != root))
+}
+
+/// Identifies the filesystem `path` lives on.
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::symlink_metadata(path).ok().map(|meta| meta.dev())
+}
+
+/// Windows has no device id in std yet, so paths are told apart by their
+/// prefix (drive letter or UNC share) until a proper volume check exists.
+#[cfg(windows)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
+
+    let std::path::Component::Prefix(prefix) = path.components().next()? else {
+        return None;
+    };
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    prefix.as_os_str().to_ascii_lowercase().hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn device_id(_path: &Path) -> Option<u64> {
+    None
+}
+
 fn has_dot_git(path: &Path) -> bool {
     std::fs::metadata(path.join(".git")).is_ok()
 }
 
-fn find_nested_git_roots(start: &Path, max_depth: usize) -> Vec<PathBuf> {
+fn find_nested_git_roots(start: &Path, max_depth: usize, open_dirs: &DirLimit) -> Vec<PathBuf> {
     let mut stack = vec![(start.to_path_buf(), 0usize)];
     let mut roots = Vec::new();
 
     while let Some((dir, depth)) = stack.pop() {
+        let _permit = open_dirs.acquire();
         let entries = match std::fs::read_dir(&dir) {
             Ok(entries) => entries,
             Err(_) => continue,
@@ -272,6 +1174,7 @@ fn find_nested_git_roots(start: &Path, max_depth: usize) -> Vec<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::make_temp_dir;
     use std::{
         collections::HashSet,
         ffi::OsString,
@@ -297,15 +1200,436 @@ mod tests {
         )
         .unwrap();
 
-        let mut artifact_dir_names = HashSet::new();
-        artifact_dir_names.insert(OsString::from("target"));
+        let options = ScanOptions::new(HashSet::from([OsString::from("target")]));
 
-        let found = scan_artifact_dirs(&root, &artifact_dir_names);
+        let found = scan_artifact_dirs(&root, &options, &WalkTally::default());
         assert_eq!(found, vec![worktree_target]);
 
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn pruning_outside_repos_skips_trees_without_a_shallow_git() {
+        let root = make_temp_dir("clean-my-code-scan");
+        fs::create_dir_all(root.join("datasets/a/b/c/target")).unwrap();
+        fs::create_dir_all(root.join("work/app/.git")).unwrap();
+        fs::create_dir_all(root.join("work/app/target")).unwrap();
+        fs::create_dir_all(root.join("deep/x/y/z/lib/.git")).unwrap();
+        fs::create_dir_all(root.join("deep/x/y/z/lib/target")).unwrap();
+
+        let scan = |prune| {
+            let options = ScanOptions {
+                prune_outside_repos: prune,
+                ..ScanOptions::new(HashSet::from([OsString::from("target")]))
+            };
+            scan_artifact_dirs(&root, &options, &WalkTally::default())
+        };
+        let full = scan(None);
+        let shallow = scan(Some(1));
+        let deep = scan(Some(3));
+        let inside_repo = scan_artifact_dirs(
+            &root.join("work/app"),
+            &ScanOptions {
+                prune_outside_repos: Some(0),
+                ..ScanOptions::new(HashSet::from([OsString::from("target")]))
+            },
+            &WalkTally::default(),
+        );
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(
+            full,
+            vec![
+                root.join("datasets/a/b/c/target"),
+                root.join("deep/x/y/z/lib/target"),
+                root.join("work/app/target"),
+            ]
+        );
+        assert_eq!(shallow, vec![root.join("work/app/target")]);
+        assert_eq!(
+            deep,
+            vec![
+                root.join("deep/x/y/z/lib/target"),
+                root.join("work/app/target"),
+            ]
+        );
+        assert_eq!(inside_repo, vec![root.join("work/app/target")]);
+    }
+
+    #[test]
+    fn streaming_scan_sends_each_candidate_once() {
+        let root = make_temp_dir("clean-my-code-scan");
+        for repo in ["a", "b", "group/c"] {
+            fs::create_dir_all(root.join(repo).join(".git")).unwrap();
+            fs::create_dir_all(root.join(repo).join("target/debug")).unwrap();
+            fs::create_dir_all(root.join(repo).join("web/node_modules")).unwrap();
+        }
+        let options = ScanOptions::new(HashSet::from([
+            OsString::from("target"),
+            OsString::from("node_modules"),
+        ]));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        scan_artifact_dirs_streaming(&root, &options, &WalkTally::default(), |path| {
+            tx.send(path).unwrap();
+        });
+        drop(tx);
+        let mut streamed = rx.into_iter().collect::<Vec<_>>();
+        streamed.sort();
+        let collected = scan_artifact_dirs(&root, &options, &WalkTally::default());
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(streamed.len(), 6);
+        assert_eq!(streamed, collected);
+    }
+
+    #[test]
+    fn max_depth_stops_descending_past_the_limit() {
+        let root = make_temp_dir("clean-my-code-scan");
+        fs::create_dir_all(root.join("repo/.git")).unwrap();
+        fs::create_dir_all(root.join("group/repo2/.git")).unwrap();
+        let shallow = root.join("repo/a/target");
+        let deep = root.join("repo/a/b/target");
+        let deep_nested = root.join("group/repo2/x/target");
+        for dir in [&shallow, &deep, &deep_nested] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        let scan = |max_depth| {
+            let options = ScanOptions {
+                max_depth,
+                ..ScanOptions::new(HashSet::from([OsString::from("target")]))
+            };
+            scan_artifact_dirs(&root, &options, &WalkTally::default())
+        };
+        let at_three = scan(Some(3));
+        let at_four = scan(Some(4));
+        let unlimited = scan(None);
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(at_three, vec![shallow.clone()]);
+        let mut all = vec![deep_nested, deep, shallow];
+        all.sort();
+        assert_eq!(at_four, all);
+        assert_eq!(unlimited, all);
+    }
+
+    #[test]
+    fn same_device_keeps_dirs_on_the_root_device() {
+        let root = make_temp_dir("clean-my-code-scan");
+        fs::create_dir_all(root.join("repo/.git")).unwrap();
+        fs::create_dir_all(root.join("repo/target/deep")).unwrap();
+        fs::write(root.join("repo/target/deep/blob"), vec![0u8; 7]).unwrap();
+
+        let options = ScanOptions {
+            same_device: true,
+            ..ScanOptions::new(HashSet::from([OsString::from("target")]))
+        };
+        let tally = WalkTally::default();
+        let found = scan_artifact_dirs(&root, &options, &tally);
+        let stats = dir_stats(&root.join("repo/target"), &options).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(found, vec![root.join("repo/target")]);
+        assert_eq!(tally.other_device.load(Ordering::Relaxed), 0);
+        assert_eq!((stats.size_bytes, stats.other_device), (7, 0));
+        assert!(!is_on_other_device(None, Path::new("/")));
+    }
+
+    #[test]
+    fn excludes_inside_an_artifact_are_left_out_of_its_size() {
+        let root = make_temp_dir("clean-my-code-scan");
+        fs::create_dir_all(root.join("repo/.git")).unwrap();
+        fs::create_dir_all(root.join("repo/target/keep-me")).unwrap();
+        fs::write(root.join("repo/target/blob"), vec![0u8; 7]).unwrap();
+        fs::write(root.join("repo/target/keep-me/notes"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("repo/target/secret.key"), vec![0u8; 50]).unwrap();
+
+        let options = ScanOptions {
+            excludes: ExcludeSet::new(["keep-*", "*.key"]).unwrap(),
+            ..ScanOptions::new(HashSet::from([OsString::from("target")]))
+        };
+        let stats = dir_stats(&root.join("repo/target"), &options).unwrap();
+        let unfiltered =
+            dir_stats(&root.join("repo/target"), &ScanOptions::new(HashSet::new())).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!((stats.size_bytes, stats.file_count), (7, 1));
+        assert_eq!(stats.excluded, 2);
+        assert_eq!((unfiltered.size_bytes, unfiltered.excluded), (157, 0));
+    }
+
+    #[test]
+    fn dir_stats_track_oldest_mtime_and_newest_atime_of_files() {
+        let root = make_temp_dir("clean-my-code-scan");
+        fs::create_dir_all(root.join("target/deep")).unwrap();
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        let now = SystemTime::now();
+        for (path, modified, accessed) in [
+            ("target/a", now - day * 400, now - day * 3),
+            ("target/deep/b", now - day * 10, now - day * 10),
+        ] {
+            fs::write(root.join(path), "x").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(root.join(path))
+                .unwrap()
+                .set_times(
+                    fs::FileTimes::new()
+                        .set_modified(modified)
+                        .set_accessed(accessed),
+                )
+                .unwrap();
+        }
+
+        let stats = dir_stats(&root.join("target"), &ScanOptions::new(HashSet::new())).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(stats.oldest_mtime, Some(now - day * 400));
+        assert_eq!(stats.newest_atime, Some(now - day * 3));
+    }
+
+    #[test]
+    fn largest_files_keeps_the_top_k_biggest_first() {
+        let root = make_temp_dir("clean-my-code-scan");
+        fs::create_dir_all(root.join("target/deep/er")).unwrap();
+        fs::write(root.join("target/a"), vec![0u8; 10]).unwrap();
+        fs::write(root.join("target/deep/b"), vec![0u8; 40]).unwrap();
+        fs::write(root.join("target/deep/er/c"), vec![0u8; 30]).unwrap();
+        fs::write(root.join("target/deep/d"), vec![0u8; 5]).unwrap();
+
+        let options = ScanOptions {
+            largest_files: 2,
+            ..ScanOptions::new(HashSet::new())
+        };
+        let stats = dir_stats(&root.join("target"), &options).unwrap();
+        let off = dir_stats(&root.join("target"), &ScanOptions::new(HashSet::new())).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(
+            stats.largest_files,
+            [
+                (root.join("target/deep/b"), 40),
+                (root.join("target/deep/er/c"), 30),
+            ]
+        );
+        assert_eq!(stats.size_bytes, 85);
+        assert!(off.largest_files.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn following_symlinks_terminates_on_loops_and_counts_dirs_once() {
+        use std::os::unix::fs::symlink;
+
+        let root = make_temp_dir("clean-my-code-scan");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("target/deep")).unwrap();
+        fs::write(repo.join("target/deep/blob"), vec![0u8; 7]).unwrap();
+        fs::create_dir_all(root.join("elsewhere/target")).unwrap();
+        symlink(&repo, repo.join("target/deep/loop")).unwrap();
+        symlink(&root, repo.join("up")).unwrap();
+        symlink(repo.join("target"), repo.join("alias")).unwrap();
+        symlink(repo.join("target/deep"), repo.join("target/deep-again")).unwrap();
+        symlink(root.join("elsewhere"), repo.join("linked")).unwrap();
+        symlink(&repo, root.join("repo-link")).unwrap();
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..ScanOptions::new(HashSet::from([OsString::from("target")]))
+        };
+        let found = scan_artifact_dirs(&root, &options, &WalkTally::default());
+        let stats = dir_stats(&repo.join("target"), &options).unwrap();
+        let through_link = dir_stats(&root.join("repo-link"), &options).unwrap();
+        let not_followed =
+            dir_stats(&root.join("repo-link"), &ScanOptions::new(HashSet::new())).unwrap();
+        let plain = scan_artifact_dirs(
+            &root,
+            &ScanOptions::new(HashSet::from([OsString::from("target")])),
+            &WalkTally::default(),
+        );
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(
+            found,
+            vec![root.join("elsewhere/target"), repo.join("target")]
+        );
+        assert_eq!((stats.size_bytes, stats.file_count), (7, 1));
+        assert_eq!((through_link.size_bytes, through_link.file_count), (7, 1));
+        assert_eq!(not_followed.file_count, 0);
+        assert_eq!(
+            plain,
+            vec![root.join("elsewhere/target"), repo.join("target")]
+        );
+    }
+
+    #[test]
+    fn cancelled_walks_return_early_with_partial_results() {
+        let root = make_temp_dir("clean-my-code-scan");
+        fs::create_dir_all(root.join("repo/.git")).unwrap();
+        fs::create_dir_all(root.join("repo/target/deep")).unwrap();
+        fs::write(root.join("repo/target/deep/blob"), vec![0u8; 7]).unwrap();
+
+        let cancel = Arc::new(AtomicBool::new(true));
+        let options = ScanOptions {
+            cancel: Some(Arc::clone(&cancel)),
+            ..ScanOptions::new(HashSet::from([OsString::from("target")]))
+        };
+        let found = scan_artifact_dirs(&root, &options, &WalkTally::default());
+        let stats = dir_stats(&root.join("repo/target"), &options).unwrap();
+        cancel.store(false, Ordering::Relaxed);
+        let full = dir_stats(&root.join("repo/target"), &options).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        assert!(found.is_empty());
+        assert_eq!((stats.size_bytes, stats.file_count), (0, 0));
+        assert_eq!((full.size_bytes, full.file_count), (7, 1));
+    }
+
+    #[test]
+    fn open_dir_limit_keeps_results_on_wide_trees() {
+        let root = make_temp_dir("clean-my-code-scan");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        for i in 0..10_000 {
+            fs::create_dir(repo.join(format!("d{i}"))).unwrap();
+        }
+        for i in (0..10_000).step_by(1000) {
+            fs::create_dir(repo.join(format!("d{i}/target"))).unwrap();
+            fs::write(repo.join(format!("d{i}/target/blob")), [0u8; 3]).unwrap();
+        }
+        let options = ScanOptions::new(HashSet::from([OsString::from("target")]));
+        let unlimited = scan_artifact_dirs(&root, &options, &WalkTally::default());
+        let unlimited_stats = dir_stats(&repo, &options).unwrap();
+
+        let options = ScanOptions {
+            open_dirs: Arc::new(DirLimit::new(2)),
+            ..options
+        };
+        let peak = AtomicUsize::new(0);
+        let done = AtomicBool::new(false);
+        let limited = std::thread::scope(|scope| {
+            let watcher = scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let open = options.open_dirs.state.lock().unwrap().open;
+                    peak.fetch_max(open, Ordering::Relaxed);
+                    std::thread::yield_now();
+                }
+            });
+            let found = scan_artifact_dirs(&root, &options, &WalkTally::default());
+            let stats = dir_stats(&repo, &options).unwrap();
+            done.store(true, Ordering::Relaxed);
+            watcher.join().unwrap();
+            (found, stats)
+        });
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(unlimited.len(), 10);
+        assert_eq!(limited.0, unlimited);
+        assert_eq!(limited.1, unlimited_stats);
+        assert_eq!(
+            (unlimited_stats.size_bytes, unlimited_stats.file_count),
+            (30, 10)
+        );
+        assert!(peak.load(Ordering::Relaxed) <= 2);
+    }
+
+    #[test]
+    fn regex_patterns_match_dir_basenames() {
+        let root = make_temp_dir("clean-my-code-scan");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        for dir in ["eslint-cache", "pkg.egg-info", "src/cache-dir", "target"] {
+            fs::create_dir_all(repo.join(dir)).unwrap();
+        }
+
+        let mut options = ScanOptions::new(HashSet::from([OsString::from("target")]));
+        options.artifacts.patterns = vec![
+            NamePattern::Regex(Regex::new("-cache$").unwrap()),
+            NamePattern::Regex(Regex::new(r"\.egg-info$").unwrap()),
+        ];
+        let found = scan_artifact_dirs(&root, &options, &WalkTally::default());
+        assert_eq!(
+            found,
+            vec![
+                repo.join("eslint-cache"),
+                repo.join("pkg.egg-info"),
+                repo.join("target"),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn trailing_paths_match_the_last_components_and_outer_matches_win() {
+        let root = make_temp_dir("clean-my-code-scan");
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        for dir in [
+            ".build/checkouts/dep",
+            "pkg/dist-newstyle/build/x",
+            "dist-newstyle/cache",
+            "checkouts",
+        ] {
+            fs::create_dir_all(repo.join(dir)).unwrap();
+        }
+        let scan = |entries: &[&str]| {
+            let names = entries.iter().map(OsString::from).collect();
+            let found = scan_artifact_dirs(&root, &ScanOptions::new(names), &WalkTally::default());
+            found
+                .iter()
+                .map(|path| path.strip_prefix(&repo).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            scan(&[".build/checkouts", "dist-newstyle/build"]),
+            [
+                PathBuf::from(".build/checkouts"),
+                PathBuf::from("pkg/dist-newstyle/build"),
+            ]
+        );
+        // The outer name match wins; nothing inside it is reported too.
+        assert_eq!(
+            scan(&[".build", ".build/checkouts"]),
+            [PathBuf::from(".build")]
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn glob_patterns_match_whole_names_on_raw_bytes() {
+        let mut matcher = ArtifactMatcher::new(HashSet::from([OsString::from("target")]));
+        matcher.patterns = vec![
+            NamePattern::glob("cmake-build-*").unwrap(),
+            NamePattern::glob("*.egg-info").unwrap(),
+            NamePattern::glob("out[0-9]?").unwrap(),
+        ];
+        let matches = |name: &str| matcher.matches(OsStr::new(name));
+
+        assert!(matches("target"));
+        assert!(matches("cmake-build-debug"));
+        assert!(matches("pkg.egg-info"));
+        assert!(matches("out1x"));
+        assert!(!matches("my-cmake-build-debug"));
+        assert!(!matches("pkg.egg-info.bak"));
+        assert!(!matches("out1"));
+        assert!(matches("out1é"));
+        assert!(!matches("src"));
+        assert!(NamePattern::glob("a/b").is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            assert!(matcher.matches(OsStr::from_bytes(b"cmake-build-\xff")));
+            assert!(!matcher.matches(OsStr::from_bytes(b"\xfftarget")));
+            assert!(matcher.matches(OsStr::from_bytes(b"out1\xff")));
+        }
+    }
+
     #[test]
     fn scan_falls_back_to_deeper_walk_when_probe_misses() {
         let root = make_temp_dir("clean-my-code-scan");
@@ -315,22 +1639,111 @@ mod tests {
         fs::create_dir_all(&target).unwrap();
         fs::write(repo_root.join(".git"), "gitdir: /tmp/fake\n").unwrap();
 
-        let mut artifact_dir_names = HashSet::new();
-        artifact_dir_names.insert(OsString::from("target"));
+        let options = ScanOptions::new(HashSet::from([OsString::from("target")]));
 
-        let found = scan_artifact_dirs(&root, &artifact_dir_names);
+        let found = scan_artifact_dirs(&root, &options, &WalkTally::default());
         assert_eq!(found, vec![target]);
 
         let _ = fs::remove_dir_all(root);
     }
 
-    fn make_temp_dir(prefix: &str) -> PathBuf {
-        let stamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        let path = std::env::temp_dir().join(format!("{prefix}-{}-{stamp}", std::process::id()));
-        fs::create_dir_all(&path).unwrap();
-        path
+    #[cfg(unix)]
+    #[test]
+    fn counts_unreadable_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = make_temp_dir("clean-my-code-scan");
+        let locked = root.join("locked");
+        fs::create_dir_all(locked.join("inner")).unwrap();
+        fs::write(root.join("file.bin"), vec![0u8; 10]).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        // Root ignores permissions, so there is nothing to observe.
+        let readable_anyway = fs::read_dir(&locked).is_ok();
+
+        let tally = WalkTally::default();
+        scan_artifact_dirs(&root, &ScanOptions::default(), &tally);
+        let errors = WalkErrors::default();
+        let stats =
+            dir_stats_with_progress(&root, &ScanOptions::default(), &errors, |_| {}).unwrap();
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        let _ = fs::remove_dir_all(root);
+
+        assert_eq!(stats.size_bytes, 10);
+        assert_eq!(stats.file_count, 1);
+        if !readable_anyway {
+            assert_eq!(tally.errors.total(), 1);
+            assert_eq!(stats.unreadable, 1);
+            let expected = vec![WalkError {
+                path: locked.clone(),
+                kind: std::io::ErrorKind::PermissionDenied,
+            }];
+            assert_eq!(tally.errors.into_kept(), expected);
+            assert_eq!(errors.into_kept(), expected);
+        }
+    }
+
+    #[test]
+    fn sizing_progress_reports_merged_totals_at_most_once_per_interval() {
+        let reported = Mutex::new(Vec::new());
+        let on_progress = |stats: &DirStats| reported.lock().unwrap().push(stats.size_bytes);
+        let progress = SizingProgress {
+            totals: ThreadTotals::new(),
+            on_progress: &on_progress,
+            last: Mutex::new(Instant::now() - SIZING_PROGRESS_INTERVAL),
+            root_device: None,
+            skip_mounts: &[],
+            excludes: &ExcludeSet::default(),
+            follow: None,
+            largest: None,
+            errors: &WalkErrors::default(),
+            cancel: None,
+            open_dirs: &DirLimit::default(),
+        };
+        let chunk = DirStats {
+            size_bytes: 10,
+            ..DirStats::default()
+        };
+
+        progress.flush(chunk.clone());
+        progress.flush(chunk);
+
+        assert_eq!(*reported.lock().unwrap(), [10]);
+        assert_eq!(progress.totals.sum().size_bytes, 20);
+    }
+
+    #[test]
+    fn thread_totals_sum_adds_from_every_worker() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let newest = UNIX_EPOCH + Duration::from_secs(500);
+        let totals = pool.install(|| {
+            let totals = ThreadTotals::new();
+            rayon::scope(|scope| {
+                for i in 0..1000u64 {
+                    let totals = &totals;
+                    scope.spawn(move |_| {
+                        totals.add(DirStats {
+                            size_bytes: i,
+                            file_count: 1,
+                            newest_mtime: Some(UNIX_EPOCH + Duration::from_secs(i % 501)),
+                            ..DirStats::default()
+                        })
+                    });
+                }
+            });
+            totals
+        });
+        // From outside the pool too.
+        totals.add(DirStats {
+            unreadable: 1,
+            ..DirStats::default()
+        });
+
+        let sum = totals.sum();
+        assert_eq!((sum.size_bytes, sum.file_count), (499_500, 1000));
+        assert_eq!((sum.newest_mtime, sum.unreadable), (Some(newest), 1));
     }
 }
@@ -0,0 +1,145 @@
+use std::{fmt::Write as _, path::Path};
+
+use crate::{clean::DeleteTarget, format::format_bytes};
+
+/// Flavor of the reviewable delete script written by `--emit-script`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptShell {
+    Posix,
+    PowerShell,
+}
+
+impl ScriptShell {
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            Self::PowerShell
+        } else {
+            Self::Posix
+        }
+    }
+}
+
+/// Builds a script that removes `targets` the way `clean` would, with a
+/// header comment carrying the totals.
+pub fn delete_script(shell: ScriptShell, targets: &[&DeleteTarget]) -> String {
+    let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
+    let mut script = String::new();
+    if shell == ScriptShell::Posix {
+        script.push_str("#!/bin/sh\n");
+    }
+    let _ = writeln!(
+        script,
+        "# Generated by `clean-my-code clean --dry-run --emit-script`."
+    );
+    let _ = writeln!(
+        script,
+        "# Deletes {} artifact dirs, reclaiming {}.",
+        targets.len(),
+        format_bytes(planned_bytes)
+    );
+    match shell {
+        ScriptShell::Posix => script.push_str("set -eu\n\n"),
+        ScriptShell::PowerShell => script.push_str("$ErrorActionPreference = 'Stop'\n\n"),
+    }
+
+    for target in targets {
+        let _ = writeln!(script, "# {}", format_bytes(target.planned_bytes));
+        match shell {
+            ScriptShell::Posix => {
+                let _ = writeln!(script, "rm -rf -- {}", posix_quote(&target.path));
+            }
+            ScriptShell::PowerShell => {
+                let _ = writeln!(
+                    script,
+                    "Remove-Item -LiteralPath {} -Recurse -Force",
+                    powershell_quote(&target.path)
+                );
+            }
+        }
+    }
+    script
+}
+
+/// Single-quotes a path for `sh`; nothing inside single quotes is special
+/// except the quote itself. Bytes that aren't UTF-8 are spliced in through
+/// `printf` octal escapes so the script names the exact same dir.
+fn posix_quote(path: &Path) -> String {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let mut quoted = String::new();
+    let mut invalid = Vec::new();
+    let flush_invalid = |quoted: &mut String, invalid: &mut Vec<u8>| {
+        if invalid.is_empty() {
+            return;
+        }
+        quoted.push_str("\"$(printf '");
+        for byte in invalid.drain(..) {
+            let _ = write!(quoted, "\\{byte:03o}");
+        }
+        quoted.push_str("')\"");
+    };
+    for chunk in bytes.utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            flush_invalid(&mut quoted, &mut invalid);
+            let _ = write!(quoted, "'{}'", chunk.valid().replace('\'', "'\\''"));
+        }
+        invalid.extend_from_slice(chunk.invalid());
+    }
+    flush_invalid(&mut quoted, &mut invalid);
+    if quoted.is_empty() {
+        quoted.push_str("''");
+    }
+    quoted
+}
+
+/// Single-quotes a path for PowerShell, where a quote is escaped by doubling.
+fn powershell_quote(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    format!("'{}'", raw.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn quotes_paths_for_each_shell() {
+        let target = DeleteTarget {
+            repo_root: PathBuf::from("/ws/it's"),
+            path: PathBuf::from("/ws/it's/target $(x)"),
+            planned_bytes: 2048,
+        };
+
+        let posix = delete_script(ScriptShell::Posix, &[&target]);
+        assert!(posix.starts_with("#!/bin/sh\n"));
+        assert!(posix.contains("# Deletes 1 artifact dirs, reclaiming 2.0 KiB.\n"));
+        assert!(posix.ends_with("rm -rf -- '/ws/it'\\''s/target $(x)'\n"));
+
+        let powershell = delete_script(ScriptShell::PowerShell, &[&target]);
+        assert!(
+            powershell
+                .ends_with("Remove-Item -LiteralPath '/ws/it''s/target $(x)' -Recurse -Force\n")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn splices_non_utf8_bytes_into_posix_scripts() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/ws/caf\xe9\xff/target"));
+        assert_eq!(
+            posix_quote(path),
+            "'/ws/caf'\"$(printf '\\351\\377')\"'/target'"
+        );
+
+        // The shell must hand `rm` the very same bytes.
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", posix_quote(path)))
+            .output()
+            .unwrap();
+        assert_eq!(output.stdout, b"/ws/caf\xe9\xff/target");
+    }
+}
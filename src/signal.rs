@@ -0,0 +1,59 @@
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, Result};
+
+/// Records SIGTERM/SIGHUP instead of dying, so long-running work can stop
+/// between steps and leave the terminal in a usable state.
+#[derive(Debug, Clone)]
+pub struct TerminationFlag(Arc<AtomicUsize>);
+
+impl TerminationFlag {
+    pub fn register() -> Result<Self> {
+        let flag = Arc::new(AtomicUsize::new(0));
+        for signal in TERMINATION_SIGNALS {
+            signal_hook::flag::register_usize(*signal, Arc::clone(&flag), *signal as usize)
+                .with_context(|| format!("failed to register handler for signal {signal}"))?;
+        }
+        Ok(Self(flag))
+    }
+
+    /// The most recent termination signal received, if any.
+    pub fn signal(&self) -> Option<i32> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            signal => Some(signal as i32),
+        }
+    }
+}
+
+#[cfg(unix)]
+const TERMINATION_SIGNALS: &[i32] = &[signal_hook::consts::SIGTERM, signal_hook::consts::SIGHUP];
+#[cfg(not(unix))]
+const TERMINATION_SIGNALS: &[i32] = &[signal_hook::consts::SIGTERM];
+
+/// Returned after a termination signal once in-flight work has wound down.
+#[derive(Debug)]
+pub struct Terminated {
+    pub signal: i32,
+}
+
+impl Terminated {
+    /// Shell convention: 128 + signal number.
+    pub fn exit_code(&self) -> u8 {
+        u8::try_from(128 + self.signal).unwrap_or(u8::MAX)
+    }
+}
+
+impl fmt::Display for Terminated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "terminated by signal {}", self.signal)
+    }
+}
+
+impl std::error::Error for Terminated {}
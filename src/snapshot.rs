@@ -0,0 +1,259 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::{
+    atomic_write::write_atomically,
+    format::{ByteUnit, format_bytes_with, format_local_date},
+    json::{self, JsonObject, JsonValue},
+    report::RepoReport,
+    roots::ScanRoots,
+};
+
+const SNAPSHOT_VERSION: u64 = 1;
+
+/// Per-repo totals saved by `scan --snapshot`, so a later `scan --diff`
+/// can show what grew in between.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub taken_at: SystemTime,
+    pub repos: Vec<SnapshotRepo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotRepo {
+    /// Lossy UTF-8 form of the repo root; also the key repos are matched on.
+    pub repo_root: String,
+    pub size_bytes: u64,
+    pub newest_mtime: Option<SystemTime>,
+}
+
+impl Snapshot {
+    pub fn from_reports(reports: &[RepoReport], taken_at: SystemTime) -> Self {
+        Self {
+            taken_at,
+            repos: reports
+                .iter()
+                .map(|report| SnapshotRepo {
+                    repo_root: report.repo_root.to_string_lossy().into_owned(),
+                    size_bytes: report.total_size_bytes,
+                    newest_mtime: report.newest_mtime,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let repos = json::array(self.repos.iter().map(|repo| {
+            let newest_mtime = repo
+                .newest_mtime
+                .map(|t| unix_secs(t).to_string())
+                .unwrap_or_else(|| "null".to_string());
+            JsonObject::new()
+                .str("repo_root", &repo.repo_root)
+                .num("size_bytes", repo.size_bytes)
+                .raw("newest_mtime", &newest_mtime)
+                .finish()
+        }));
+        JsonObject::new()
+            .num("version", SNAPSHOT_VERSION)
+            .num("taken_at", unix_secs(self.taken_at))
+            .raw("repos", &repos)
+            .finish()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value = json::parse(text)?;
+        let version = value.get("version").and_then(JsonValue::as_u64);
+        if version != Some(SNAPSHOT_VERSION) {
+            bail!("unsupported snapshot version: {version:?}");
+        }
+        let taken_at = value
+            .get("taken_at")
+            .and_then(JsonValue::as_u64)
+            .ok_or_else(|| anyhow!("snapshot has no taken_at"))?;
+        let repos = value
+            .get("repos")
+            .and_then(JsonValue::as_array)
+            .ok_or_else(|| anyhow!("snapshot has no repos"))?
+            .iter()
+            .map(|repo| {
+                Ok(SnapshotRepo {
+                    repo_root: repo
+                        .get("repo_root")
+                        .and_then(JsonValue::as_str)
+                        .ok_or_else(|| anyhow!("snapshot repo has no repo_root"))?
+                        .to_string(),
+                    size_bytes: repo
+                        .get("size_bytes")
+                        .and_then(JsonValue::as_u64)
+                        .ok_or_else(|| anyhow!("snapshot repo has no size_bytes"))?,
+                    newest_mtime: repo
+                        .get("newest_mtime")
+                        .and_then(JsonValue::as_u64)
+                        .map(from_unix_secs),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            taken_at: from_unix_secs(taken_at),
+            repos,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read snapshot: {path:?}"))?;
+        Self::from_json(&text).with_context(|| format!("invalid snapshot: {path:?}"))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        // Write then rename so `--diff` and `--snapshot` can share a file.
+        write_atomically(path, &(self.to_json() + "\n"), "snapshot")
+    }
+}
+
+/// How one repo's total changed between a snapshot and the current scan.
+/// `None` on either side means the repo is new or gone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoDelta {
+    pub repo_root: String,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+impl RepoDelta {
+    pub fn change(&self) -> i128 {
+        i128::from(self.after.unwrap_or(0)) - i128::from(self.before.unwrap_or(0))
+    }
+}
+
+/// Repos whose total changed (or that appeared or disappeared), biggest
+/// change first, plus the number of repos that stayed the same size.
+pub fn diff_snapshot(old: &Snapshot, new: &Snapshot) -> (Vec<RepoDelta>, usize) {
+    let mut before: HashMap<&str, u64> = old
+        .repos
+        .iter()
+        .map(|repo| (repo.repo_root.as_str(), repo.size_bytes))
+        .collect();
+    let mut unchanged = 0;
+    let mut deltas = Vec::new();
+    for repo in &new.repos {
+        let was = before.remove(repo.repo_root.as_str());
+        if was == Some(repo.size_bytes) {
+            unchanged += 1;
+            continue;
+        }
+        deltas.push(RepoDelta {
+            repo_root: repo.repo_root.clone(),
+            before: was,
+            after: Some(repo.size_bytes),
+        });
+    }
+    deltas.extend(before.into_iter().map(|(repo_root, size)| RepoDelta {
+        repo_root: repo_root.to_string(),
+        before: Some(size),
+        after: None,
+    }));
+    deltas.sort_by(|a, b| {
+        b.change()
+            .abs()
+            .cmp(&a.change().abs())
+            .then_with(|| a.repo_root.cmp(&b.repo_root))
+    });
+    (deltas, unchanged)
+}
+
+pub fn print_diff(scan_roots: &ScanRoots, old: &Snapshot, new: &Snapshot, unit: Option<ByteUnit>) {
+    let (deltas, unchanged) = diff_snapshot(old, new);
+    let net = deltas.iter().map(RepoDelta::change).sum::<i128>();
+
+    println!(
+        "Compared with snapshot from {} ({} repos)",
+        format_local_date(old.taken_at),
+        old.repos.len()
+    );
+    println!(
+        "Changed: {}  Unchanged: {unchanged}  Net: {}",
+        deltas.len(),
+        format_signed_bytes(net, unit)
+    );
+    for delta in &deltas {
+        let repo_display = scan_roots.display_rel(Path::new(&delta.repo_root));
+        let detail = match (delta.before, delta.after) {
+            (None, Some(after)) => format!("new, {}", format_bytes_with(after, unit)),
+            (Some(before), None) => format!("gone, was {}", format_bytes_with(before, unit)),
+            (Some(before), Some(after)) => {
+                format!(
+                    "{} -> {}",
+                    format_bytes_with(before, unit),
+                    format_bytes_with(after, unit)
+                )
+            }
+            (None, None) => String::new(),
+        };
+        println!(
+            "  {:>12}  {repo_display}  ({detail})",
+            format_signed_bytes(delta.change(), unit)
+        );
+    }
+    println!();
+}
+
+fn format_signed_bytes(change: i128, unit: Option<ByteUnit>) -> String {
+    let sign = if change < 0 { '-' } else { '+' };
+    let magnitude = u64::try_from(change.unsigned_abs()).unwrap_or(u64::MAX);
+    format!("{sign}{}", format_bytes_with(magnitude, unit))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn from_unix_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(root: &str, size_bytes: u64) -> SnapshotRepo {
+        SnapshotRepo {
+            repo_root: root.to_string(),
+            size_bytes,
+            newest_mtime: Some(from_unix_secs(1_700_000_000)),
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trips_and_diffs() {
+        let old = Snapshot {
+            taken_at: from_unix_secs(1_700_000_000),
+            repos: vec![repo("/ws/a", 100), repo("/ws/b", 50), repo("/ws/gone", 10)],
+        };
+        assert_eq!(Snapshot::from_json(&old.to_json()).unwrap(), old);
+
+        let new = Snapshot {
+            taken_at: from_unix_secs(1_700_086_400),
+            repos: vec![repo("/ws/a", 400), repo("/ws/b", 50), repo("/ws/new", 5)],
+        };
+        let (deltas, unchanged) = diff_snapshot(&old, &new);
+        assert_eq!(unchanged, 1);
+        assert_eq!(
+            deltas
+                .iter()
+                .map(|d| (d.repo_root.as_str(), d.change()))
+                .collect::<Vec<_>>(),
+            vec![("/ws/a", 300), ("/ws/gone", -10), ("/ws/new", 5)]
+        );
+        assert_eq!(format_signed_bytes(-10, None), "-10 B");
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::atomic_write::write_atomically;
+
+/// Set once the user has acknowledged that deletions are permanent.
+pub const FIRST_DELETE_ACKNOWLEDGED: &str = "first_delete_acknowledged";
+
+/// How the TUI shows repo paths: `relative`, `home` or `absolute`.
+pub const PATH_DISPLAY: &str = "path_display";
+
+/// Small persisted `key=value` store for one-time prompts and UI state.
+///
+/// Lives at `$XDG_STATE_HOME/clean-my-code/state`, falling back to
+/// `~/.local/state/clean-my-code/state`. A missing or unreadable file is
+/// treated as empty.
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    path: Option<PathBuf>,
+    values: BTreeMap<String, String>,
+}
+
+impl State {
+    pub fn load() -> Self {
+        match default_state_path() {
+            Some(path) => Self::load_from(path),
+            None => Self::default(),
+        }
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let values = fs::read_to_string(&path)
+            .map(|text| parse(&text))
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            values,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn get_bool(&self, key: &str) -> bool {
+        self.get(key) == Some("true")
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        self.values.insert(key.to_string(), value.into());
+    }
+
+    /// Writes the state file; a no-op when no state dir could be determined.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create state dir: {parent:?}"))?;
+        }
+
+        let mut text = String::new();
+        for (key, value) in &self.values {
+            text.push_str(key);
+            text.push('=');
+            text.push_str(value);
+            text.push('\n');
+        }
+
+        write_atomically(path, &text, "state")
+    }
+}
+
+fn parse(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn default_state_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| Path::new(&home).join(".local").join("state"))
+        })?;
+    Some(base.join("clean-my-code").join("state"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::make_temp_dir;
+
+    #[test]
+    fn round_trips_values_through_the_state_file() {
+        let dir = make_temp_dir("clean-my-code-state");
+        let path = dir.join("nested").join("state");
+
+        let mut state = State::load_from(path.clone());
+        assert!(!state.get_bool(FIRST_DELETE_ACKNOWLEDGED));
+        state.set(FIRST_DELETE_ACKNOWLEDGED, "true");
+        state.save().unwrap();
+
+        let reloaded = State::load_from(path);
+        assert!(reloaded.get_bool(FIRST_DELETE_ACKNOWLEDGED));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,36 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Creates a fresh dir under the system temp dir, unique to this process
+/// and call.
+pub(crate) fn make_temp_dir(prefix: &str) -> PathBuf {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("{prefix}-{}-{stamp}", std::process::id()));
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+/// Runs `git -C repo args...` with a throwaway identity, so commits work
+/// without a global git config.
+pub(crate) fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Turns the existing dir `repo` into an empty git repo.
+pub(crate) fn init_repo(repo: &Path) {
+    git(repo, &["init", "-q"]);
+}
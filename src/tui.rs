@@ -1,21 +1,25 @@
 use std::{
     cmp::Ordering as CmpOrdering,
     collections::{HashMap, HashSet},
-    ffi::OsString,
+    ffi::{OsStr, OsString},
+    io::Write,
     path::{Path, PathBuf},
     sync::{
-        Arc,
+        Arc, Once,
         atomic::{AtomicBool, AtomicUsize, Ordering},
         mpsc,
     },
     thread,
-    time::{Duration, Instant, SystemTime},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{Context, Result};
 use crossterm::{
     cursor::{Hide, Show},
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -26,89 +30,229 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{
-        Block, Borders, Cell, Clear, HighlightSpacing, Paragraph, Row, Table, TableState, Wrap,
+        Block, Borders, Cell, Clear, HighlightSpacing, LineGauge, Paragraph, Row, Table,
+        TableState, Wrap,
     },
 };
 use rayon::prelude::*;
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
     clean::{
-        DeleteProgress, DeleteSummary, DeleteTarget, execute_delete_with_progress,
-        plan_delete_targets,
+        DeleteOptions, DeleteProgress, DeleteSummary, DeleteTarget, execute_delete_with_progress,
+        format_delete_summary, group_targets_by_repo, plan_delete_targets,
+    },
+    cli::summary_json,
+    format::{
+        ByteUnit, abbreviate_home, display_path, display_rel_path, format_bytes_with,
+        format_local_date, format_percent, truncate_middle,
+    },
+    git::{GitHead, GitOptions, git_head, git_remote_url, is_git_ignored},
+    report::{
+        AgeBasis, ArtifactNameTotal, ArtifactOrder, ArtifactRecord, CandidateError,
+        CandidateErrorKind, CandidateOutcome, RepoReport, STALE_AFTER_DAYS, ScanCounters, is_stale,
+        is_within_file_range, is_within_size_range, process_candidate, process_candidate_with,
+        repo_age_days, sort_artifacts, stale_ratio, totals_by_artifact_name,
+    },
+    roots::ScanRoots,
+    scan::{
+        ArtifactMatcher, DirStats, ScanOptions, WalkErrors, WalkTally, scan_artifact_dirs_streaming,
     },
-    format::{display_rel_path, format_bytes},
-    git::{GitHead, git_head},
-    report::{ArtifactRecord, RepoReport, process_candidate},
-    scan::scan_artifact_dirs,
+    signal::{Terminated, TerminationFlag},
+    state::{FIRST_DELETE_ACKNOWLEDGED, PATH_DISPLAY, State},
 };
 
 #[derive(Debug, Clone)]
 pub struct TuiOptions {
     pub min_size_bytes: u64,
+    pub max_size_bytes: Option<u64>,
+    pub min_files: u64,
+    pub max_files: Option<u64>,
     pub dry_run: bool,
+    /// How often to wake up once no scan or clean is running.
+    pub idle_poll: Duration,
+    /// Where `w` on the Result screen saves the report; the current
+    /// directory when unset.
+    pub report_dir: Option<PathBuf>,
+    pub parallel_delete: bool,
+    /// Select stale repos and go straight to Confirm once the scan is done.
+    pub clean_all: bool,
+    /// With `clean_all`, accept the Confirm screen without asking.
+    pub yes: bool,
+    pub require_clean_worktree: bool,
+    pub prune_empty_parents: bool,
+    /// Quit once the Result screen has been shown this long.
+    pub auto_exit_after: Option<Duration>,
+    /// List repos without any ignored artifact too, greyed out.
+    pub include_empty_repos: bool,
+    /// Time limit for each scan; once one ran out, `f` resumes without it.
+    pub scan_timeout: Option<Duration>,
+    /// Bucket the repo list by top-level dir under the scan root, under
+    /// header rows with per-group subtotals.
+    pub group_by_parent: bool,
+    /// Which file times say when an artifact was last used.
+    pub age_basis: AgeBasis,
+    /// Every size is shown in this unit (`--display-unit`); `None` scales
+    /// each one on its own.
+    pub display_unit: Option<ByteUnit>,
 }
 
 pub fn run(
-    scan_root: &Path,
-    artifact_dir_names: HashSet<OsString>,
+    scan_roots: &ScanRoots,
+    scan_options: ScanOptions,
     threads: Option<usize>,
     options: TuiOptions,
 ) -> Result<()> {
     let now = SystemTime::now();
 
     let (tx, rx) = mpsc::channel::<AppEvent>();
-    let scan_cancel = Arc::new(AtomicBool::new(false));
     let clean_cancel = Arc::new(AtomicBool::new(false));
-    spawn_scan_worker(
-        scan_root.to_path_buf(),
-        artifact_dir_names,
-        threads,
-        Arc::clone(&scan_cancel),
-        tx.clone(),
-    );
 
     let mut app = App::new(now);
+    app.state = State::load();
+    app.path_display = PathDisplay::from_state(&app.state);
+    app.scan_options = scan_options;
+    app.scan_threads = threads;
+    app.scan_timeout = options.scan_timeout;
+    app.start_scan(scan_roots, &tx);
+    let mut auto_clean_pending = options.clean_all;
+    let termination = TerminationFlag::register()?;
+    install_panic_hook();
     let mut terminal = TerminalGuard::enter().context("failed to initialize terminal")?;
 
+    // Redraw only when something changed; while work is in flight, a periodic
+    // tick keeps spinners and elapsed-time counters moving.
+    let mut dirty = true;
+    let mut last_tick = Instant::now();
+    let mut result_shown_at: Option<Instant> = None;
+
     loop {
+        if let Some(signal) = termination.signal() {
+            app.stop_scans(SCAN_STOP_GRACE);
+            clean_cancel.store(true, Ordering::Relaxed);
+
+            // Let the clean worker finish its current target so the summary
+            // reflects exactly what was deleted, but don't wait on a worker
+            // stuck on a slow mount (or gone) for longer than the grace.
+            let mut summary_lines = Vec::new();
+            if matches!(app.screen, Screen::Cleaning(_)) {
+                let deadline = Instant::now() + CLEAN_STOP_GRACE;
+                summary_lines = loop {
+                    match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                        Ok(AppEvent::Clean(CleanEvent::Finished { summary, canceled })) => {
+                            break format_delete_summary(
+                                scan_roots,
+                                &summary,
+                                options.dry_run,
+                                canceled,
+                                options.display_unit,
+                            );
+                        }
+                        Ok(event @ AppEvent::Clean(CleanEvent::Progress { .. })) => {
+                            app.apply_event(scan_roots, &options, &tx, event);
+                        }
+                        Ok(_) => {}
+                        Err(_) => {
+                            let Screen::Cleaning(cleaning) = &app.screen else {
+                                break Vec::new();
+                            };
+                            break partial_clean_summary(scan_roots, cleaning, &options);
+                        }
+                    }
+                };
+            }
+
+            drop(terminal);
+            // The controlling terminal may already be gone after SIGHUP.
+            let mut stderr = std::io::stderr();
+            for line in summary_lines {
+                let _ = writeln!(stderr, "{line}");
+            }
+            return Err(Terminated { signal }.into());
+        }
+
         while let Ok(event) = rx.try_recv() {
-            app.apply_event(scan_root, &options, event);
-        }
-
-        terminal.draw(|frame| render(frame, scan_root, &options, &mut app))?;
-
-        if event::poll(Duration::from_millis(50)).context("failed to poll terminal events")? {
-            let event = event::read().context("failed to read terminal event")?;
-            if let Event::Key(key) = event {
-                if handle_key(
-                    scan_root,
-                    &options,
-                    &scan_cancel,
-                    &clean_cancel,
-                    &tx,
-                    &mut app,
-                    key,
-                )? {
+            app.apply_event(scan_roots, &options, &tx, event);
+            dirty = true;
+        }
+        if auto_clean_pending && app.scan_done && matches!(app.screen, Screen::Main) {
+            auto_clean_pending = false;
+            start_auto_clean(scan_roots, &options, &clean_cancel, &tx, &mut app);
+        }
+
+        let busy = app.is_busy();
+        if busy && last_tick.elapsed() >= BUSY_TICK {
+            dirty = true;
+        }
+
+        if dirty {
+            terminal.draw(|frame| render(frame, scan_roots, &options, &mut app))?;
+            dirty = false;
+            last_tick = Instant::now();
+        }
+
+        let mut poll_timeout = if busy { BUSY_POLL } else { options.idle_poll };
+        if matches!(app.screen, Screen::Result) {
+            let shown_at = *result_shown_at.get_or_insert_with(Instant::now);
+            if let Some(limit) = options.auto_exit_after {
+                let Some(remaining) = limit.checked_sub(shown_at.elapsed()) else {
+                    break;
+                };
+                poll_timeout = poll_timeout.min(remaining);
+            }
+        } else {
+            result_shown_at = None;
+        }
+
+        if event::poll(poll_timeout).context("failed to poll terminal events")? {
+            dirty = true;
+            match event::read().context("failed to read terminal event")? {
+                Event::Key(key)
+                    if handle_key(scan_roots, &options, &clean_cancel, &tx, &mut app, key)? =>
+                {
                     break;
                 }
+                Event::Mouse(mouse) => handle_mouse(&options, &tx, &mut app, mouse),
+                // Repaint every cell: after a resize the terminal may still
+                // show pieces of the old layout.
+                Event::Resize(_, _) => terminal.clear()?,
+                _ => {}
             }
         }
     }
 
-    scan_cancel.store(true, Ordering::Relaxed);
+    // Restore the terminal only once the scan has wound down, so no git
+    // subprocess outlives the UI.
+    app.stop_scans(SCAN_STOP_GRACE);
     clean_cancel.store(true, Ordering::Relaxed);
     Ok(())
 }
 
+const BUSY_POLL: Duration = Duration::from_millis(50);
+/// Page size before anything has been drawn.
+const DEFAULT_PAGE_ROWS: usize = 10;
+const BUSY_TICK: Duration = Duration::from_millis(200);
+/// How long quitting waits for a canceled scan to finish its in-flight git
+/// calls.
+const SCAN_STOP_GRACE: Duration = Duration::from_millis(500);
+/// How long a termination signal waits for the clean worker to finish its
+/// current target before exiting with the totals so far.
+const CLEAN_STOP_GRACE: Duration = Duration::from_secs(3);
+/// How long scan results for a dir this session deleted are ignored, so a
+/// scan that listed it before the delete can't bring it back.
+const RECENTLY_DELETED_TTL: Duration = Duration::from_secs(60);
+
 fn spawn_scan_worker(
-    scan_root: PathBuf,
-    artifact_dir_names: HashSet<OsString>,
+    scan_id: u64,
+    scan_roots: ScanRoots,
+    scan_options: ScanOptions,
     threads: Option<usize>,
     cancel: Arc<AtomicBool>,
     tx: mpsc::Sender<AppEvent>,
-) {
+) -> thread::JoinHandle<()> {
     thread::spawn(move || {
-        let run = || scan_worker(scan_root, artifact_dir_names, cancel, tx);
+        let error_tx = tx.clone();
+        let run = || scan_worker(scan_id, scan_roots, scan_options, cancel, tx);
 
         let result = match threads {
             Some(threads) => rayon::ThreadPoolBuilder::new()
@@ -119,40 +263,99 @@ fn spawn_scan_worker(
             None => run(),
         };
 
+        // Printing would land on the alternate screen; the status line shows
+        // it instead.
         if let Err(err) = result {
-            eprintln!("scan worker error: {err:#}");
+            let event = ScanEvent::Failed {
+                message: format!("{err:#}"),
+            };
+            let _ = error_tx.send(AppEvent::FullScan { scan_id, event });
         }
-    });
+    })
 }
 
 fn scan_worker(
-    scan_root: PathBuf,
-    artifact_dir_names: HashSet<OsString>,
+    scan_id: u64,
+    scan_roots: ScanRoots,
+    scan_options: ScanOptions,
     cancel: Arc<AtomicBool>,
     tx: mpsc::Sender<AppEvent>,
 ) -> Result<()> {
     if cancel.load(Ordering::Relaxed) {
         return Ok(());
     }
-
-    let candidates = scan_artifact_dirs(&scan_root, &artifact_dir_names);
-    let total = candidates.len();
-    let _ = tx.send(AppEvent::Scan(ScanEvent::CandidatesTotal { total }));
-    if total == 0 {
-        let _ = tx.send(AppEvent::Scan(ScanEvent::Finished));
-        return Ok(());
-    }
+    let send = |event: ScanEvent| {
+        let _ = tx.send(AppEvent::FullScan { scan_id, event });
+    };
+    // Lets a canceled scan abandon a dir it is in the middle of sizing.
+    let scan_options = ScanOptions {
+        cancel: Some(Arc::clone(&cancel)),
+        ..scan_options
+    };
 
     let processed = AtomicUsize::new(0);
     let head_started: Arc<std::sync::Mutex<HashSet<PathBuf>>> =
         Arc::new(std::sync::Mutex::new(HashSet::new()));
 
-    candidates.par_iter().for_each(|path| {
+    // Only counted, through `outcome.warnings()`; the TUI lists no paths.
+    // Failed candidates are sent one by one, with their error.
+    let sizing_errors = WalkErrors::default();
+    let timed_out = AtomicBool::new(false);
+    let report_timeout = || {
+        if !timed_out.swap(true, Ordering::Relaxed) {
+            send(ScanEvent::TimedOut);
+        }
+    };
+    let process = |path: PathBuf| {
         if cancel.load(Ordering::Relaxed) {
             return;
         }
 
-        if let Some(record) = process_candidate(path) {
+        let outcome = process_candidate_with(
+            &path,
+            &scan_options,
+            &sizing_errors,
+            |path| {
+                send(ScanEvent::Sizing {
+                    path: path.to_path_buf(),
+                })
+            },
+            |path, stats| {
+                send(ScanEvent::SizingProgress {
+                    path: path.to_path_buf(),
+                    size_bytes: stats.size_bytes,
+                })
+            },
+        );
+        let count = outcome.warnings();
+        if count > 0 {
+            send(ScanEvent::Warnings { count });
+        }
+        let count = outcome.other_device_dirs();
+        if count > 0 {
+            send(ScanEvent::OtherDevice { count });
+        }
+        let record = match outcome {
+            CandidateOutcome::Artifact(record) => Some(record),
+            CandidateOutcome::OutsideRepo(stats) => {
+                send(ScanEvent::OutsideRepo { stats });
+                None
+            }
+            CandidateOutcome::NotIgnored { repo_root } => {
+                send(ScanEvent::RepoSeen { repo_root });
+                None
+            }
+            CandidateOutcome::Failed(error) => {
+                send(ScanEvent::Warning { error });
+                None
+            }
+            CandidateOutcome::TimedOut => {
+                report_timeout();
+                None
+            }
+            CandidateOutcome::Skipped => None,
+        };
+        if let Some(record) = record {
             let repo_root = record.repo_root.clone();
             let should_spawn_head = {
                 let mut started = match head_started.lock() {
@@ -162,33 +365,113 @@ fn scan_worker(
                 started.insert(repo_root.clone())
             };
 
+            // Each git call below can take a while on a big repo; don't
+            // start new ones once the scan is canceled.
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
             if should_spawn_head {
-                let head = git_head(&repo_root).unwrap_or(None);
-                let _ = tx.send(AppEvent::Scan(ScanEvent::RepoHead { repo_root, head }));
+                // A failed lookup still ends the spinner, with a warning
+                // instead of "no commits".
+                let head = match git_head(&repo_root, &scan_options.git) {
+                    Ok(head) => head,
+                    Err(err) => {
+                        send(ScanEvent::Warning {
+                            error: CandidateError::new(
+                                &repo_root,
+                                CandidateErrorKind::GitHead,
+                                &err,
+                            ),
+                        });
+                        None
+                    }
+                };
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                let url = git_remote_url(&repo_root, &scan_options.git).unwrap_or(None);
+                send(ScanEvent::RepoHead {
+                    repo_root: repo_root.clone(),
+                    head,
+                });
+                send(ScanEvent::RepoRemote { repo_root, url });
             }
 
-            let _ = tx.send(AppEvent::Scan(ScanEvent::Artifact { record }));
+            send(ScanEvent::RepoSeen {
+                repo_root: record.repo_root.clone(),
+            });
+            send(ScanEvent::Artifact { record });
         }
 
         let processed_count = processed.fetch_add(1, Ordering::Relaxed) + 1;
-        if processed_count == total || processed_count % 64 == 0 {
-            let _ = tx.send(AppEvent::Scan(ScanEvent::CandidateProcessed {
+        if processed_count.is_multiple_of(64) {
+            send(ScanEvent::CandidateProcessed {
                 processed: processed_count,
-            }));
+            });
+        }
+    };
+
+    // Candidates are processed as discovery finds them, on the same pool,
+    // so the first rows show up long before a big tree is fully walked.
+    let tally = WalkTally::default();
+    let found = AtomicUsize::new(0);
+    rayon::scope(|scope| {
+        let process = &process;
+        for root in scan_roots.iter() {
+            scan_artifact_dirs_streaming(root, &scan_options, &tally, |path| {
+                found.fetch_add(1, Ordering::Relaxed);
+                scope.spawn(move |_| process(path));
+            });
+        }
+        send(ScanEvent::CandidatesTotal {
+            total: found.load(Ordering::Relaxed),
+        });
+        let count = tally.errors.total();
+        if count > 0 {
+            send(ScanEvent::Warnings { count });
+        }
+        let count = tally.other_device.load(Ordering::Relaxed);
+        if count > 0 {
+            send(ScanEvent::OtherDevice { count });
+        }
+        if tally.timed_out.load(Ordering::Relaxed) {
+            report_timeout();
         }
     });
 
-    let _ = tx.send(AppEvent::Scan(ScanEvent::CandidateProcessed {
-        processed: total,
-    }));
-    let _ = tx.send(AppEvent::Scan(ScanEvent::Finished));
+    send(ScanEvent::CandidateProcessed {
+        processed: processed.into_inner(),
+    });
+    send(ScanEvent::Finished);
     Ok(())
 }
 
 #[derive(Debug)]
 enum AppEvent {
+    /// From a full scan; dropped unless `scan_id` is the current scan.
+    FullScan {
+        scan_id: u64,
+        event: ScanEvent,
+    },
     Scan(ScanEvent),
     Clean(CleanEvent),
+    /// Dropped unless `scan_id` is the current scan, whose start forgot
+    /// every earlier check.
+    IgnoreRechecked {
+        scan_id: u64,
+        path: PathBuf,
+        check: IgnoreCheck,
+    },
+}
+
+/// Result of re-running `git check-ignore` on an artifact after the scan.
+/// Artifacts without an entry were confirmed ignored at scan time only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum IgnoreCheck {
+    Pending,
+    Ignored,
+    NotIgnored,
+    Failed(String),
 }
 
 #[derive(Debug)]
@@ -203,9 +486,52 @@ enum ScanEvent {
         repo_root: PathBuf,
         head: Option<GitHead>,
     },
+    RepoSeen {
+        repo_root: PathBuf,
+    },
+    OutsideRepo {
+        stats: DirStats,
+    },
+    /// Unreadable entries the scan skipped over.
+    Warnings {
+        count: usize,
+    },
+    /// A candidate that could not be checked or sized.
+    Warning {
+        error: CandidateError,
+    },
+    /// Dirs left out for living on another device.
+    OtherDevice {
+        count: usize,
+    },
+    /// The scan ran out of time; what it shows is partial.
+    TimedOut,
+    /// A candidate's size walk started.
+    Sizing {
+        path: PathBuf,
+    },
+    /// Running total of a size walk that is taking a while.
+    SizingProgress {
+        path: PathBuf,
+        size_bytes: u64,
+    },
+    /// The scan worker gave up with an error; nothing more will arrive.
+    Failed {
+        message: String,
+    },
+    RepoRemote {
+        repo_root: PathBuf,
+        url: Option<String>,
+    },
     Artifact {
         record: ArtifactRecord,
     },
+    ArtifactRefreshed {
+        record: ArtifactRecord,
+    },
+    ArtifactGone {
+        path: PathBuf,
+    },
     Finished,
 }
 
@@ -226,12 +552,32 @@ struct App {
     now: SystemTime,
 
     sort_mode: SortMode,
+    age_filter: AgeFilter,
+    /// The size threshold as moved with `+`/`-`; `--min-size` until then.
+    min_size_override: Option<u64>,
     items: Vec<RepoItem>,
+    /// The cursor, as an index into the visible repos; group headers are
+    /// not counted, so navigation never lands on one.
     table_state: TableState,
+    /// Selection and scroll offset of the table as drawn, in rows that
+    /// include group headers. See `table_rows`.
+    display_state: TableState,
+    focus: Focus,
+    path_display: PathDisplay,
+    /// Highlighted artifact of the highlighted repo, while the detail pane
+    /// has focus.
+    detail_cursor: usize,
     pending_heads: HashMap<PathBuf, Option<GitHead>>,
+    pending_remotes: HashMap<PathBuf, Option<String>>,
+    ignore_checks: HashMap<PathBuf, IgnoreCheck>,
 
     screen: Screen,
     result_lines: Vec<String>,
+    /// Summary behind `result_lines` and whether that clean was canceled,
+    /// kept in full for saving the report.
+    last_summary: Option<(DeleteSummary, bool)>,
+    /// Outcome of saving the report, shown under the result.
+    result_note: Option<String>,
 
     scan_started_at: Instant,
     scan_elapsed_final: Option<Duration>,
@@ -239,14 +585,182 @@ struct App {
     scan_processed: usize,
     scan_done: bool,
     artifacts_found: usize,
+    seen_repos: HashSet<PathBuf>,
+    counters: ScanCounters,
+    /// Candidates the scan could not check or size, in arrival order.
+    scan_failures: Vec<CandidateError>,
+    /// Why the scan worker stopped early, if it failed.
+    scan_error: Option<String>,
+    /// Most recently started size walk, relative to the scan roots.
+    sizing: Option<String>,
+    /// Bytes counted so far by that walk, once it reported progress.
+    sizing_bytes: Option<u64>,
+    spinner_frame: usize,
 
     new_repo_default_selected: Option<bool>,
+    /// Where the repo table was last drawn, for mapping mouse clicks to rows.
+    table_area: Option<Rect>,
+    /// Repo rows that fit in the table as last drawn; PageUp/PageDown move
+    /// by this much.
+    page_rows: usize,
+
+    scan_options: ScanOptions,
+    /// Names switched off in the artifact settings popup, kept so they can be
+    /// switched back on.
+    disabled_artifact_names: HashSet<OsString>,
+    scan_threads: Option<usize>,
+    /// Applied to every scan this starts, from its start.
+    scan_timeout: Option<Duration>,
+    scan_id: u64,
+    scan_cancel: Arc<AtomicBool>,
+    /// Scan threads that may still be running, canceled ones included.
+    scan_workers: Vec<thread::JoinHandle<()>>,
+
+    state: State,
+    session: SessionTotals,
+    /// Dirs deleted this session and when, see `RECENTLY_DELETED_TTL`.
+    recently_deleted: HashMap<PathBuf, Instant>,
+    /// The `?` overlay is open over whatever screen is current.
+    show_help: bool,
+}
+
+/// A row of the repo table as drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TableRow {
+    /// Not selectable; the cursor skips it.
+    Group(GroupRow),
+    /// Index into the visible repos, as `App::table_state` counts them.
+    Repo(usize),
+}
+
+/// Header of a group of repos, with subtotals of its visible repos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GroupRow {
+    path: PathBuf,
+    repos: usize,
+    size_bytes: u64,
+}
+
+/// Which Main-screen pane receives the movement keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Table,
+    Detail,
+}
+
+/// How the Repo column shows repo paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathDisplay {
+    /// Relative to the scan root containing the repo.
+    Relative,
+    /// Absolute, with the home directory abbreviated to `~`.
+    Home,
+    Absolute,
+}
+
+impl PathDisplay {
+    fn next(self) -> Self {
+        match self {
+            Self::Relative => Self::Home,
+            Self::Home => Self::Absolute,
+            Self::Absolute => Self::Relative,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Relative => "relative",
+            Self::Home => "home",
+            Self::Absolute => "absolute",
+        }
+    }
+
+    fn from_state(state: &State) -> Self {
+        match state.get(PATH_DISPLAY) {
+            Some("home") => Self::Home,
+            Some("absolute") => Self::Absolute,
+            _ => Self::Relative,
+        }
+    }
+
+    fn display(self, scan_roots: &ScanRoots, path: &Path) -> String {
+        match self {
+            Self::Relative => scan_roots.display_rel(path),
+            Self::Home => {
+                let home = std::env::var_os("HOME").map(PathBuf::from);
+                abbreviate_home(home.as_deref(), path)
+            }
+            Self::Absolute => display_path(path),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SortMode {
     Age,
     Size,
+    StaleRatio,
+    /// Oldest head commit first; repos whose head is unknown go last.
+    HeadAge,
+}
+
+impl SortMode {
+    /// How each repo's artifacts are listed under this mode.
+    fn artifact_order(self, basis: AgeBasis) -> ArtifactOrder {
+        match self {
+            SortMode::Age | SortMode::HeadAge => ArtifactOrder::Age(basis),
+            SortMode::Size | SortMode::StaleRatio => ArtifactOrder::Size,
+        }
+    }
+}
+
+/// Where `+`/`-` move the size threshold: each step ten times the last, so
+/// a few presses go from everything to only the giants.
+const MIN_SIZE_STEPS: &[u64] = &[
+    0,
+    1024 * 1024,
+    10 * 1024 * 1024,
+    100 * 1024 * 1024,
+    1024 * 1024 * 1024,
+    10 * 1024 * 1024 * 1024,
+    100 * 1024 * 1024 * 1024,
+];
+
+/// Presets for hiding repos whose artifacts were touched recently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AgeFilter {
+    Any,
+    Days30,
+    Days90,
+    Days180,
+}
+
+impl AgeFilter {
+    fn next(self) -> Self {
+        match self {
+            AgeFilter::Any => AgeFilter::Days30,
+            AgeFilter::Days30 => AgeFilter::Days90,
+            AgeFilter::Days90 => AgeFilter::Days180,
+            AgeFilter::Days180 => AgeFilter::Any,
+        }
+    }
+
+    fn min_days(self) -> Option<u64> {
+        match self {
+            AgeFilter::Any => None,
+            AgeFilter::Days30 => Some(30),
+            AgeFilter::Days90 => Some(90),
+            AgeFilter::Days180 => Some(180),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UpsertMode {
+    /// Scan results: the first record for a path wins.
+    Append,
+    /// Refresh results: the new record replaces the stored one.
+    Replace,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -256,6 +770,11 @@ enum SortKey {
         bytes: u64,
         time: Option<SystemTime>,
     },
+    StaleRatio {
+        permille: u64,
+        bytes: u64,
+    },
+    HeadAge(Option<i64>),
 }
 
 impl App {
@@ -266,46 +785,252 @@ impl App {
         Self {
             now,
             sort_mode: SortMode::Age,
+            age_filter: AgeFilter::Any,
+            min_size_override: None,
             items: Vec::new(),
             table_state,
+            display_state: TableState::default(),
+            focus: Focus::Table,
+            path_display: PathDisplay::Relative,
+            detail_cursor: 0,
             pending_heads: HashMap::new(),
+            pending_remotes: HashMap::new(),
+            ignore_checks: HashMap::new(),
             screen: Screen::Main,
             result_lines: Vec::new(),
+            last_summary: None,
+            result_note: None,
             scan_started_at: Instant::now(),
             scan_elapsed_final: None,
             scan_total: None,
             scan_processed: 0,
             scan_done: false,
             artifacts_found: 0,
+            seen_repos: HashSet::new(),
+            counters: ScanCounters::default(),
+            scan_failures: Vec::new(),
+            scan_error: None,
+            sizing: None,
+            sizing_bytes: None,
+            spinner_frame: 0,
             new_repo_default_selected: None,
+            table_area: None,
+            page_rows: DEFAULT_PAGE_ROWS,
+            scan_options: ScanOptions::default(),
+            disabled_artifact_names: HashSet::new(),
+            scan_threads: None,
+            scan_timeout: None,
+            scan_id: 0,
+            scan_cancel: Arc::new(AtomicBool::new(false)),
+            scan_workers: Vec::new(),
+            state: State::default(),
+            session: SessionTotals::default(),
+            recently_deleted: HashMap::new(),
+            show_help: false,
+        }
+    }
+
+    /// Cancels any running scan, forgets its results and starts a new one
+    /// with the current artifact dir names.
+    fn start_scan(&mut self, scan_roots: &ScanRoots, tx: &mpsc::Sender<AppEvent>) {
+        self.items.clear();
+        self.table_state.select(None);
+        self.pending_heads.clear();
+        self.pending_remotes.clear();
+        self.seen_repos.clear();
+        self.resume_scan(scan_roots, tx);
+    }
+
+    /// Scans again on top of what is listed: artifacts already shown (and
+    /// their selection) stay, and the new scan only adds what they miss.
+    fn resume_scan(&mut self, scan_roots: &ScanRoots, tx: &mpsc::Sender<AppEvent>) {
+        self.scan_cancel.store(true, Ordering::Relaxed);
+        self.scan_cancel = Arc::new(AtomicBool::new(false));
+        self.scan_id += 1;
+
+        // Checks still running belong to the old scan and are dropped.
+        self.ignore_checks.clear();
+        self.scan_started_at = Instant::now();
+        self.scan_elapsed_final = None;
+        self.scan_total = None;
+        self.scan_processed = 0;
+        self.scan_done = false;
+        self.artifacts_found = 0;
+        // The scan counts every warning and skipped dir again.
+        self.counters = ScanCounters {
+            repos_scanned: self.seen_repos.len(),
+            ..ScanCounters::default()
+        };
+        self.scan_failures.clear();
+        self.scan_error = None;
+        self.sizing = None;
+        self.sizing_bytes = None;
+
+        self.scan_workers.retain(|worker| !worker.is_finished());
+        self.scan_workers.push(spawn_scan_worker(
+            self.scan_id,
+            scan_roots.clone(),
+            ScanOptions {
+                deadline: self.scan_timeout.map(|timeout| Instant::now() + timeout),
+                ..self.scan_options.clone()
+            },
+            self.scan_threads,
+            Arc::clone(&self.scan_cancel),
+            tx.clone(),
+        ));
+    }
+
+    /// Cancels the current scan and waits up to `grace` for every scan
+    /// thread to acknowledge by exiting.
+    fn stop_scans(&mut self, grace: Duration) {
+        self.scan_cancel.store(true, Ordering::Relaxed);
+        let deadline = Instant::now() + grace;
+        while self.scan_workers.iter().any(|worker| !worker.is_finished())
+            && Instant::now() < deadline
+        {
+            thread::sleep(Duration::from_millis(10));
         }
+        self.scan_workers.retain(|worker| !worker.is_finished());
     }
 
     fn toggle_sort_mode(&mut self, options: &TuiOptions) {
         self.sort_mode = match self.sort_mode {
             SortMode::Age => SortMode::Size,
-            SortMode::Size => SortMode::Age,
+            SortMode::Size => SortMode::StaleRatio,
+            SortMode::StaleRatio => SortMode::HeadAge,
+            SortMode::HeadAge => SortMode::Age,
         };
+        // The re-sort reorders the detail pane too; keep its cursor on the
+        // same artifact.
+        let cursor_path = self.selected_item(options).and_then(|item| {
+            item.report
+                .artifacts
+                .get(self.detail_cursor)
+                .map(|artifact| artifact.path.clone())
+        });
+        let order = self.sort_mode.artifact_order(options.age_basis);
+        for item in &mut self.items {
+            sort_artifacts(&mut item.report.artifacts, order);
+        }
 
         self.sort_keep_cursor(options);
+        self.detail_cursor = cursor_path
+            .and_then(|path| {
+                self.selected_item(options)?
+                    .report
+                    .artifacts
+                    .iter()
+                    .position(|artifact| artifact.path == path)
+            })
+            .unwrap_or(0);
+    }
+
+    /// Whether a scan, clean, or background lookup is still running and the
+    /// screen needs periodic redraws.
+    fn is_busy(&self) -> bool {
+        !self.scan_done
+            || matches!(self.screen, Screen::Cleaning(_))
+            || self.items.iter().any(|item| !item.head_loaded)
+            || self
+                .ignore_checks
+                .values()
+                .any(|check| *check == IgnoreCheck::Pending)
+    }
+
+    fn visibility(&self, options: &TuiOptions) -> Visibility {
+        Visibility {
+            min_size_bytes: self.min_size_bytes(options),
+            max_size_bytes: options.max_size_bytes,
+            min_files: options.min_files,
+            max_files: options.max_files,
+            min_age_days: self.age_filter.min_days(),
+            now: self.now,
+            age_basis: options.age_basis,
+            include_empty: options.include_empty_repos,
+        }
+    }
+
+    /// Switches the Repo column to the next path form and remembers it.
+    fn cycle_path_display(&mut self, scan_roots: &ScanRoots) {
+        self.path_display = self.path_display.next();
+        for item in &mut self.items {
+            item.repo_display = self
+                .path_display
+                .display(scan_roots, &item.report.repo_root);
+        }
+        self.state.set(PATH_DISPLAY, self.path_display.as_str());
+        // Losing the preference isn't worth interrupting the session for.
+        let _ = self.state.save();
+    }
+
+    fn min_size_bytes(&self, options: &TuiOptions) -> u64 {
+        self.min_size_override.unwrap_or(options.min_size_bytes)
+    }
+
+    /// Moves the size threshold to the next step of `MIN_SIZE_STEPS` above
+    /// or below the current one, which need not be a step itself.
+    fn step_min_size(&mut self, options: &TuiOptions, up: bool) {
+        let current = self.min_size_bytes(options);
+        let next = if up {
+            MIN_SIZE_STEPS.iter().copied().find(|&step| step > current)
+        } else {
+            MIN_SIZE_STEPS
+                .iter()
+                .copied()
+                .rev()
+                .find(|&step| step < current)
+        };
+        let Some(next) = next else {
+            return;
+        };
+        let current_repo_root = self.selected_repo_root(options);
+        self.min_size_override = Some(next);
+        self.restore_selection(options, current_repo_root);
+    }
+
+    fn cycle_age_filter(&mut self, options: &TuiOptions) {
+        let current_repo_root = self.selected_repo_root(options);
+        self.age_filter = self.age_filter.next();
+        self.restore_selection(options, current_repo_root);
     }
 
-    fn apply_event(&mut self, scan_root: &Path, options: &TuiOptions, event: AppEvent) {
+    fn apply_event(
+        &mut self,
+        scan_roots: &ScanRoots,
+        options: &TuiOptions,
+        tx: &mpsc::Sender<AppEvent>,
+        event: AppEvent,
+    ) {
         match event {
-            AppEvent::Scan(event) => self.apply_scan_event(scan_root, options, event),
-            AppEvent::Clean(event) => self.apply_clean_event(scan_root, options, event),
+            AppEvent::FullScan { scan_id, event } => {
+                if scan_id == self.scan_id {
+                    self.apply_scan_event(scan_roots, options, event);
+                }
+            }
+            AppEvent::Scan(event) => self.apply_scan_event(scan_roots, options, event),
+            AppEvent::Clean(event) => self.apply_clean_event(scan_roots, options, tx, event),
+            AppEvent::IgnoreRechecked {
+                scan_id,
+                path,
+                check,
+            } => {
+                if scan_id == self.scan_id {
+                    self.ignore_checks.insert(path, check);
+                }
+            }
         }
     }
 
-    fn apply_scan_event(&mut self, scan_root: &Path, options: &TuiOptions, event: ScanEvent) {
+    fn apply_scan_event(&mut self, scan_roots: &ScanRoots, options: &TuiOptions, event: ScanEvent) {
         match event {
             ScanEvent::CandidatesTotal { total } => {
                 self.scan_total = Some(total);
-                self.scan_processed = 0;
                 self.scan_elapsed_final = None;
             }
             ScanEvent::CandidateProcessed { processed } => {
-                self.scan_processed = processed;
+                // Counts are sent from several threads and may arrive out of
+                // order.
+                self.scan_processed = self.scan_processed.max(processed);
             }
             ScanEvent::RepoHead { repo_root, head } => {
                 if let Some(item) = self
@@ -315,16 +1040,85 @@ impl App {
                 {
                     item.head_loaded = true;
                     item.report.head = head;
+                    if self.sort_mode == SortMode::HeadAge {
+                        self.sort_keep_cursor(options);
+                    }
                 } else {
                     self.pending_heads.insert(repo_root, head);
                 }
             }
+            ScanEvent::RepoSeen { repo_root } => {
+                if self.seen_repos.insert(repo_root.clone()) {
+                    self.counters.repos_scanned = self.seen_repos.len();
+                    if options.include_empty_repos {
+                        self.push_empty_repo(scan_roots, options, repo_root);
+                    }
+                }
+            }
+            ScanEvent::OutsideRepo { stats } => {
+                self.counters.record_outside_repo(&stats);
+            }
+            ScanEvent::Warnings { count } => {
+                self.counters.warnings += count;
+            }
+            ScanEvent::Warning { error } => {
+                self.counters.warnings += 1;
+                if error.kind == CandidateErrorKind::GitHead
+                    && let Some(item) = self
+                        .items
+                        .iter_mut()
+                        .find(|i| i.report.repo_root == error.path)
+                {
+                    item.head_failed = true;
+                }
+                self.scan_failures.push(error);
+            }
+            ScanEvent::OtherDevice { count } => {
+                self.counters.other_device_dirs += count;
+            }
+            ScanEvent::TimedOut => self.counters.timed_out = true,
+            ScanEvent::Sizing { path } => {
+                self.sizing = Some(scan_roots.display_rel(&path));
+                self.sizing_bytes = None;
+            }
+            ScanEvent::SizingProgress { path, size_bytes } => {
+                self.sizing = Some(scan_roots.display_rel(&path));
+                self.sizing_bytes = Some(size_bytes);
+            }
+            ScanEvent::RepoRemote { repo_root, url } => {
+                if let Some(item) = self
+                    .items
+                    .iter_mut()
+                    .find(|i| i.report.repo_root == repo_root)
+                {
+                    item.remote_url = url;
+                } else {
+                    self.pending_remotes.insert(repo_root, url);
+                }
+            }
             ScanEvent::Artifact { record } => {
                 self.artifacts_found += 1;
-                self.upsert_artifact(scan_root, options, record);
+                self.upsert_artifact(scan_roots, options, record, UpsertMode::Append);
+            }
+            ScanEvent::ArtifactRefreshed { record } => {
+                self.ignore_checks.remove(&record.path);
+                self.upsert_artifact(scan_roots, options, record, UpsertMode::Replace);
+            }
+            ScanEvent::ArtifactGone { path } => {
+                self.ignore_checks.remove(&path);
+                self.remove_artifact(options, &path);
+            }
+            ScanEvent::Failed { message } => {
+                self.scan_error = Some(message);
+                self.scan_done = true;
+                self.sizing = None;
+                self.sizing_bytes = None;
+                self.scan_elapsed_final = Some(self.scan_started_at.elapsed());
             }
             ScanEvent::Finished => {
                 self.scan_done = true;
+                self.sizing = None;
+                self.sizing_bytes = None;
                 self.scan_elapsed_final = Some(self.scan_started_at.elapsed());
                 if let Some(total) = self.scan_total {
                     self.scan_processed = total;
@@ -333,7 +1127,13 @@ impl App {
         }
     }
 
-    fn apply_clean_event(&mut self, scan_root: &Path, options: &TuiOptions, event: CleanEvent) {
+    fn apply_clean_event(
+        &mut self,
+        scan_roots: &ScanRoots,
+        options: &TuiOptions,
+        tx: &mpsc::Sender<AppEvent>,
+        event: CleanEvent,
+    ) {
         match event {
             CleanEvent::Progress { progress, current } => {
                 let Screen::Cleaning(cleaning) = &mut self.screen else {
@@ -348,19 +1148,123 @@ impl App {
                 cleaning.error_count = progress.error_count;
                 cleaning.current = Some(format!(
                     "{}  {}",
-                    display_rel_path(scan_root, &current.repo_root),
+                    scan_roots.display_rel(&current.repo_root),
                     display_rel_path(&current.repo_root, &current.path)
                 ));
             }
             CleanEvent::Finished { summary, canceled } => {
+                if !options.dry_run
+                    && let Screen::Cleaning(cleaning) = &self.screen
+                {
+                    let left = summary
+                        .skipped
+                        .iter()
+                        .map(|(path, _)| path)
+                        .chain(summary.errors.iter().map(|(path, _)| path))
+                        .collect::<HashSet<_>>();
+                    let gone = cleaning
+                        .targets
+                        .iter()
+                        .filter(|t| !left.contains(&t.path))
+                        .filter(|t| std::fs::symlink_metadata(&t.path).is_err())
+                        .map(|t| t.path.clone())
+                        .collect::<Vec<_>>();
+                    self.mark_deleted(gone, Instant::now());
+                }
+                let (touched_repos, quick) = match &self.screen {
+                    Screen::Cleaning(cleaning) => (
+                        cleaning
+                            .targets
+                            .iter()
+                            .map(|t| t.repo_root.clone())
+                            .collect::<HashSet<_>>(),
+                        cleaning.quick,
+                    ),
+                    _ => (HashSet::new(), false),
+                };
+                self.refresh_repos(&touched_repos, tx);
+
+                self.session.record(&summary, options.dry_run);
+
+                // A fully successful quick clean goes straight back to the
+                // list; anything else is worth a look at the result.
+                let fully_deleted = !options.dry_run
+                    && !canceled
+                    && summary.errors.is_empty()
+                    && summary.deleted_paths == summary.planned_paths;
+                if quick && fully_deleted {
+                    self.items
+                        .retain(|item| !touched_repos.contains(&item.report.repo_root));
+                    self.sort_keep_cursor(options);
+                    self.screen = Screen::Main;
+                    return;
+                }
+
                 self.screen = Screen::Result;
-                self.result_lines =
-                    format_delete_summary(scan_root, &summary, options.dry_run, canceled);
+                self.result_lines = format_delete_summary(
+                    scan_roots,
+                    &summary,
+                    options.dry_run,
+                    canceled,
+                    options.display_unit,
+                );
+                if let Some(label) = self.session.label(options.display_unit) {
+                    self.result_lines.push(String::new());
+                    self.result_lines.push(label);
+                }
+                if self.counters.warnings > 0 {
+                    self.result_lines
+                        .push(format!("scan warnings: {}", self.counters.warnings));
+                }
+                self.result_lines.push(String::new());
+                self.result_lines.push(RESULT_FOOTER.to_string());
+                self.last_summary = Some((summary, canceled));
             }
         }
     }
 
-    fn upsert_artifact(&mut self, scan_root: &Path, options: &TuiOptions, record: ArtifactRecord) {
+    /// Re-runs `process_candidate` for the previously known artifacts of the
+    /// given repos on a background thread, so their rows reflect a clean
+    /// without rescanning the whole root.
+    fn refresh_repos(&self, repo_roots: &HashSet<PathBuf>, tx: &mpsc::Sender<AppEvent>) {
+        let paths = self
+            .items
+            .iter()
+            .filter(|item| repo_roots.contains(&item.report.repo_root))
+            .flat_map(|item| item.report.artifacts.iter().map(|a| a.path.clone()))
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            return;
+        }
+
+        spawn_refresh_worker(paths, self.scan_options.clone(), tx.clone());
+    }
+
+    fn mark_deleted(&mut self, paths: Vec<PathBuf>, at: Instant) {
+        self.recently_deleted
+            .retain(|_, deleted_at| deleted_at.elapsed() < RECENTLY_DELETED_TTL);
+        self.recently_deleted
+            .extend(paths.into_iter().map(|path| (path, at)));
+    }
+
+    fn was_recently_deleted(&self, path: &Path) -> bool {
+        self.recently_deleted
+            .get(path)
+            .is_some_and(|deleted_at| deleted_at.elapsed() < RECENTLY_DELETED_TTL)
+    }
+
+    fn upsert_artifact(
+        &mut self,
+        scan_roots: &ScanRoots,
+        options: &TuiOptions,
+        record: ArtifactRecord,
+        mode: UpsertMode,
+    ) {
+        // A scan that listed the dir before it was deleted may still report
+        // it; don't let a just-cleaned repo flicker back.
+        if self.was_recently_deleted(&record.path) {
+            return;
+        }
         let repo_root = record.repo_root.clone();
         let sort_mode = self.sort_mode;
         let now = self.now;
@@ -369,31 +1273,46 @@ impl App {
             .iter_mut()
             .find(|i| i.report.repo_root == repo_root)
         {
-            if item.report.artifacts.iter().any(|a| a.path == record.path) {
+            let existing = item
+                .report
+                .artifacts
+                .iter()
+                .position(|a| a.path == record.path);
+            if existing.is_some() && mode == UpsertMode::Append {
                 return;
             }
 
-            let old_sort_key = Self::sort_key_for_report(sort_mode, &item.report);
-
-            item.report.total_size_bytes = item
-                .report
-                .total_size_bytes
-                .saturating_add(record.stats.size_bytes);
-            item.report.newest_mtime = item.report.newest_mtime.max(record.stats.newest_mtime);
-            item.report.artifacts.push(record);
-
-            item.report.artifacts.sort_by(|a, b| {
-                b.stats
-                    .size_bytes
-                    .cmp(&a.stats.size_bytes)
-                    .then_with(|| a.path.cmp(&b.path))
-            });
+            let old_sort_key = Self::sort_key_for_report(sort_mode, &item.report, now, options);
+            // An empty repo (`--include-empty-repos`) getting its first
+            // artifact is selected like a newly discovered one.
+            let was_empty = item.report.artifacts.is_empty();
+
+            match existing {
+                Some(index) => item.report.artifacts[index] = record,
+                None => {
+                    // The user picked some of this repo's artifacts by
+                    // hand; one found later is not among them.
+                    if item.selected && !item.excluded.is_empty() {
+                        item.excluded.insert(record.path.clone());
+                    }
+                    item.report.artifacts.push(record);
+                }
+            }
+            recompute_report_totals(
+                &mut item.report,
+                sort_mode.artifact_order(options.age_basis),
+            );
 
-            if item.selection_mode == SelectionMode::Auto {
+            if was_empty {
+                (item.selected, item.selection_mode) =
+                    initial_selection(self.new_repo_default_selected, &item.report, options, now);
+                item.keep_unverified();
+            } else if item.selection_mode == SelectionMode::Auto {
                 item.selected = should_auto_select(&item.report, options, now);
+                item.keep_unverified();
             }
 
-            let new_sort_key = Self::sort_key_for_report(sort_mode, &item.report);
+            let new_sort_key = Self::sort_key_for_report(sort_mode, &item.report, now, options);
 
             if old_sort_key != new_sort_key {
                 self.sort_keep_cursor(options);
@@ -407,51 +1326,158 @@ impl App {
             Some(head) => (head, true),
             None => (None, false),
         };
+        let head_failed = self.head_failed(&repo_root);
+        let remote_url = self.pending_remotes.remove(&repo_root).flatten();
 
         let record_size_bytes = record.stats.size_bytes;
         let record_newest_mtime = record.stats.newest_mtime;
+        let record_oldest_mtime = record.stats.oldest_mtime;
+        let record_newest_atime = record.stats.newest_atime;
+        let record_file_count = record.stats.file_count;
         let report = RepoReport {
             repo_root: repo_root.clone(),
             head,
             artifacts: vec![record],
             total_size_bytes: record_size_bytes,
+            total_file_count: record_file_count,
             newest_mtime: record_newest_mtime,
+            oldest_mtime: record_oldest_mtime,
+            newest_atime: record_newest_atime,
+            root_mtime: None,
         };
 
-        let (selected, selection_mode) = match self.new_repo_default_selected {
-            Some(selected) => (selected, SelectionMode::Manual),
-            None => (
-                should_auto_select(&report, options, now),
-                SelectionMode::Auto,
-            ),
-        };
+        let (selected, selection_mode) =
+            initial_selection(self.new_repo_default_selected, &report, options, now);
 
         self.items.push(RepoItem {
             report,
             head_loaded,
+            head_failed,
+            remote_url,
             selected,
             selection_mode,
-            repo_display: display_rel_path(scan_root, &repo_root),
+            repo_display: self.path_display.display(scan_roots, &repo_root),
+            group: scan_roots.top_level_parent(&repo_root),
+            excluded: HashSet::new(),
         });
+        if let Some(item) = self.items.last_mut() {
+            item.keep_unverified();
+        }
 
         self.sort_keep_cursor(options);
         self.ensure_selection_valid(options);
     }
 
-    fn sort_key_for_report(sort_mode: SortMode, report: &RepoReport) -> SortKey {
-        match sort_mode {
-            SortMode::Age => SortKey::Age(report.newest_mtime),
-            SortMode::Size => SortKey::Size {
-                bytes: report.total_size_bytes,
-                time: report.newest_mtime,
-            },
+    /// Whether this scan's head lookup for `repo_root` failed.
+    fn head_failed(&self, repo_root: &Path) -> bool {
+        self.scan_failures
+            .iter()
+            .any(|failure| failure.kind == CandidateErrorKind::GitHead && failure.path == repo_root)
+    }
+
+    /// Lists a repo that has no ignored artifacts (yet), for
+    /// `--include-empty-repos`. It can't be selected until one shows up.
+    fn push_empty_repo(
+        &mut self,
+        scan_roots: &ScanRoots,
+        options: &TuiOptions,
+        repo_root: PathBuf,
+    ) {
+        if self.items.iter().any(|i| i.report.repo_root == repo_root) {
+            return;
         }
+        let head = self.pending_heads.get(&repo_root).cloned().flatten();
+        self.items.push(RepoItem {
+            report: RepoReport {
+                repo_root: repo_root.clone(),
+                head,
+                artifacts: Vec::new(),
+                total_size_bytes: 0,
+                total_file_count: 0,
+                newest_mtime: None,
+                oldest_mtime: None,
+                newest_atime: None,
+                root_mtime: None,
+            },
+            // Heads are only looked up for repos with artifacts.
+            head_loaded: true,
+            head_failed: false,
+            remote_url: None,
+            selected: false,
+            selection_mode: SelectionMode::Auto,
+            repo_display: self.path_display.display(scan_roots, &repo_root),
+            group: scan_roots.top_level_parent(&repo_root),
+            excluded: HashSet::new(),
+        });
+        self.sort_keep_cursor(options);
+        self.ensure_selection_valid(options);
     }
 
-    fn sort_keep_cursor(&mut self, options: &TuiOptions) {
-        let current_repo_root = self.selected_repo_root(options);
+    /// Repos the scan came across that have no ignored artifact to list.
+    fn empty_repo_count(&self) -> usize {
+        let with_artifacts = self
+            .items
+            .iter()
+            .filter(|i| !i.report.artifacts.is_empty())
+            .map(|i| &i.report.repo_root)
+            .collect::<HashSet<_>>();
+        self.seen_repos
+            .iter()
+            .filter(|repo_root| !with_artifacts.contains(repo_root))
+            .count()
+    }
 
-        match self.sort_mode {
+    fn remove_artifact(&mut self, options: &TuiOptions, path: &Path) {
+        let Some(index) = self
+            .items
+            .iter()
+            .position(|i| i.report.artifacts.iter().any(|a| a.path == path))
+        else {
+            return;
+        };
+
+        let item = &mut self.items[index];
+        item.report.artifacts.retain(|a| a.path != path);
+        if item.report.artifacts.is_empty() {
+            self.items.remove(index);
+        } else {
+            recompute_report_totals(
+                &mut item.report,
+                self.sort_mode.artifact_order(options.age_basis),
+            );
+            if item.selection_mode == SelectionMode::Auto {
+                item.selected = should_auto_select(&item.report, options, self.now);
+                item.keep_unverified();
+            }
+        }
+
+        self.sort_keep_cursor(options);
+    }
+
+    fn sort_key_for_report(
+        sort_mode: SortMode,
+        report: &RepoReport,
+        now: SystemTime,
+        options: &TuiOptions,
+    ) -> SortKey {
+        match sort_mode {
+            SortMode::Age => SortKey::Age(report.newest_mtime),
+            SortMode::Size => SortKey::Size {
+                bytes: report.total_size_bytes,
+                time: report.newest_mtime,
+            },
+            SortMode::StaleRatio => SortKey::StaleRatio {
+                permille: (stale_ratio(report, now, options.age_basis) * 1000.0) as u64,
+                bytes: report.total_size_bytes,
+            },
+            SortMode::HeadAge => SortKey::HeadAge(report.head.as_ref().map(|h| h.unix_seconds)),
+        }
+    }
+
+    fn sort_keep_cursor(&mut self, options: &TuiOptions) {
+        let current_repo_root = self.selected_repo_root(options);
+
+        match self.sort_mode {
             SortMode::Age => {
                 self.items.sort_by(|a, b| {
                     let a_time = a.report.newest_mtime;
@@ -474,6 +1500,33 @@ impl App {
                         .then_with(|| a.report.repo_root.cmp(&b.report.repo_root))
                 });
             }
+            SortMode::StaleRatio => {
+                let (now, basis) = (self.now, options.age_basis);
+                self.items.sort_by(|a, b| {
+                    stale_ratio(&b.report, now, basis)
+                        .total_cmp(&stale_ratio(&a.report, now, basis))
+                        .then_with(|| b.report.total_size_bytes.cmp(&a.report.total_size_bytes))
+                        .then_with(|| a.report.repo_root.cmp(&b.report.repo_root))
+                });
+            }
+            SortMode::HeadAge => {
+                self.items.sort_by(|a, b| {
+                    let a_head = a.report.head.as_ref().map(|h| h.unix_seconds);
+                    let b_head = b.report.head.as_ref().map(|h| h.unix_seconds);
+
+                    match (a_head, b_head) {
+                        (Some(a), Some(b)) => a.cmp(&b),
+                        (Some(_), None) => CmpOrdering::Less,
+                        (None, Some(_)) => CmpOrdering::Greater,
+                        (None, None) => CmpOrdering::Equal,
+                    }
+                    .then_with(|| a.report.repo_root.cmp(&b.report.repo_root))
+                });
+            }
+        }
+        // Stable, so each group keeps the order above.
+        if options.group_by_parent {
+            self.items.sort_by(|a, b| a.group.cmp(&b.group));
         }
 
         self.restore_selection(options, current_repo_root);
@@ -495,6 +1548,7 @@ impl App {
     }
 
     fn restore_selection(&mut self, options: &TuiOptions, repo_root: Option<PathBuf>) {
+        let visibility = self.visibility(options);
         let visible_len = self.visible_len(options);
         if visible_len == 0 {
             self.table_state.select(None);
@@ -504,7 +1558,7 @@ impl App {
         if let Some(repo_root) = repo_root {
             let mut row = 0usize;
             for item in &self.items {
-                if !is_visible(&item.report, options) {
+                if !is_visible(&item.report, &visibility) {
                     continue;
                 }
 
@@ -520,10 +1574,11 @@ impl App {
     }
 
     fn selected_repo_root(&self, options: &TuiOptions) -> Option<PathBuf> {
+        let visibility = self.visibility(options);
         let selected_row = self.table_state.selected()?;
         let mut row = 0usize;
         for item in &self.items {
-            if !is_visible(&item.report, options) {
+            if !is_visible(&item.report, &visibility) {
                 continue;
             }
 
@@ -535,10 +1590,61 @@ impl App {
         None
     }
 
+    fn selected_item(&self, options: &TuiOptions) -> Option<&RepoItem> {
+        let visibility = self.visibility(options);
+        let selected_row = self.table_state.selected()?;
+        self.items
+            .iter()
+            .filter(|item| is_visible(&item.report, &visibility))
+            .nth(selected_row)
+    }
+
+    /// Re-verifies in the background that the artifacts of every selected,
+    /// visible repo are still gitignored. Artifacts already checked (or being
+    /// checked) are not queued again.
+    fn recheck_selected(&mut self, options: &TuiOptions, tx: &mpsc::Sender<AppEvent>) {
+        let visibility = self.visibility(options);
+        let mut pending = Vec::new();
+        for item in &self.items {
+            if !is_visible(&item.report, &visibility) {
+                continue;
+            }
+            for artifact in item.planned_artifacts() {
+                if !self.ignore_checks.contains_key(&artifact.path) {
+                    pending.push((artifact.repo_root.clone(), artifact.path.clone()));
+                }
+            }
+        }
+
+        self.request_ignore_checks(pending, tx);
+    }
+
+    fn request_ignore_checks(
+        &mut self,
+        pending: Vec<(PathBuf, PathBuf)>,
+        tx: &mpsc::Sender<AppEvent>,
+    ) {
+        if pending.is_empty() {
+            return;
+        }
+
+        for (_, path) in &pending {
+            self.ignore_checks
+                .insert(path.clone(), IgnoreCheck::Pending);
+        }
+        spawn_ignore_recheck_worker(
+            self.scan_id,
+            pending,
+            self.scan_options.git.clone(),
+            tx.clone(),
+        );
+    }
+
     fn visible_len(&self, options: &TuiOptions) -> usize {
+        let visibility = self.visibility(options);
         self.items
             .iter()
-            .filter(|item| is_visible(&item.report, options))
+            .filter(|item| is_visible(&item.report, &visibility))
             .count()
     }
 
@@ -586,31 +1692,264 @@ impl App {
         self.table_state.select(Some(next));
     }
 
+    /// The rows of the repo table: one per visible repo, preceded by a
+    /// header per group with `group_by_parent`. Items are sorted by group
+    /// then, so each group's repos are adjacent.
+    fn table_rows(&self, options: &TuiOptions) -> Vec<TableRow> {
+        let visibility = self.visibility(options);
+        let visible = self
+            .items
+            .iter()
+            .filter(|item| is_visible(&item.report, &visibility));
+        let mut rows: Vec<TableRow> = Vec::new();
+        let mut group_at = 0;
+        for (index, item) in visible.enumerate() {
+            if options.group_by_parent {
+                match rows.get_mut(group_at) {
+                    Some(TableRow::Group(group)) if group.path == item.group => {
+                        group.repos += 1;
+                        group.size_bytes = group
+                            .size_bytes
+                            .saturating_add(item.report.total_size_bytes);
+                    }
+                    _ => {
+                        group_at = rows.len();
+                        rows.push(TableRow::Group(GroupRow {
+                            path: item.group.clone(),
+                            repos: 1,
+                            size_bytes: item.report.total_size_bytes,
+                        }));
+                    }
+                }
+            }
+            rows.push(TableRow::Repo(index));
+        }
+        rows
+    }
+
+    /// Maps a terminal cell to a visible row index of the repo table;
+    /// group headers map to nothing.
+    fn table_row_at(&self, options: &TuiOptions, column: u16, row: u16) -> Option<usize> {
+        let area = self.table_area?;
+        // The first line of the table area is the header row.
+        if column < area.x || column >= area.right() || row <= area.y || row >= area.bottom() {
+            return None;
+        }
+        let index = self.display_state.offset() + usize::from(row - area.y - 1);
+        match self.table_rows(options).get(index) {
+            Some(TableRow::Repo(repo)) => Some(*repo),
+            _ => None,
+        }
+    }
+
     fn toggle_current(&mut self, options: &TuiOptions) {
+        let visibility = self.visibility(options);
         let Some(selected_row) = self.table_state.selected() else {
             return;
         };
 
         let mut row = 0usize;
         for item in &mut self.items {
-            if !is_visible(&item.report, options) {
+            if !is_visible(&item.report, &visibility) {
                 continue;
             }
             if row == selected_row {
+                if item.report.artifacts.is_empty() {
+                    return;
+                }
                 item.selected = !item.selected;
                 item.selection_mode = SelectionMode::Manual;
+                item.excluded.clear();
                 return;
             }
             row += 1;
         }
     }
 
-    fn select_all(&mut self, value: bool) {
-        self.new_repo_default_selected = Some(value);
+    fn selected_item_mut(&mut self, options: &TuiOptions) -> Option<&mut RepoItem> {
+        let visibility = self.visibility(options);
+        let selected_row = self.table_state.selected()?;
+        self.items
+            .iter_mut()
+            .filter(|item| is_visible(&item.report, &visibility))
+            .nth(selected_row)
+    }
+
+    fn move_detail_cursor_by(&mut self, options: &TuiOptions, delta: isize) {
+        let len = self
+            .selected_item(options)
+            .map_or(0, |item| item.report.artifacts.len());
+        let max = len.saturating_sub(1) as isize;
+        self.detail_cursor = (self.detail_cursor as isize + delta).clamp(0, max) as usize;
+    }
+
+    /// Plans or keeps the artifact under the detail cursor.
+    fn toggle_detail_artifact(&mut self, options: &TuiOptions) {
+        let cursor = self.detail_cursor;
+        let Some(item) = self.selected_item_mut(options) else {
+            return;
+        };
+        let Some(artifact) = item.report.artifacts.get(cursor) else {
+            return;
+        };
+        let path = artifact.path.clone();
+        let planned = item.selected && !item.excluded.contains(&path);
+        item.set_artifacts_selected(vec![path], !planned);
+    }
+
+    /// Hands the highlighted repo (or every visible one) back to the auto
+    /// policy, dropping any manual choice.
+    fn reset_to_auto(&mut self, options: &TuiOptions, all: bool) {
+        let visibility = self.visibility(options);
+        let highlighted = self.table_state.selected();
+        let now = self.now;
+        let visible = self
+            .items
+            .iter_mut()
+            .filter(|item| is_visible(&item.report, &visibility))
+            .enumerate();
+        for (row, item) in visible {
+            if !all && Some(row) != highlighted {
+                continue;
+            }
+            item.selection_mode = SelectionMode::Auto;
+            item.selected = should_auto_select(&item.report, options, now);
+            item.excluded.clear();
+            item.keep_unverified();
+        }
+    }
+
+    fn select_all(&mut self, options: &TuiOptions, value: bool) {
+        let visibility = self.visibility(options);
         for item in &mut self.items {
+            if !is_visible(&item.report, &visibility) || item.report.artifacts.is_empty() {
+                continue;
+            }
             item.selected = value;
             item.selection_mode = SelectionMode::Manual;
+            item.excluded.clear();
+        }
+    }
+
+    /// Flips what is planned in every visible repo: unselected repos become
+    /// fully selected, and in selected ones the kept artifacts become the
+    /// planned ones. Later repos go back to the auto policy.
+    fn invert_selection(&mut self, options: &TuiOptions) {
+        let visibility = self.visibility(options);
+        for item in &mut self.items {
+            if !is_visible(&item.report, &visibility) || item.report.artifacts.is_empty() {
+                continue;
+            }
+            if item.selected && item.is_partially_selected() {
+                item.excluded = item
+                    .report
+                    .artifacts
+                    .iter()
+                    .map(|a| a.path.clone())
+                    .filter(|path| !item.excluded.contains(path))
+                    .collect();
+            } else {
+                item.selected = !item.selected;
+                item.excluded.clear();
+            }
+            item.selection_mode = SelectionMode::Manual;
+        }
+        self.new_repo_default_selected = None;
+    }
+
+    /// Per-name totals over the visible repos, with how many of each name's
+    /// dirs are currently planned for deletion.
+    fn artifact_types(&self, options: &TuiOptions) -> Vec<(ArtifactNameTotal, usize)> {
+        let visibility = self.visibility(options);
+        let visible = || {
+            self.items
+                .iter()
+                .filter(move |item| is_visible(&item.report, &visibility))
+        };
+        let planned = visible()
+            .flat_map(|item| item.planned_artifacts())
+            .filter_map(|a| a.path.file_name())
+            .fold(HashMap::<&OsStr, usize>::new(), |mut counts, name| {
+                *counts.entry(name).or_default() += 1;
+                counts
+            });
+
+        totals_by_artifact_name(visible().flat_map(|item| item.report.artifacts.iter()))
+            .into_iter()
+            .map(|total| {
+                let planned = planned.get(total.name.as_os_str()).copied().unwrap_or(0);
+                (total, planned)
+            })
+            .collect()
+    }
+
+    /// Plans every visible artifact named `name` unless all of them already
+    /// are, in which case they are all kept instead.
+    fn toggle_artifact_type(&mut self, options: &TuiOptions, name: &OsStr) {
+        let all_planned = self
+            .artifact_types(options)
+            .iter()
+            .find(|(total, _)| total.name == name)
+            .is_some_and(|(total, planned)| *planned == total.dirs);
+        let visibility = self.visibility(options);
+        for item in &mut self.items {
+            if is_visible(&item.report, &visibility) {
+                item.set_artifact_name_selected(name, !all_planned);
+            }
+        }
+    }
+
+    /// Cycles how repos discovered later are selected: by the auto policy,
+    /// always selected, or always deselected.
+    fn cycle_new_repo_default(&mut self) {
+        self.new_repo_default_selected = match self.new_repo_default_selected {
+            None => Some(true),
+            Some(true) => Some(false),
+            Some(false) => None,
+        };
+    }
+}
+
+/// Totals across every clean run in this TUI session. Dry runs delete
+/// nothing, so what they would have reclaimed is kept separately.
+#[derive(Debug, Clone, Copy, Default)]
+struct SessionTotals {
+    deleted_dirs: usize,
+    deleted_bytes: u64,
+    dry_run_dirs: usize,
+    dry_run_bytes: u64,
+}
+
+impl SessionTotals {
+    fn record(&mut self, summary: &DeleteSummary, dry_run: bool) {
+        if dry_run {
+            self.dry_run_dirs += summary.kept_paths;
+            self.dry_run_bytes = self
+                .dry_run_bytes
+                .saturating_add(summary.kept_planned_bytes);
+        } else {
+            self.deleted_dirs += summary.deleted_paths;
+            self.deleted_bytes = self.deleted_bytes.saturating_add(summary.deleted_bytes);
+        }
+    }
+
+    fn label(&self, unit: Option<ByteUnit>) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.deleted_dirs > 0 {
+            parts.push(format!(
+                "reclaimed this session: {} ({} dirs)",
+                format_bytes_with(self.deleted_bytes, unit),
+                self.deleted_dirs
+            ));
         }
+        if self.dry_run_dirs > 0 {
+            parts.push(format!(
+                "dry runs this session: would reclaim {} ({} dirs)",
+                format_bytes_with(self.dry_run_bytes, unit),
+                self.dry_run_dirs
+            ));
+        }
+        (!parts.is_empty()).then(|| parts.join("  "))
     }
 }
 
@@ -618,12 +1957,99 @@ impl App {
 struct RepoItem {
     report: RepoReport,
     head_loaded: bool,
+    /// The head lookup failed, so a missing head doesn't mean no commits.
+    head_failed: bool,
+    remote_url: Option<String>,
     selected: bool,
     selection_mode: SelectionMode,
     repo_display: String,
+    /// Top-level dir under the scan root, for `group_by_parent`.
+    group: PathBuf,
+    /// Artifacts kept even though the repo is selected.
+    excluded: HashSet<PathBuf>,
 }
 
-impl RepoItem {}
+impl RepoItem {
+    /// Artifacts that will be deleted if the current selection is cleaned.
+    fn planned_artifacts(&self) -> impl Iterator<Item = &ArtifactRecord> {
+        self.report
+            .artifacts
+            .iter()
+            .filter(|a| self.selected && a.is_deletable() && !self.excluded.contains(&a.path))
+    }
+
+    fn is_partially_selected(&self) -> bool {
+        self.selected
+            && self
+                .report
+                .artifacts
+                .iter()
+                .any(|a| self.excluded.contains(&a.path))
+    }
+
+    /// Keeps unverified artifacts out of a selection the policy made; only
+    /// picking them by hand plans them. A repo left with nothing planned
+    /// is deselected.
+    fn keep_unverified(&mut self) {
+        if !self.selected {
+            return;
+        }
+        self.excluded.extend(
+            self.report
+                .artifacts
+                .iter()
+                .filter(|a| a.unverified)
+                .map(|a| a.path.clone()),
+        );
+        if self.planned_artifacts().next().is_none() {
+            self.selected = false;
+            self.excluded.clear();
+        }
+    }
+
+    /// Plans or keeps every artifact of this repo named `name`. Selecting a
+    /// name in an unselected repo selects only the matching artifacts.
+    fn set_artifact_name_selected(&mut self, name: &OsStr, value: bool) {
+        let matching = self
+            .report
+            .artifacts
+            .iter()
+            .filter(|a| a.path.file_name() == Some(name))
+            .map(|a| a.path.clone())
+            .collect::<Vec<_>>();
+        self.set_artifacts_selected(matching, value);
+    }
+
+    /// Plans or keeps the given artifacts of this repo, selecting or
+    /// deselecting the repo itself as needed.
+    fn set_artifacts_selected(&mut self, matching: Vec<PathBuf>, value: bool) {
+        if matching.is_empty() {
+            return;
+        }
+
+        if value {
+            if !self.selected {
+                self.selected = true;
+                self.excluded = self
+                    .report
+                    .artifacts
+                    .iter()
+                    .map(|a| a.path.clone())
+                    .collect();
+            }
+            for path in &matching {
+                self.excluded.remove(path);
+            }
+        } else if self.selected {
+            self.excluded.extend(matching);
+            if self.planned_artifacts().next().is_none() {
+                self.selected = false;
+                self.excluded.clear();
+            }
+        }
+        self.selection_mode = SelectionMode::Manual;
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SelectionMode {
@@ -634,6 +2060,9 @@ enum SelectionMode {
 #[derive(Debug)]
 enum Screen {
     Main,
+    ArtifactSettings(ArtifactSettings),
+    /// Cursor into `App::artifact_types`.
+    ArtifactTypes(usize),
     Confirm(ConfirmData),
     Cleaning(CleaningData),
     Result,
@@ -642,21 +2071,192 @@ enum Screen {
 #[derive(Debug, Clone, Copy)]
 enum ScreenKind {
     Main,
+    ArtifactSettings,
+    ArtifactTypes,
     Confirm,
     Cleaning,
     Result,
 }
 
+/// State of the artifact dir names popup; edits only take effect (and
+/// trigger a rescan) when applied.
+#[derive(Debug)]
+struct ArtifactSettings {
+    /// Sorted by name; the flag is whether the name is enabled.
+    entries: Vec<(OsString, bool)>,
+    cursor: usize,
+    /// Name being typed after pressing '+', if any.
+    input: Option<String>,
+    /// Why the last Enter did not apply, if it didn't.
+    note: Option<String>,
+}
+
+impl ArtifactSettings {
+    fn new(enabled: &HashSet<OsString>, disabled: &HashSet<OsString>) -> Self {
+        let mut entries = enabled
+            .iter()
+            .map(|name| (name.clone(), true))
+            .chain(
+                disabled
+                    .iter()
+                    .filter(|name| !enabled.contains(*name))
+                    .map(|name| (name.clone(), false)),
+            )
+            .collect::<Vec<_>>();
+        entries.sort();
+        Self {
+            entries,
+            cursor: 0,
+            input: None,
+            note: None,
+        }
+    }
+
+    fn move_cursor_by(&mut self, delta: isize) {
+        let max = self.entries.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(max);
+    }
+
+    fn toggle_current(&mut self) {
+        if let Some((_, enabled)) = self.entries.get_mut(self.cursor) {
+            *enabled = !*enabled;
+        }
+    }
+
+    /// Adds (or re-enables) a name and moves the cursor to it. A name may
+    /// be a `/`-separated path suffix like `.build/checkouts`; ones with an
+    /// empty, `.` or `..` component are ignored.
+    fn add(&mut self, name: &str) {
+        let name = name.trim();
+        if name.contains('\\')
+            || name
+                .split('/')
+                .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            return;
+        }
+        let name = OsString::from(name);
+        let index = match self.entries.iter().position(|(n, _)| *n == name) {
+            Some(index) => {
+                self.entries[index].1 = true;
+                index
+            }
+            None => {
+                let index = self.entries.partition_point(|(n, _)| *n < name);
+                self.entries.insert(index, (name, true));
+                index
+            }
+        };
+        self.cursor = index;
+    }
+
+    fn split(&self) -> (HashSet<OsString>, HashSet<OsString>) {
+        let enabled = self
+            .entries
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name.clone())
+            .collect();
+        let disabled = self
+            .entries
+            .iter()
+            .filter(|(_, enabled)| !*enabled)
+            .map(|(name, _)| name.clone())
+            .collect();
+        (enabled, disabled)
+    }
+}
+
 #[derive(Debug)]
 struct ConfirmData {
     targets: Vec<DeleteTarget>,
+    remote_urls: HashMap<PathBuf, String>,
     selected_repos: usize,
     planned_dirs: usize,
     planned_bytes: u64,
+    /// First visible line of the per-repo tree.
+    scroll: usize,
+    /// Tree lines that fit on screen as last drawn, for PageUp/PageDown.
+    page_lines: usize,
+    /// Index of the highlighted dir, in tree order.
+    cursor: usize,
+    /// Dirs taken out of the plan on this screen; the repo selection on
+    /// Main is left alone.
+    spared: HashSet<PathBuf>,
+    /// Set while the one-time "deletion is permanent" prompt is shown; holds
+    /// what has been typed so far.
+    first_delete_input: Option<String>,
+    /// Quick clean of the highlighted repo only (`D`), independent of the
+    /// repo selection.
+    quick: bool,
+}
+
+impl ConfirmData {
+    fn move_cursor_by(&mut self, delta: isize) {
+        let max = self.targets.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(max);
+    }
+
+    /// Targets in the order the per-repo tree lists them.
+    fn tree_targets(&self) -> Vec<&DeleteTarget> {
+        group_targets_by_repo(&self.targets)
+            .into_iter()
+            .flat_map(|group| group.targets)
+            .collect()
+    }
+
+    /// Line of the highlighted dir in the tree, counting repo lines.
+    fn cursor_line(&self) -> usize {
+        let mut line = 0;
+        let mut index = 0;
+        for group in group_targets_by_repo(&self.targets) {
+            line += 1;
+            if self.cursor < index + group.targets.len() {
+                return line + self.cursor - index;
+            }
+            line += group.targets.len();
+            index += group.targets.len();
+        }
+        line
+    }
+
+    /// Takes the highlighted dir out of the plan, or puts it back.
+    fn toggle_cursor_target(&mut self) {
+        let Some(path) = self.tree_targets().get(self.cursor).map(|t| t.path.clone()) else {
+            return;
+        };
+        if !self.spared.remove(&path) {
+            self.spared.insert(path);
+        }
+        let remaining = self.remaining_targets();
+        self.planned_dirs = remaining.len();
+        self.planned_bytes = remaining.iter().map(|t| t.planned_bytes).sum();
+        self.selected_repos = remaining
+            .iter()
+            .map(|t| &t.repo_root)
+            .collect::<HashSet<_>>()
+            .len();
+    }
+
+    /// What confirming deletes: the plan minus the spared dirs.
+    fn remaining_targets(&self) -> Vec<DeleteTarget> {
+        self.targets
+            .iter()
+            .filter(|t| !self.spared.contains(&t.path))
+            .cloned()
+            .collect()
+    }
+
+    /// A partial quick clean leaves the repo with artifacts, so it is
+    /// finished like a regular clean.
+    fn is_quick(&self) -> bool {
+        self.quick && self.spared.is_empty()
+    }
 }
 
 #[derive(Debug)]
 struct CleaningData {
+    targets: Vec<DeleteTarget>,
     total: usize,
     planned_bytes: u64,
     processed: usize,
@@ -667,12 +2267,42 @@ struct CleaningData {
     current: Option<String>,
     started_at: Instant,
     cancel_requested: bool,
+    quick: bool,
+}
+
+/// Summary lines from the Cleaning screen's counters, for when the clean
+/// worker did not report back in time.
+fn partial_clean_summary(
+    scan_roots: &ScanRoots,
+    cleaning: &CleaningData,
+    options: &TuiOptions,
+) -> Vec<String> {
+    let unit = options.display_unit;
+    let dry_run_label = if options.dry_run { " (dry run)" } else { "" };
+    vec![
+        format!("root: {scan_roots}"),
+        format!(
+            "status: interrupted; deletion was still running, so totals are partial ({} of {} dirs handled)",
+            cleaning.processed, cleaning.total
+        ),
+        format!(
+            "planned: {} dirs, reclaim {}{dry_run_label}",
+            cleaning.total,
+            format_bytes_with(cleaning.planned_bytes, unit)
+        ),
+        format!(
+            "deleted: {} dirs, reclaimed {}",
+            cleaning.deleted_paths,
+            format_bytes_with(cleaning.deleted_bytes, unit)
+        ),
+        format!("skipped: {} dirs", cleaning.skipped_paths),
+        format!("errors: {}", cleaning.error_count),
+    ]
 }
 
 fn handle_key(
-    scan_root: &Path,
+    scan_roots: &ScanRoots,
     options: &TuiOptions,
-    scan_cancel: &Arc<AtomicBool>,
     clean_cancel: &Arc<AtomicBool>,
     tx: &mpsc::Sender<AppEvent>,
     app: &mut App,
@@ -680,6 +2310,8 @@ fn handle_key(
 ) -> Result<bool> {
     let screen_kind = match &app.screen {
         Screen::Main => ScreenKind::Main,
+        Screen::ArtifactSettings(_) => ScreenKind::ArtifactSettings,
+        Screen::ArtifactTypes(_) => ScreenKind::ArtifactTypes,
         Screen::Confirm(_) => ScreenKind::Confirm,
         Screen::Cleaning(_) => ScreenKind::Cleaning,
         Screen::Result => ScreenKind::Result,
@@ -703,122 +2335,601 @@ fn handle_key(
         return Ok(true);
     }
 
-    match screen_kind {
-        ScreenKind::Main => handle_key_main(scan_root, options, app, key),
-        ScreenKind::Confirm => {
-            handle_key_confirm(scan_root, options, scan_cancel, clean_cancel, tx, app, key)
+    if app.show_help {
+        if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+            app.show_help = false;
         }
+        return Ok(false);
+    }
+    if key.code == KeyCode::Char('?') && !is_typing(&app.screen) {
+        app.show_help = true;
+        return Ok(false);
+    }
+
+    match screen_kind {
+        ScreenKind::Main => handle_key_main(scan_roots, options, tx, app, key),
+        ScreenKind::ArtifactSettings => handle_key_artifact_settings(scan_roots, tx, app, key),
+        ScreenKind::ArtifactTypes => handle_key_artifact_types(options, tx, app, key),
+        ScreenKind::Confirm => handle_key_confirm(scan_roots, options, clean_cancel, tx, app, key),
         ScreenKind::Cleaning => handle_key_cleaning(clean_cancel, app, key),
-        ScreenKind::Result => Ok(true),
+        ScreenKind::Result => handle_key_result(options, app, key),
     }
 }
 
-fn handle_key_main(
-    _scan_root: &Path,
-    options: &TuiOptions,
-    app: &mut App,
-    key: KeyEvent,
-) -> Result<bool> {
+/// Whether keys currently go into a text input rather than to commands.
+fn is_typing(screen: &Screen) -> bool {
+    match screen {
+        Screen::Confirm(confirm) => confirm.first_delete_input.is_some(),
+        Screen::ArtifactSettings(settings) => settings.input.is_some(),
+        _ => false,
+    }
+}
+
+fn handle_key_result(options: &TuiOptions, app: &mut App, key: KeyEvent) -> Result<bool> {
     match key.code {
-        KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
-        KeyCode::Up => app.move_cursor_up(options),
-        KeyCode::Down => app.move_cursor_down(options),
-        KeyCode::PageUp => app.move_cursor_by(options, -10),
-        KeyCode::PageDown => app.move_cursor_by(options, 10),
-        KeyCode::Char(' ') => app.toggle_current(options),
-        KeyCode::Char('a') => app.select_all(true),
-        KeyCode::Char('n') => app.select_all(false),
-        KeyCode::Tab => app.toggle_sort_mode(options),
-        KeyCode::Enter => {
-            let targets = plan_delete_targets(
-                app.items
-                    .iter()
-                    .filter(|item| is_visible(&item.report, options))
-                    .map(|item| (&item.report, item.selected)),
+        KeyCode::Char('q') => Ok(true),
+        KeyCode::Char('w') => {
+            let dir = options.report_dir.as_deref().unwrap_or(Path::new("."));
+            let summary = app
+                .last_summary
+                .as_ref()
+                .map(|(summary, canceled)| summary_json(summary, options.dry_run, *canceled));
+            let written = write_result_report(
+                dir,
+                &app.result_lines,
+                summary.as_deref(),
+                SystemTime::now(),
             );
-
-            if targets.is_empty() {
-                app.screen = Screen::Result;
-                app.result_lines = vec!["Nothing to delete for current selection.".to_string()];
-                return Ok(false);
-            }
-
-            let planned_dirs = targets.len();
-            let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
-            let selected_repos = app
-                .items
-                .iter()
-                .filter(|item| item.selected && is_visible(&item.report, options))
-                .count();
-
-            app.screen = Screen::Confirm(ConfirmData {
-                targets,
-                selected_repos,
-                planned_dirs,
-                planned_bytes,
+            app.result_note = Some(match written {
+                Ok(path) => format!("Saved to {}", path.display()),
+                Err(err) => format!("Failed to save: {err:#}"),
             });
+            Ok(false)
+        }
+        _ => {
+            app.screen = Screen::Main;
+            app.result_lines.clear();
+            app.last_summary = None;
+            app.result_note = None;
+            Ok(false)
         }
-        _ => {}
     }
-
-    Ok(false)
 }
 
-fn handle_key_confirm(
-    scan_root: &Path,
-    options: &TuiOptions,
-    scan_cancel: &Arc<AtomicBool>,
+/// Writes the result text, then the full summary as the JSON object
+/// `clean --progress-json` ends with, to `clean-code-result-<unix seconds>.txt` in
+/// `dir`. An existing report is never overwritten: a second save in the
+/// same second gets a `-1`, `-2`, ... suffix.
+fn write_result_report(
+    dir: &Path,
+    lines: &[String],
+    summary_json: Option<&str>,
+    now: SystemTime,
+) -> Result<PathBuf> {
+    let stamp = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut contents = lines
+        .iter()
+        .filter(|line| line.as_str() != RESULT_FOOTER)
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    if let Some(json) = summary_json {
+        contents.push_str(&format!("\n{json}\n"));
+    }
+
+    let mut attempt = 0;
+    loop {
+        let name = match attempt {
+            0 => format!("clean-code-result-{stamp}.txt"),
+            n => format!("clean-code-result-{stamp}-{n}.txt"),
+        };
+        let path = dir.join(name);
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path);
+        match file {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to write {}", path.display()));
+            }
+        }
+    }
+}
+
+fn handle_key_main(
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+    key: KeyEvent,
+) -> Result<bool> {
+    if app.focus == Focus::Detail {
+        match key.code {
+            KeyCode::Left | KeyCode::Esc => app.focus = Focus::Table,
+            KeyCode::Up => app.move_detail_cursor_by(options, -1),
+            KeyCode::Down => app.move_detail_cursor_by(options, 1),
+            KeyCode::PageUp => app.move_detail_cursor_by(options, -10),
+            KeyCode::PageDown => app.move_detail_cursor_by(options, 10),
+            KeyCode::Char(' ') => {
+                app.toggle_detail_artifact(options);
+                app.recheck_selected(options, tx);
+            }
+            _ => return handle_key_main_table(scan_roots, options, tx, app, key),
+        }
+        return Ok(false);
+    }
+    handle_key_main_table(scan_roots, options, tx, app, key)
+}
+
+fn handle_key_main_table(
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+    key: KeyEvent,
+) -> Result<bool> {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+        KeyCode::Right if app.selected_item(options).is_some() => {
+            app.focus = Focus::Detail;
+            app.detail_cursor = 0;
+        }
+        KeyCode::Up => app.move_cursor_up(options),
+        KeyCode::Down => app.move_cursor_down(options),
+        KeyCode::PageUp => app.move_cursor_by(options, -(app.page_rows as isize)),
+        KeyCode::PageDown => app.move_cursor_by(options, app.page_rows as isize),
+        KeyCode::Char(' ') => {
+            app.toggle_current(options);
+            app.recheck_selected(options, tx);
+        }
+        KeyCode::Char('a') => {
+            app.select_all(options, true);
+            app.recheck_selected(options, tx);
+        }
+        KeyCode::Char('n') => app.select_all(options, false),
+        KeyCode::Char('i') => {
+            app.invert_selection(options);
+            app.recheck_selected(options, tx);
+        }
+        KeyCode::Char(c @ ('u' | 'U')) => {
+            app.reset_to_auto(options, c == 'U');
+            app.recheck_selected(options, tx);
+        }
+        KeyCode::Char('d') => app.cycle_new_repo_default(),
+        KeyCode::Char('f') if app.counters.timed_out => {
+            app.scan_timeout = None;
+            app.resume_scan(scan_roots, tx);
+        }
+        KeyCode::Char('g') => app.cycle_age_filter(options),
+        KeyCode::Char('+') | KeyCode::Char('=') => app.step_min_size(options, true),
+        KeyCode::Char('-') => app.step_min_size(options, false),
+        KeyCode::Char('p') => app.cycle_path_display(scan_roots),
+        KeyCode::Char('t') => app.screen = Screen::ArtifactTypes(0),
+        KeyCode::Char('A') => {
+            app.screen = Screen::ArtifactSettings(ArtifactSettings::new(
+                &app.scan_options.artifacts.entries(),
+                &app.disabled_artifact_names,
+            ));
+        }
+        KeyCode::Tab => app.toggle_sort_mode(options),
+        KeyCode::Enter => confirm_selection(options, tx, app),
+        KeyCode::Char('D') => {
+            let Some(item) = app.selected_item(options) else {
+                return Ok(false);
+            };
+            let targets = plan_delete_targets(std::iter::once((&item.report, true)));
+            if !targets.is_empty() {
+                open_confirm(app, tx, targets, 1, true);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(false)
+}
+
+/// Plans the visible selection and moves to the Confirm screen, or to a
+/// "nothing to delete" result.
+fn confirm_selection(options: &TuiOptions, tx: &mpsc::Sender<AppEvent>, app: &mut App) {
+    let visibility = app.visibility(options);
+    let mut targets = plan_delete_targets(
+        app.items
+            .iter()
+            .filter(|item| is_visible(&item.report, &visibility))
+            .map(|item| (&item.report, item.selected)),
+    );
+    let excluded = app
+        .items
+        .iter()
+        .flat_map(|item| item.excluded.iter())
+        .collect::<HashSet<_>>();
+    targets.retain(|target| !excluded.contains(&target.path));
+
+    if targets.is_empty() {
+        app.screen = Screen::Result;
+        app.result_lines = vec![
+            "Nothing to delete for current selection.".to_string(),
+            String::new(),
+            RESULT_FOOTER.to_string(),
+        ];
+        app.last_summary = None;
+        return;
+    }
+
+    let selected_repos = app
+        .items
+        .iter()
+        .filter(|item| item.selected && is_visible(&item.report, &visibility))
+        .count();
+    open_confirm(app, tx, targets, selected_repos, false);
+}
+
+/// `--clean-all`: once the initial scan is done, selects every visible repo
+/// the staleness policy picks and opens the Confirm screen (accepting it
+/// right away with `--yes`).
+fn start_auto_clean(
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
+    clean_cancel: &Arc<AtomicBool>,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+) {
+    // Repos that only show up later (e.g. from a refresh) must not join a
+    // plan that was made without them.
+    app.new_repo_default_selected = Some(false);
+    let visibility = app.visibility(options);
+    let now = app.now;
+    for item in &mut app.items {
+        if is_visible(&item.report, &visibility) {
+            item.selected = should_auto_select(&item.report, options, now);
+            item.selection_mode = SelectionMode::Manual;
+            item.excluded.clear();
+            item.keep_unverified();
+        }
+    }
+
+    confirm_selection(options, tx, app);
+    if options.yes && matches!(app.screen, Screen::Confirm(_)) {
+        accept_confirm(scan_roots, options, clean_cancel, tx, app);
+    }
+}
+
+fn open_confirm(
+    app: &mut App,
+    tx: &mpsc::Sender<AppEvent>,
+    targets: Vec<DeleteTarget>,
+    selected_repos: usize,
+    quick: bool,
+) {
+    let unchecked = targets
+        .iter()
+        .filter(|t| !app.ignore_checks.contains_key(&t.path))
+        .map(|t| (t.repo_root.clone(), t.path.clone()))
+        .collect();
+    app.request_ignore_checks(unchecked, tx);
+
+    let planned_dirs = targets.len();
+    let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
+
+    let planned_repos = targets.iter().map(|t| &t.repo_root).collect::<HashSet<_>>();
+    let remote_urls = app
+        .items
+        .iter()
+        .filter(|item| planned_repos.contains(&item.report.repo_root))
+        .filter_map(|item| {
+            let url = item.remote_url.clone()?;
+            Some((item.report.repo_root.clone(), url))
+        })
+        .collect();
+
+    app.screen = Screen::Confirm(ConfirmData {
+        targets,
+        remote_urls,
+        selected_repos,
+        planned_dirs,
+        planned_bytes,
+        scroll: 0,
+        page_lines: DEFAULT_PAGE_ROWS,
+        cursor: 0,
+        spared: HashSet::new(),
+        first_delete_input: None,
+        quick,
+    });
+}
+
+fn start_clean(
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
+    clean_cancel: &Arc<AtomicBool>,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+    targets: Vec<DeleteTarget>,
+    quick: bool,
+) {
+    app.scan_cancel.store(true, Ordering::Relaxed);
+    clean_cancel.store(false, Ordering::Relaxed);
+    spawn_clean_worker(
+        targets.clone(),
+        DeleteOptions {
+            dry_run: options.dry_run,
+            parallel: options.parallel_delete,
+            require_clean_worktree: options.require_clean_worktree,
+            prune_empty_parents: options.prune_empty_parents,
+            free_goal: None,
+            scan: app.scan_options.clone(),
+        },
+        Arc::clone(clean_cancel),
+        tx.clone(),
+    );
+
+    let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
+    let current = targets.first().map(|target| {
+        format!(
+            "{}  {}",
+            scan_roots.display_rel(&target.repo_root),
+            display_rel_path(&target.repo_root, &target.path)
+        )
+    });
+    app.screen = Screen::Cleaning(CleaningData {
+        total: targets.len(),
+        targets,
+        planned_bytes,
+        processed: 0,
+        deleted_paths: 0,
+        deleted_bytes: 0,
+        skipped_paths: 0,
+        error_count: 0,
+        current,
+        started_at: Instant::now(),
+        cancel_requested: false,
+        quick,
+    });
+}
+
+fn handle_key_confirm(
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
     clean_cancel: &Arc<AtomicBool>,
     tx: &mpsc::Sender<AppEvent>,
     app: &mut App,
     key: KeyEvent,
 ) -> Result<bool> {
-    let targets = match &app.screen {
-        Screen::Confirm(confirm) => confirm.targets.clone(),
-        _ => return Ok(false),
+    let Screen::Confirm(confirm) = &mut app.screen else {
+        return Ok(false);
     };
 
+    if let Some(input) = &mut confirm.first_delete_input {
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter if input.trim().eq_ignore_ascii_case("yes") => {
+                let targets = confirm.remaining_targets();
+                let quick = confirm.is_quick();
+                app.state.set(FIRST_DELETE_ACKNOWLEDGED, "true");
+                // On failure the prompt simply shows again next run.
+                let _ = app.state.save();
+                start_clean(scan_roots, options, clean_cancel, tx, app, targets, quick);
+            }
+            KeyCode::Enter => input.clear(),
+            KeyCode::Esc => confirm.first_delete_input = None,
+            _ => {}
+        }
+        return Ok(false);
+    }
+
     match key.code {
         KeyCode::Char('y') | KeyCode::Char('Y') => {
-            scan_cancel.store(true, Ordering::Relaxed);
-            clean_cancel.store(false, Ordering::Relaxed);
-            spawn_clean_worker(
-                targets.clone(),
-                options.dry_run,
-                Arc::clone(clean_cancel),
-                tx.clone(),
-            );
-
-            let planned_bytes = targets.iter().map(|t| t.planned_bytes).sum::<u64>();
-            let current = targets.first().map(|target| {
-                format!(
-                    "{}  {}",
-                    display_rel_path(scan_root, &target.repo_root),
-                    display_rel_path(&target.repo_root, &target.path)
-                )
-            });
-            app.screen = Screen::Cleaning(CleaningData {
-                total: targets.len(),
-                planned_bytes,
-                processed: 0,
-                deleted_paths: 0,
-                deleted_bytes: 0,
-                skipped_paths: 0,
-                error_count: 0,
-                current,
-                started_at: Instant::now(),
-                cancel_requested: false,
-            });
+            accept_confirm(scan_roots, options, clean_cancel, tx, app);
             Ok(false)
         }
         KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') | KeyCode::Char('N') => {
             app.screen = Screen::Main;
             Ok(false)
         }
+        KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown => {
+            if let Screen::Confirm(confirm) = &mut app.screen {
+                let page = confirm.page_lines as isize;
+                confirm.move_cursor_by(match key.code {
+                    KeyCode::Up => -1,
+                    KeyCode::Down => 1,
+                    KeyCode::PageUp => -page,
+                    _ => page,
+                });
+            }
+            Ok(false)
+        }
+        KeyCode::Char(' ') => {
+            if let Screen::Confirm(confirm) = &mut app.screen {
+                confirm.toggle_cursor_target();
+            }
+            Ok(false)
+        }
         _ => Ok(false),
     }
 }
 
+/// Starts the confirmed clean, unless the one-time acknowledgment of
+/// permanent deletion is still needed.
+fn accept_confirm(
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
+    clean_cancel: &Arc<AtomicBool>,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+) {
+    let Screen::Confirm(confirm) = &mut app.screen else {
+        return;
+    };
+    let targets = confirm.remaining_targets();
+    if targets.is_empty() {
+        // Everything was spared; there is nothing to confirm.
+        return;
+    }
+    if !options.dry_run && !app.state.get_bool(FIRST_DELETE_ACKNOWLEDGED) {
+        confirm.first_delete_input = Some(String::new());
+        return;
+    }
+    let quick = confirm.is_quick();
+    start_clean(scan_roots, options, clean_cancel, tx, app, targets, quick);
+}
+
+fn handle_key_artifact_settings(
+    scan_roots: &ScanRoots,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+    key: KeyEvent,
+) -> Result<bool> {
+    let Screen::ArtifactSettings(settings) = &mut app.screen else {
+        return Ok(false);
+    };
+
+    if let Some(input) = &mut settings.input {
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let name = std::mem::take(input);
+                settings.input = None;
+                settings.add(&name);
+            }
+            KeyCode::Esc => settings.input = None,
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    match key.code {
+        KeyCode::Up => settings.move_cursor_by(-1),
+        KeyCode::Down => settings.move_cursor_by(1),
+        KeyCode::PageUp => settings.move_cursor_by(-10),
+        KeyCode::PageDown => settings.move_cursor_by(10),
+        KeyCode::Char(' ') => settings.toggle_current(),
+        KeyCode::Char('+') | KeyCode::Char('i') => settings.input = Some(String::new()),
+        KeyCode::Enter => {
+            let (enabled, disabled) = settings.split();
+            let artifacts = ArtifactMatcher {
+                patterns: app.scan_options.artifacts.patterns.clone(),
+                ..ArtifactMatcher::new(enabled)
+            };
+            if artifacts.is_empty() {
+                settings.note = Some("Enable at least one name to scan for.".to_string());
+                return Ok(false);
+            }
+            app.screen = Screen::Main;
+            app.disabled_artifact_names = disabled;
+            if artifacts.names != app.scan_options.artifacts.names
+                || artifacts.trailing != app.scan_options.artifacts.trailing
+            {
+                app.scan_options.artifacts = artifacts;
+                app.start_scan(scan_roots, tx);
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => app.screen = Screen::Main,
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_key_artifact_types(
+    options: &TuiOptions,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+    key: KeyEvent,
+) -> Result<bool> {
+    let Screen::ArtifactTypes(cursor) = app.screen else {
+        return Ok(false);
+    };
+    let types = app.artifact_types(options);
+    let max = types.len().saturating_sub(1);
+
+    match key.code {
+        KeyCode::Up => app.screen = Screen::ArtifactTypes(cursor.saturating_sub(1)),
+        KeyCode::Down => app.screen = Screen::ArtifactTypes((cursor + 1).min(max)),
+        KeyCode::Char(' ') | KeyCode::Enter => {
+            if let Some((total, _)) = types.get(cursor) {
+                app.toggle_artifact_type(options, &total.name);
+                app.recheck_selected(options, tx);
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t') => app.screen = Screen::Main,
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Clicking a row moves the cursor to it (clicking its Sel cell also toggles
+/// it); the wheel moves the cursor, or scrolls the confirm tree.
+fn handle_mouse(
+    options: &TuiOptions,
+    tx: &mpsc::Sender<AppEvent>,
+    app: &mut App,
+    mouse: MouseEvent,
+) {
+    if app.show_help {
+        return;
+    }
+    match &mut app.screen {
+        Screen::Main => match mouse.kind {
+            MouseEventKind::ScrollUp if app.focus == Focus::Detail => {
+                app.move_detail_cursor_by(options, -1)
+            }
+            MouseEventKind::ScrollDown if app.focus == Focus::Detail => {
+                app.move_detail_cursor_by(options, 1)
+            }
+            MouseEventKind::ScrollUp => app.move_cursor_by(options, -1),
+            MouseEventKind::ScrollDown => app.move_cursor_by(options, 1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(row) = app.table_row_at(options, mouse.column, mouse.row) else {
+                    return;
+                };
+                app.focus = Focus::Table;
+                app.table_state.select(Some(row));
+                let in_sel_column = app
+                    .table_area
+                    .is_some_and(|area| mouse.column < area.x + SEL_COLUMN_WIDTH);
+                if in_sel_column {
+                    app.toggle_current(options);
+                    app.recheck_selected(options, tx);
+                }
+            }
+            _ => {}
+        },
+        Screen::Confirm(confirm) => match mouse.kind {
+            MouseEventKind::ScrollUp => confirm.move_cursor_by(-1),
+            MouseEventKind::ScrollDown => confirm.move_cursor_by(1),
+            _ => {}
+        },
+        Screen::ArtifactTypes(cursor) => match mouse.kind {
+            MouseEventKind::ScrollUp => *cursor = cursor.saturating_sub(1),
+            MouseEventKind::ScrollDown => {
+                let next = *cursor + 1;
+                let max = app.artifact_types(options).len().saturating_sub(1);
+                app.screen = Screen::ArtifactTypes(next.min(max));
+            }
+            _ => {}
+        },
+        Screen::ArtifactSettings(settings) => match mouse.kind {
+            MouseEventKind::ScrollUp => settings.move_cursor_by(-1),
+            MouseEventKind::ScrollDown => settings.move_cursor_by(1),
+            _ => {}
+        },
+        Screen::Cleaning(_) | Screen::Result => {}
+    }
+}
+
 fn handle_key_cleaning(
     clean_cancel: &Arc<AtomicBool>,
     app: &mut App,
@@ -837,85 +2948,209 @@ fn handle_key_cleaning(
     Ok(false)
 }
 
-fn render(frame: &mut Frame, scan_root: &Path, options: &TuiOptions, app: &mut App) {
+fn render(frame: &mut Frame, scan_roots: &ScanRoots, options: &TuiOptions, app: &mut App) {
+    app.spinner_frame = app.spinner_frame.wrapping_add(1);
     match &app.screen {
-        Screen::Main => render_main(frame, scan_root, options, app),
-        Screen::Confirm(confirm) => render_confirm(frame, scan_root, options, confirm),
-        Screen::Cleaning(cleaning) => render_cleaning(frame, scan_root, options, cleaning),
-        Screen::Result => render_result(frame, scan_root, app),
+        Screen::Main => render_main(frame, scan_roots, options, app),
+        Screen::ArtifactTypes(cursor) => {
+            let cursor = *cursor;
+            render_main(frame, scan_roots, options, app);
+            render_artifact_types(
+                frame,
+                &app.artifact_types(options),
+                cursor,
+                options.display_unit,
+            );
+        }
+        Screen::ArtifactSettings(_) => {
+            render_main(frame, scan_roots, options, app);
+            if let Screen::ArtifactSettings(settings) = &app.screen {
+                render_artifact_settings(frame, settings);
+            }
+        }
+        Screen::Confirm(_) => {
+            if let Screen::Confirm(confirm) = &mut app.screen {
+                render_confirm(frame, scan_roots, options, confirm, &app.ignore_checks);
+            }
+        }
+        Screen::Cleaning(cleaning) => render_cleaning(frame, scan_roots, options, cleaning),
+        Screen::Result => render_result(frame, scan_roots, app),
+    }
+    if app.show_help {
+        render_help(frame);
     }
 }
 
-fn render_main(frame: &mut Frame, scan_root: &Path, options: &TuiOptions, app: &mut App) {
+const SEL_COLUMN_WIDTH: u16 = 3;
+const SIZE_COLUMN_WIDTH: u16 = 12;
+const AGE_COLUMN_WIDTH: u16 = 6;
+const STALE_COLUMN_WIDTH: u16 = 6;
+const COMMIT_COLUMN_WIDTH: u16 = 10;
+/// Columns before the Repo column, including one column of spacing after
+/// each.
+const REPO_COLUMN_OFFSET: u16 = SEL_COLUMN_WIDTH
+    + SIZE_COLUMN_WIDTH
+    + AGE_COLUMN_WIDTH
+    + STALE_COLUMN_WIDTH
+    + COMMIT_COLUMN_WIDTH
+    + 5;
+
+fn render_main(frame: &mut Frame, scan_roots: &ScanRoots, options: &TuiOptions, app: &mut App) {
     let area = frame.area();
+    let scan_ratio = scan_ratio(app);
+    let gauge_height = u16::from(scan_ratio.is_some());
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(4),
             Constraint::Min(0),
-            Constraint::Length(3),
+            Constraint::Length(3 + gauge_height),
         ])
         .split(area);
 
-    let (planned_dirs, reclaim_bytes, selected_repos) = summarize_selection(&app.items, options);
-    let visible_repos = app
+    let visibility = app.visibility(options);
+    let (planned_dirs, reclaim_bytes, selected_repos) =
+        summarize_selection(&app.items, &visibility);
+    let (visible_repos, visible_bytes) = app
         .items
         .iter()
-        .filter(|item| is_visible(&item.report, options))
-        .count();
+        .filter(|item| is_visible(&item.report, &visibility))
+        .fold((0usize, 0u64), |(repos, bytes), item| {
+            (
+                repos + 1,
+                bytes.saturating_add(item.report.total_size_bytes),
+            )
+        });
 
     let dry_run_label = if options.dry_run { " DRY RUN" } else { "" };
     let sort_label = match app.sort_mode {
         SortMode::Age => "age",
         SortMode::Size => "size",
+        SortMode::StaleRatio => "stale ratio",
+        SortMode::HeadAge => "commit age",
+    };
+    let new_repo_label = match app.new_repo_default_selected {
+        None => "auto",
+        Some(true) => "selected",
+        Some(false) => "deselected",
+    };
+
+    let age_label = match app.age_filter.min_days() {
+        Some(days) => format!(" age>={days}d"),
+        None => String::new(),
     };
 
     let header = Paragraph::new(Text::from(vec![
         Line::from(format!(
-            "clean-my-code  show>={}  auto-select>=180d{}  sort={sort_label}",
-            format_bytes(options.min_size_bytes),
-            dry_run_label
-        )),
-        Line::from(format!("root: {}", scan_root.display())),
-        Line::from(format!(
-            "shown: {} repos  selected: {} repos  planned: {} dirs  reclaim: {}",
-            visible_repos,
-            selected_repos,
-            planned_dirs,
-            format_bytes(reclaim_bytes)
+            "clean-my-code  show {}{age_label}  auto-select>={STALE_AFTER_DAYS}d{}  sort={sort_label}  new repos={new_repo_label}  paths={}",
+            size_range_label(options, app.min_size_bytes(options)),
+            dry_run_label,
+            app.path_display.as_str()
         )),
-        Line::from(""),
+        Line::from(format!("root: {}", scan_roots)),
+        Line::from(vec![
+            Span::raw(format!(
+                "shown: {} repos  selected: {} repos  planned: {} dirs  reclaim: {} of {} total visible",
+                visible_repos,
+                selected_repos,
+                planned_dirs,
+                format_bytes_with(reclaim_bytes, options.display_unit),
+                format_bytes_with(visible_bytes, options.display_unit)
+            )),
+            // Artifacts are still streaming in, so none of these are final.
+            Span::styled(
+                if app.scan_done { "" } else { "  (scanning…)" },
+                Style::default().fg(Color::Yellow),
+            ),
+        ]),
+        Line::from(
+            [
+                app.session
+                    .label(options.display_unit)
+                    .map(|label| Span::styled(label, Style::default().fg(Color::LightGreen))),
+                app.counters
+                    .outside_repo_summary(options.display_unit)
+                    .map(|summary| {
+                        Span::styled(
+                            format!("  skipped: {summary}"),
+                            Style::default().fg(Color::DarkGray),
+                        )
+                    }),
+                (!app.scan_options.skip_mounts.is_empty()).then(|| {
+                    Span::styled(
+                        format!(
+                            "  skipped network fs: {}",
+                            app.scan_options
+                                .skip_mounts
+                                .iter()
+                                .map(|mount| scan_roots.display_rel(mount))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    )
+                }),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>(),
+        ),
     ]));
     frame.render_widget(header, layout[0]);
 
-    let visible_items: Vec<Row<'static>> = app
+    let detail_height = app
+        .selected_item(options)
+        .map(|item| {
+            (item.report.artifacts.len() as u16)
+                .saturating_add(4)
+                .clamp(6, 12)
+        })
+        .unwrap_or(0);
+    let body = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(detail_height)])
+        .split(layout[1]);
+
+    let table_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(pane_border_style(app.focus == Focus::Table));
+    let table_inner = table_block.inner(body[0]);
+    // One row goes to the header.
+    app.page_rows = usize::from(table_inner.height.saturating_sub(1)).max(1);
+    let repo_width = usize::from(table_inner.width.saturating_sub(REPO_COLUMN_OFFSET));
+    let any_visible = app
         .items
         .iter()
-        .filter(|item| is_visible(&item.report, options))
-        .map(|item| render_repo_row(item, app.now))
-        .collect();
+        .any(|item| is_visible(&item.report, &visibility));
 
-    if visible_items.is_empty() {
-        let threshold = format_bytes(options.min_size_bytes);
+    if !any_visible {
         let message = if app.scan_done {
-            format!("No gitignored artifacts >= {threshold} found.")
+            format!(
+                "No gitignored artifacts {}{age_label} found.",
+                size_range_label(options, app.min_size_bytes(options))
+            )
         } else {
             "Scanning...".to_string()
         };
-        frame.render_widget(Paragraph::new(message), layout[1]);
+        frame.render_widget(Paragraph::new(message).block(table_block), body[0]);
         app.table_state.select(None);
+        app.table_area = None;
     } else {
         app.ensure_selection_valid(options);
 
-        let (size_label, age_label) = match app.sort_mode {
-            SortMode::Age => ("Size", "Age*"),
-            SortMode::Size => ("Size*", "Age"),
+        let (size_label, age_label, stale_label, commit_label) = match app.sort_mode {
+            SortMode::Age => ("Size", "Age*", "Stale", "Commit"),
+            SortMode::Size => ("Size*", "Age", "Stale", "Commit"),
+            SortMode::StaleRatio => ("Size", "Age", "Stale*", "Commit"),
+            SortMode::HeadAge => ("Size", "Age", "Stale", "Commit*"),
         };
 
         let header = Row::new(vec![
             Cell::from("Sel"),
             Cell::from(Text::from(size_label).alignment(Alignment::Right)),
             Cell::from(Text::from(age_label).alignment(Alignment::Right)),
+            Cell::from(Text::from(stale_label).alignment(Alignment::Right)),
+            Cell::from(commit_label),
             Cell::from("Repo"),
         ])
         .style(
@@ -925,13 +3160,54 @@ fn render_main(frame: &mut Frame, scan_root: &Path, options: &TuiOptions, app: &
         );
 
         let widths = [
-            Constraint::Length(3),
-            Constraint::Length(11),
-            Constraint::Length(6),
+            Constraint::Length(SEL_COLUMN_WIDTH),
+            Constraint::Length(SIZE_COLUMN_WIDTH),
+            Constraint::Length(AGE_COLUMN_WIDTH),
+            Constraint::Length(STALE_COLUMN_WIDTH),
+            Constraint::Length(COMMIT_COLUMN_WIDTH),
             Constraint::Min(10),
         ];
 
-        let table = Table::new(visible_items, widths)
+        let visible_items = app
+            .items
+            .iter()
+            .filter(|item| is_visible(&item.report, &visibility))
+            .collect::<Vec<_>>();
+        let table_rows = app.table_rows(options);
+        let rows = table_rows
+            .iter()
+            .map(|row| match row {
+                TableRow::Group(group) => {
+                    let label = app.path_display.display(scan_roots, &group.path);
+                    render_group_row(group, &label, repo_width, options.display_unit)
+                }
+                TableRow::Repo(index) => render_repo_row(
+                    visible_items[*index],
+                    app.now,
+                    options.age_basis,
+                    app.spinner_frame,
+                    repo_width,
+                    options.display_unit,
+                ),
+            })
+            .collect::<Vec<_>>();
+        let cursor = app.table_state.selected().and_then(|selected| {
+            table_rows
+                .iter()
+                .position(|row| *row == TableRow::Repo(selected))
+        });
+        app.display_state.select(cursor);
+        // Keep the header of the highlighted repo's group in view when
+        // scrolling up onto its first repo.
+        if let Some(cursor) = cursor
+            && cursor > 0
+            && matches!(table_rows[cursor - 1], TableRow::Group(_))
+            && app.display_state.offset() >= cursor
+        {
+            *app.display_state.offset_mut() = cursor - 1;
+        }
+
+        let table = Table::new(rows, widths)
             .header(header)
             .column_spacing(1)
             .highlight_spacing(HighlightSpacing::Never)
@@ -940,41 +3216,325 @@ fn render_main(frame: &mut Frame, scan_root: &Path, options: &TuiOptions, app: &
                     .bg(Color::DarkGray)
                     .add_modifier(Modifier::BOLD),
             );
-        frame.render_stateful_widget(table, layout[1], &mut app.table_state);
+        frame.render_widget(table_block, body[0]);
+        frame.render_stateful_widget(table, table_inner, &mut app.display_state);
+        app.table_area = Some(table_inner);
+
+        if let Some(item) = app.selected_item(options) {
+            render_detail(frame, scan_roots, options, app, item, body[1]);
+        }
     }
 
+    let footer_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(gauge_height)])
+        .split(layout[2]);
     let footer = Paragraph::new(Text::from(vec![
         help_line(),
-        Line::from(progress_line(app)),
+        progress_line(scan_roots, app, options.display_unit),
     ]))
     .wrap(Wrap { trim: true });
-    frame.render_widget(footer, layout[2]);
+    frame.render_widget(footer, footer_layout[0]);
+    if let Some(ratio) = scan_ratio {
+        let gauge = LineGauge::default()
+            .ratio(ratio)
+            .label(format!(
+                "{}/{} ",
+                app.scan_processed,
+                app.scan_total.unwrap_or_default()
+            ))
+            .filled_style(Style::default().fg(Color::Cyan))
+            .unfilled_style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(gauge, footer_layout[1]);
+    }
+}
+
+/// Share of scan candidates processed, for the footer gauge. `None` while
+/// discovery is still counting them, and once the scan is over.
+fn scan_ratio(app: &App) -> Option<f64> {
+    let total = app.scan_total.filter(|_| !app.scan_done)?;
+    if total == 0 {
+        return Some(1.0);
+    }
+    Some((app.scan_processed as f64 / total as f64).min(1.0))
 }
 
-fn render_repo_row(item: &RepoItem, now: SystemTime) -> Row<'static> {
-    let checkbox = if item.selected { "[x]" } else { "[ ]" };
+fn size_range_label(options: &TuiOptions, min_size_bytes: u64) -> String {
+    let sizes = match options.max_size_bytes {
+        Some(max) => format!(
+            "{}..={}",
+            format_bytes_with(min_size_bytes, options.display_unit),
+            format_bytes_with(max, options.display_unit)
+        ),
+        None => format!(
+            ">={}",
+            format_bytes_with(min_size_bytes, options.display_unit)
+        ),
+    };
+    match (options.min_files, options.max_files) {
+        (0, None) => sizes,
+        (min, Some(max)) => format!("{sizes} files={min}..={max}"),
+        (min, None) => format!("{sizes} files>={min}"),
+    }
+}
+
+fn render_repo_row(
+    item: &RepoItem,
+    now: SystemTime,
+    age_basis: AgeBasis,
+    spinner_frame: usize,
+    repo_width: usize,
+    unit: Option<ByteUnit>,
+) -> Row<'static> {
+    let checkbox = if item.is_partially_selected() {
+        "[~]"
+    } else if item.selected && item.selection_mode == SelectionMode::Auto {
+        "[a]"
+    } else if item.selected {
+        "[x]"
+    } else {
+        "[ ]"
+    };
     let bytes = item.report.total_size_bytes;
-    let size = format_bytes(bytes);
-    let age_days = repo_age_days(&item.report, now)
+    let size = format_bytes_with(bytes, unit);
+    let age_days = repo_age_days(&item.report, now, age_basis)
         .map(|d| format!("{d}d"))
         .unwrap_or_else(|| "-".to_string());
+    let stale = format_percent(stale_ratio(&item.report, now, age_basis));
+    if item.report.artifacts.is_empty() {
+        let commit = item
+            .report
+            .head
+            .as_ref()
+            .map(|head| head.iso8601.get(0..10).unwrap_or(&head.iso8601).to_string())
+            .unwrap_or_else(|| "-".to_string());
+        return Row::new(vec![
+            Cell::from(""),
+            Cell::from(Text::from("-").alignment(Alignment::Right)),
+            Cell::from(Text::from("-").alignment(Alignment::Right)),
+            Cell::from(Text::from("-").alignment(Alignment::Right)),
+            Cell::from(commit),
+            Cell::from(truncate_middle(&item.repo_display, repo_width)),
+        ])
+        .style(Style::default().fg(Color::DarkGray));
+    }
+
+    let commit = match (&item.report.head, item.head_loaded) {
+        (Some(head), _) => Cell::from(head.iso8601.get(0..10).unwrap_or(&head.iso8601).to_string()),
+        (None, true) if item.head_failed => {
+            Cell::from("git failed").style(Style::default().fg(Color::Yellow))
+        }
+        (None, true) => Cell::from("no commits").style(Style::default().fg(Color::DarkGray)),
+        (None, false) => {
+            Cell::from(spinner(spinner_frame)).style(Style::default().fg(Color::DarkGray))
+        }
+    };
 
     Row::new(vec![
         Cell::from(checkbox.to_string()),
         Cell::from(Text::from(size).alignment(Alignment::Right)).style(size_style(bytes)),
         Cell::from(Text::from(age_days).alignment(Alignment::Right)),
-        Cell::from(item.repo_display.clone()),
+        Cell::from(Text::from(stale).alignment(Alignment::Right)),
+        commit,
+        Cell::from(truncate_middle(&item.repo_display, repo_width)),
     ])
 }
 
-fn size_style(bytes: u64) -> Style {
-    const MIB: u64 = 1024 * 1024;
-    const GIB: u64 = 1024 * MIB;
-    const BRIGHT_BYTES: u64 = 100 * MIB;
-    const LOUD_BYTES: u64 = GIB;
-    const EXTRA_BOLD_BYTES: u64 = 10 * GIB;
-
-    if bytes >= EXTRA_BOLD_BYTES {
+fn render_group_row(
+    group: &GroupRow,
+    label: &str,
+    repo_width: usize,
+    unit: Option<ByteUnit>,
+) -> Row<'static> {
+    let repos = if group.repos == 1 {
+        "1 repo".to_string()
+    } else {
+        format!("{} repos", group.repos)
+    };
+    let suffix = format!("/  {repos}");
+    let label = truncate_middle(label, repo_width.saturating_sub(suffix.len()));
+    Row::new(vec![
+        Cell::from(""),
+        Cell::from(
+            Text::from(format_bytes_with(group.size_bytes, unit)).alignment(Alignment::Right),
+        ),
+        Cell::from(""),
+        Cell::from(""),
+        Cell::from(""),
+        Cell::from(format!("{label}{suffix}")),
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+fn spinner(frame: usize) -> &'static str {
+    const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    FRAMES[frame % FRAMES.len()]
+}
+
+fn render_detail(
+    frame: &mut Frame,
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
+    app: &App,
+    item: &RepoItem,
+    area: Rect,
+) {
+    let cursor = (app.focus == Focus::Detail).then(|| {
+        app.detail_cursor
+            .min(item.report.artifacts.len().saturating_sub(1))
+    });
+    let head = match (&item.report.head, item.head_loaded) {
+        (Some(head), _) => {
+            let short_hash = head.hash.get(0..8).unwrap_or(&head.hash);
+            format!("{} {}", head.iso8601, short_hash)
+        }
+        (None, true) if item.head_failed => "lookup failed (see warnings)".to_string(),
+        (None, true) => "no commits".to_string(),
+        (None, false) => format!("{} loading", spinner(app.spinner_frame)),
+    };
+
+    let mut lines = vec![
+        Line::from(format!("repo: {}", display_path(&item.report.repo_root))),
+        Line::from(format!("head: {head}")),
+        Line::from(format!(
+            "stale: {} of {}",
+            format_percent(stale_ratio(&item.report, app.now, options.age_basis)),
+            format_bytes_with(item.report.total_size_bytes, options.display_unit)
+        )),
+    ];
+    if let Some(mtime) = item.report.newest_mtime {
+        let days = app
+            .now
+            .duration_since(mtime)
+            .map(|d| d.as_secs() / 86_400)
+            .unwrap_or(0);
+        lines.push(Line::from(format!(
+            "touched: {} ({days}d ago)",
+            format_local_date(mtime)
+        )));
+    }
+    if let Some(atime) = item.report.newest_atime {
+        lines.push(Line::from(format!(
+            "accessed: {}",
+            format_local_date(atime)
+        )));
+    }
+    if let Some(oldest) = item.report.oldest_mtime {
+        lines.push(Line::from(format!(
+            "oldest file: {}",
+            format_local_date(oldest)
+        )));
+    }
+    if let Some(url) = &item.remote_url {
+        lines.push(Line::from(format!("remote: {url}")));
+    }
+    let header_len = lines.len();
+    for (index, artifact) in item.report.artifacts.iter().enumerate() {
+        let (marker, marker_style) = ignore_marker(app.ignore_checks.get(&artifact.path));
+        let keep = if item.selected && item.excluded.contains(&artifact.path) {
+            "keep "
+        } else {
+            "     "
+        };
+        let line = Line::from(vec![
+            Span::raw(format!(
+                "{:>11}  {keep}",
+                format_bytes_with(artifact.stats.size_bytes, options.display_unit)
+            )),
+            Span::styled(format!("{marker:<22}"), marker_style),
+            Span::raw(display_rel_path(&item.report.repo_root, &artifact.path)),
+            Span::styled(
+                if artifact.unverified {
+                    "  (unverified)"
+                } else {
+                    ""
+                },
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        if cursor == Some(index) {
+            lines.push(line.style(Style::default().bg(Color::DarkGray)));
+        } else {
+            lines.push(line);
+        }
+    }
+    if let Some(artifact) = item.report.artifacts.get(cursor.unwrap_or(0))
+        && !artifact.stats.largest_files.is_empty()
+    {
+        lines.push(Line::from(format!(
+            "largest files in {}:",
+            display_rel_path(&item.report.repo_root, &artifact.path)
+        )));
+        for (file, size) in &artifact.stats.largest_files {
+            lines.push(Line::styled(
+                format!(
+                    "{:>11}  {}",
+                    format_bytes_with(*size, options.display_unit),
+                    display_rel_path(&artifact.path, file)
+                ),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+    }
+
+    // Keep the highlighted artifact in view when the list is longer than
+    // the pane.
+    let inner_height = usize::from(area.height.saturating_sub(2));
+    let scroll = cursor.map_or(0, |cursor| {
+        (header_len + cursor + 1).saturating_sub(inner_height)
+    });
+
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .scroll((scroll as u16, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(pane_border_style(cursor.is_some()))
+                    .title(format!(
+                        "Details: {}",
+                        scan_roots.display_rel(&item.report.repo_root)
+                    )),
+            ),
+        area,
+    );
+}
+
+fn pane_border_style(focused: bool) -> Style {
+    if focused {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    }
+}
+
+fn ignore_marker(check: Option<&IgnoreCheck>) -> (&'static str, Style) {
+    match check {
+        None => ("ignored (scan)", Style::default().fg(Color::Green)),
+        Some(IgnoreCheck::Pending) => ("re-checking...", Style::default().fg(Color::DarkGray)),
+        Some(IgnoreCheck::Ignored) => ("ignored (re-checked)", Style::default().fg(Color::Green)),
+        Some(IgnoreCheck::NotIgnored) => (
+            "NOT IGNORED",
+            Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Some(IgnoreCheck::Failed(_)) => ("check failed", Style::default().fg(Color::Yellow)),
+    }
+}
+
+fn size_style(bytes: u64) -> Style {
+    const MIB: u64 = 1024 * 1024;
+    const GIB: u64 = 1024 * MIB;
+    const BRIGHT_BYTES: u64 = 100 * MIB;
+    const LOUD_BYTES: u64 = GIB;
+    const EXTRA_BOLD_BYTES: u64 = 10 * GIB;
+
+    if bytes >= EXTRA_BOLD_BYTES {
         Style::default()
             .fg(Color::LightRed)
             .add_modifier(Modifier::BOLD)
@@ -989,27 +3549,234 @@ fn size_style(bytes: u64) -> Style {
 
 fn render_confirm(
     frame: &mut Frame,
-    scan_root: &Path,
+    scan_roots: &ScanRoots,
     options: &TuiOptions,
-    confirm: &ConfirmData,
+    confirm: &mut ConfirmData,
+    ignore_checks: &HashMap<PathBuf, IgnoreCheck>,
 ) {
     let area = frame.area();
-    let message = confirm_message(scan_root, options, confirm);
-    let popup = centered_rect(80, 40, area);
+    let (popup, title) = if confirm.quick {
+        (centered_rect(70, 50, area), "Quick clean")
+    } else {
+        (centered_rect(80, 70, area), "Confirm")
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(popup);
+
+    let header = confirm_header(scan_roots, options, confirm);
+    let tree = confirm_tree(scan_roots, confirm, options.display_unit);
+    let footer = confirm_footer(scan_roots, confirm, ignore_checks);
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(header.len() as u16),
+            Constraint::Min(1),
+            Constraint::Length(footer.len() as u16),
+        ])
+        .split(inner);
+
+    // Scroll just enough to keep the highlighted dir in view.
+    let height = usize::from(layout[1].height).max(1);
+    confirm.page_lines = height;
+    let cursor_line = confirm.cursor_line();
+    if cursor_line < confirm.scroll {
+        confirm.scroll = cursor_line.saturating_sub(1);
+    } else if cursor_line >= confirm.scroll + height {
+        confirm.scroll = cursor_line + 1 - height;
+    }
 
     frame.render_widget(Clear, popup);
+    frame.render_widget(block, popup);
+    frame.render_widget(Paragraph::new(header).wrap(Wrap { trim: true }), layout[0]);
     frame.render_widget(
-        Paragraph::new(message)
-            .block(Block::default().borders(Borders::ALL).title("Confirm"))
-            .alignment(Alignment::Left)
+        Paragraph::new(tree).scroll((confirm.scroll.min(u16::MAX as usize) as u16, 0)),
+        layout[1],
+    );
+    frame.render_widget(Paragraph::new(footer).wrap(Wrap { trim: true }), layout[2]);
+
+    if let Some(input) = &confirm.first_delete_input {
+        render_first_delete_prompt(frame, input);
+    }
+}
+
+fn render_first_delete_prompt(frame: &mut Frame, input: &str) {
+    let popup = centered_rect(60, 40, frame.area());
+    let warn_style = Style::default()
+        .fg(Color::LightRed)
+        .add_modifier(Modifier::BOLD);
+    let text = Text::from(vec![
+        Line::styled("This is your first real deletion.", warn_style),
+        Line::from(""),
+        Line::from(
+            "Directories are removed permanently: they do not go to the trash and cannot be restored. Build tools can regenerate them, but that can take a long time.",
+        ),
+        Line::from(""),
+        Line::from(
+            "This prompt is only shown once. Type 'yes' and press Enter to continue, or Esc to go back.",
+        ),
+        Line::from(""),
+        Line::from(format!("> {input}_")),
+    ]);
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Before you delete"),
+            )
             .wrap(Wrap { trim: true }),
         popup,
     );
 }
 
+fn render_artifact_types(
+    frame: &mut Frame,
+    types: &[(ArtifactNameTotal, usize)],
+    cursor: usize,
+    unit: Option<ByteUnit>,
+) {
+    let popup = centered_rect(60, 70, frame.area());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Select by artifact type (visible repos)");
+    let inner = block.inner(popup);
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let rows = types
+        .iter()
+        .map(|(total, planned)| {
+            let mark = if *planned == 0 {
+                "[ ]"
+            } else if *planned == total.dirs {
+                "[x]"
+            } else {
+                "[~]"
+            };
+            Row::new(vec![
+                Cell::from(mark),
+                Cell::from(
+                    Text::from(format_bytes_with(total.size_bytes, unit))
+                        .alignment(Alignment::Right),
+                ),
+                Cell::from(Text::from(format!("{} dirs", total.dirs)).alignment(Alignment::Right)),
+                Cell::from(display_path(Path::new(&total.name))),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Length(11),
+            Constraint::Length(10),
+            Constraint::Min(10),
+        ],
+    )
+    .column_spacing(1)
+    .highlight_spacing(HighlightSpacing::Never)
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut state = TableState::default().with_selected(Some(cursor));
+
+    let key_style = Style::default().fg(Color::LightBlue);
+    let footer = Line::from(vec![
+        Span::styled("Space", key_style),
+        Span::raw(" select/keep in every repo  "),
+        Span::styled("Esc", key_style),
+        Span::raw(" close"),
+    ]);
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(block, popup);
+    frame.render_stateful_widget(table, layout[0], &mut state);
+    frame.render_widget(Paragraph::new(footer), layout[1]);
+}
+
+fn render_artifact_settings(frame: &mut Frame, settings: &ArtifactSettings) {
+    let popup = centered_rect(50, 70, frame.area());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Artifact dir names");
+    let inner = block.inner(popup);
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(3)])
+        .split(inner);
+
+    let rows = settings
+        .entries
+        .iter()
+        .map(|(name, enabled)| {
+            let mark = if *enabled { "[x]" } else { "[ ]" };
+            Row::new(vec![
+                Cell::from(mark),
+                Cell::from(display_path(Path::new(name))),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let table = Table::new(rows, [Constraint::Length(3), Constraint::Min(10)])
+        .column_spacing(1)
+        .highlight_spacing(HighlightSpacing::Never)
+        .row_highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = TableState::default().with_selected(Some(settings.cursor));
+
+    let key_style = Style::default().fg(Color::LightBlue);
+    let footer = match &settings.input {
+        Some(input) => vec![
+            Line::from(vec![
+                Span::raw("add: "),
+                Span::raw(input.clone()),
+                Span::raw("_"),
+            ]),
+            Line::from(vec![
+                Span::styled("Enter", key_style),
+                Span::raw(" add  "),
+                Span::styled("Esc", key_style),
+                Span::raw(" cancel"),
+            ]),
+        ],
+        None => vec![
+            Line::from(vec![
+                Span::styled("Space", key_style),
+                Span::raw(" enable/disable  "),
+                Span::styled("+", key_style),
+                Span::raw(" add name"),
+            ]),
+            Line::from(vec![
+                Span::styled("Enter", key_style),
+                Span::raw(" apply and rescan  "),
+                Span::styled("Esc", key_style),
+                Span::raw(" discard"),
+            ]),
+            settings
+                .note
+                .clone()
+                .map(|note| Line::styled(note, Style::default().fg(Color::Yellow)))
+                .unwrap_or_default(),
+        ],
+    };
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(block, popup);
+    frame.render_stateful_widget(table, layout[0], &mut state);
+    frame.render_widget(Paragraph::new(footer), layout[1]);
+}
+
 fn render_cleaning(
     frame: &mut Frame,
-    scan_root: &Path,
+    scan_roots: &ScanRoots,
     options: &TuiOptions,
     cleaning: &CleaningData,
 ) {
@@ -1037,11 +3804,11 @@ fn render_cleaning(
         .to_string();
 
     let text = Text::from(vec![
-        Line::from(format!("root: {}", scan_root.display())),
+        Line::from(format!("root: {}", scan_roots)),
         Line::from(format!(
             "plan: {} dirs, reclaim {}{}",
             cleaning.total,
-            format_bytes(cleaning.planned_bytes),
+            format_bytes_with(cleaning.planned_bytes, options.display_unit),
             dry_run_label
         )),
         Line::from(format!(
@@ -1049,7 +3816,7 @@ fn render_cleaning(
             cleaning.processed,
             cleaning.total,
             cleaning.deleted_paths,
-            format_bytes(cleaning.deleted_bytes),
+            format_bytes_with(cleaning.deleted_bytes, options.display_unit),
             cleaning.skipped_paths,
             cleaning.error_count,
             elapsed,
@@ -1071,86 +3838,164 @@ fn render_cleaning(
     );
 }
 
-fn render_result(frame: &mut Frame, scan_root: &Path, app: &App) {
+fn render_result(frame: &mut Frame, scan_roots: &ScanRoots, app: &App) {
     let area = frame.area();
     let popup = centered_rect(80, 60, area);
     frame.render_widget(Clear, popup);
 
-    let text = app
+    let mut text = app
         .result_lines
         .iter()
         .map(|line| Line::from(line.as_str()))
         .collect::<Vec<_>>();
+    if let Some(note) = &app.result_note {
+        text.push(Line::from(""));
+        text.push(Line::styled(
+            note.clone(),
+            Style::default().fg(Color::LightYellow),
+        ));
+    }
 
     frame.render_widget(
         Paragraph::new(Text::from(text))
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(format!("Result ({})", scan_root.display())),
+                    .title(format!("Result ({})", scan_roots)),
             )
             .wrap(Wrap { trim: true }),
         popup,
     );
 }
 
-fn confirm_message(scan_root: &Path, options: &TuiOptions, confirm: &ConfirmData) -> Text<'static> {
+fn confirm_header(
+    scan_roots: &ScanRoots,
+    options: &TuiOptions,
+    confirm: &ConfirmData,
+) -> Vec<Line<'static>> {
     let dry_run_label = if options.dry_run { " (dry run)" } else { "" };
-    let lines = vec![
-        Line::from(format!("root: {}", scan_root.display())),
+    if confirm.quick {
+        return vec![
+            Line::from(format!(
+                "plan: delete {} artifact dirs from this repo only, reclaim {}{}",
+                confirm.planned_dirs,
+                format_bytes_with(confirm.planned_bytes, options.display_unit),
+                dry_run_label
+            )),
+            Line::from(""),
+        ];
+    }
+    vec![
+        Line::from(format!("root: {}", scan_roots)),
         Line::from(format!(
             "plan: delete {} artifact dirs from {} repos, reclaim {}{}",
             confirm.planned_dirs,
             confirm.selected_repos,
-            format_bytes(confirm.planned_bytes),
+            format_bytes_with(confirm.planned_bytes, options.display_unit),
             dry_run_label
         )),
         Line::from(""),
-        Line::from("Press 'y' to confirm, 'n' to cancel."),
-    ];
+    ]
+}
 
-    Text::from(lines)
+/// Per-repo breakdown: a repo line with its subtotal followed by its dirs,
+/// using the same grouping as the `clean` subcommand's plan.
+fn confirm_tree(
+    scan_roots: &ScanRoots,
+    confirm: &ConfirmData,
+    unit: Option<ByteUnit>,
+) -> Vec<Line<'static>> {
+    let repo_style = Style::default().add_modifier(Modifier::BOLD);
+    let spared_style = Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::CROSSED_OUT);
+    let mut lines = Vec::new();
+    let mut index = 0;
+    for group in group_targets_by_repo(&confirm.targets) {
+        let mut spans = vec![Span::styled(
+            group.header_line(scan_roots, unit),
+            repo_style,
+        )];
+        if let Some(url) = confirm.remote_urls.get(group.repo_root) {
+            spans.push(Span::styled(
+                format!("  ({url})"),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        lines.push(Line::from(spans));
+        for (target, text) in group.targets.iter().zip(group.target_lines(unit)) {
+            let mut line = Line::from(text);
+            if confirm.spared.contains(&target.path) {
+                line = line.style(spared_style);
+            }
+            if index == confirm.cursor {
+                line = line.patch_style(Style::default().bg(Color::DarkGray));
+            }
+            lines.push(line);
+            index += 1;
+        }
+    }
+    lines
 }
 
-fn format_delete_summary(
-    scan_root: &Path,
-    summary: &DeleteSummary,
-    dry_run: bool,
-    canceled: bool,
-) -> Vec<String> {
-    let dry_run_label = if dry_run { " (dry run)" } else { "" };
+fn confirm_footer(
+    scan_roots: &ScanRoots,
+    confirm: &ConfirmData,
+    ignore_checks: &HashMap<PathBuf, IgnoreCheck>,
+) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from("")];
 
-    let mut lines = Vec::new();
-    lines.push(format!("root: {}", scan_root.display()));
-    if canceled {
-        lines.push("status: canceled".to_string());
-    }
-    lines.push(format!(
-        "planned: {} dirs, reclaim {}{}",
-        summary.planned_paths,
-        format_bytes(summary.planned_bytes),
-        dry_run_label
-    ));
-    lines.push(format!(
-        "deleted: {} dirs, reclaimed {}",
-        summary.deleted_paths,
-        format_bytes(summary.deleted_bytes)
-    ));
-    lines.push(format!("skipped: {} dirs", summary.skipped_paths));
+    let pending = confirm
+        .targets
+        .iter()
+        .filter(|t| ignore_checks.get(&t.path) == Some(&IgnoreCheck::Pending))
+        .count();
+    let not_ignored = confirm
+        .targets
+        .iter()
+        .filter(|t| ignore_checks.get(&t.path) == Some(&IgnoreCheck::NotIgnored))
+        .collect::<Vec<_>>();
 
-    if !summary.errors.is_empty() {
-        lines.push(String::new());
-        lines.push(format!("errors ({}):", summary.errors.len()));
-        for (path, err) in &summary.errors {
-            lines.push(format!("- {}: {err}", display_rel_path(scan_root, path)));
+    if pending > 0 {
+        lines.push(Line::from(format!(
+            "re-checking gitignore status of {pending} dirs..."
+        )));
+    }
+    if !not_ignored.is_empty() {
+        let warn_style = Style::default()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::BOLD);
+        lines.push(Line::from(Span::styled(
+            format!(
+                "warning: {} planned dirs are no longer gitignored and will be skipped:",
+                not_ignored.len()
+            ),
+            warn_style,
+        )));
+        for target in not_ignored {
+            lines.push(Line::from(Span::styled(
+                format!("- {}", scan_roots.display_rel(&target.path)),
+                warn_style,
+            )));
         }
     }
 
-    lines.push(String::new());
-    lines.push("Press any key to exit.".to_string());
+    if confirm.planned_dirs == 0 {
+        lines.push(Line::from(Span::styled(
+            "every dir is spared; restore one with Space or press 'n' to cancel",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines.push(Line::from(
+        "Press 'y' to confirm, 'n' to cancel, ↑/↓ PgUp/PgDn to move, Space to spare/restore a dir.",
+    ));
+
     lines
 }
 
+const RESULT_FOOTER: &str =
+    "Press 'w' to save this report, any other key to return to the list, 'q' to quit.";
+
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
     let vertical = Layout::default()
         .direction(Direction::Vertical)
@@ -1173,11 +4018,25 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
     horizontal[1]
 }
 
-fn repo_age_days(report: &RepoReport, now: SystemTime) -> Option<u64> {
-    let newest = report.newest_mtime?;
-    now.duration_since(newest)
-        .ok()
-        .map(|d| d.as_secs() / (24 * 60 * 60))
+fn recompute_report_totals(report: &mut RepoReport, order: ArtifactOrder) {
+    sort_artifacts(&mut report.artifacts, order);
+    report.total_size_bytes = report.artifacts.iter().map(|a| a.stats.size_bytes).sum();
+    report.total_file_count = report.artifacts.iter().map(|a| a.stats.file_count).sum();
+    report.newest_mtime = report
+        .artifacts
+        .iter()
+        .filter_map(|a| a.stats.newest_mtime)
+        .max();
+    report.oldest_mtime = report
+        .artifacts
+        .iter()
+        .filter_map(|a| a.stats.oldest_mtime)
+        .min();
+    report.newest_atime = report
+        .artifacts
+        .iter()
+        .filter_map(|a| a.stats.newest_atime)
+        .max();
 }
 
 fn cmp_time_key(a: Option<SystemTime>, b: Option<SystemTime>) -> CmpOrdering {
@@ -1189,46 +4048,89 @@ fn cmp_time_key(a: Option<SystemTime>, b: Option<SystemTime>) -> CmpOrdering {
     }
 }
 
-fn is_visible(report: &RepoReport, options: &TuiOptions) -> bool {
-    report.total_size_bytes >= options.min_size_bytes && !report.artifacts.is_empty()
+/// The filters deciding which repos are listed, combining CLI options with
+/// filters adjustable from inside the TUI.
+#[derive(Debug, Clone, Copy)]
+struct Visibility {
+    min_size_bytes: u64,
+    max_size_bytes: Option<u64>,
+    min_files: u64,
+    max_files: Option<u64>,
+    min_age_days: Option<u64>,
+    now: SystemTime,
+    age_basis: AgeBasis,
+    /// Whether repos without artifacts are listed (greyed out).
+    include_empty: bool,
 }
 
-fn should_auto_select(report: &RepoReport, options: &TuiOptions, now: SystemTime) -> bool {
-    const AUTO_SELECT_DAYS: u64 = 180;
-
-    if report.total_size_bytes < options.min_size_bytes || report.artifacts.is_empty() {
+fn is_visible(report: &RepoReport, visibility: &Visibility) -> bool {
+    if report.artifacts.is_empty() {
+        return visibility.include_empty;
+    }
+    if !is_within_size_range(report, visibility.min_size_bytes, visibility.max_size_bytes)
+        || !is_within_file_range(report, visibility.min_files, visibility.max_files)
+    {
         return false;
     }
 
-    let Some(age_days) = repo_age_days(report, now) else {
-        return false;
-    };
+    match visibility.min_age_days {
+        Some(min_age_days) => repo_age_days(report, visibility.now, visibility.age_basis)
+            .is_some_and(|age| age >= min_age_days),
+        None => true,
+    }
+}
 
-    age_days >= AUTO_SELECT_DAYS
+/// Selection for a repo seen for the first time, following the new-repo
+/// default when one is set and the auto policy otherwise.
+fn initial_selection(
+    new_repo_default: Option<bool>,
+    report: &RepoReport,
+    options: &TuiOptions,
+    now: SystemTime,
+) -> (bool, SelectionMode) {
+    match new_repo_default {
+        Some(selected) => (selected, SelectionMode::Manual),
+        None => (
+            should_auto_select(report, options, now),
+            SelectionMode::Auto,
+        ),
+    }
 }
 
-fn summarize_selection(items: &[RepoItem], options: &TuiOptions) -> (usize, u64, usize) {
+fn should_auto_select(report: &RepoReport, options: &TuiOptions, now: SystemTime) -> bool {
+    !report.artifacts.is_empty()
+        && is_within_size_range(report, options.min_size_bytes, options.max_size_bytes)
+        && is_within_file_range(report, options.min_files, options.max_files)
+        && is_stale(report, now, options.age_basis)
+}
+
+fn summarize_selection(items: &[RepoItem], visibility: &Visibility) -> (usize, u64, usize) {
     let mut planned_dirs = 0usize;
     let mut reclaim_bytes = 0u64;
     let mut selected_repos = 0usize;
 
     for item in items {
-        if !is_visible(&item.report, options) {
+        if !is_visible(&item.report, visibility) {
             continue;
         }
 
-        if !item.selected {
+        let mut planned = item.planned_artifacts().peekable();
+        if planned.peek().is_none() {
             continue;
         }
         selected_repos += 1;
-        planned_dirs += item.report.artifacts.len();
-        reclaim_bytes = reclaim_bytes.saturating_add(item.report.total_size_bytes);
+        for artifact in planned {
+            planned_dirs += 1;
+            reclaim_bytes = reclaim_bytes.saturating_add(artifact.stats.size_bytes);
+        }
     }
 
     (planned_dirs, reclaim_bytes, selected_repos)
 }
 
-fn progress_line(app: &App) -> String {
+const SIZING_WIDTH: usize = 40;
+
+fn progress_line(scan_roots: &ScanRoots, app: &App, unit: Option<ByteUnit>) -> Line<'static> {
     let elapsed = app
         .scan_elapsed_final
         .unwrap_or_else(|| app.scan_started_at.elapsed());
@@ -1239,28 +4141,88 @@ fn progress_line(app: &App) -> String {
         format!("{:.1}s", elapsed.as_secs_f64())
     };
 
-    let done = if app.scan_done { " done" } else { "" };
+    let done = match (app.scan_done, app.counters.timed_out) {
+        (true, true) => " partial (timed out; f: scan everything)",
+        (false, true) => " timed out, finishing",
+        (true, false) => " done",
+        (false, false) => "",
+    };
 
-    match app.scan_total {
+    let progress = match app.scan_total {
         Some(total) => format!(
-            "scan: {}/{} candidates  repos: {}  artifacts: {}  elapsed: {}{}",
+            "scan: {}/{} candidates  repos: {}/{}  artifacts: {}  elapsed: {}{}",
             app.scan_processed,
             total,
             app.items.len(),
+            app.counters.repos_scanned,
             app.artifacts_found,
             elapsed,
             done
         ),
         None => format!(
-            "scan: discovering candidates  repos: {}  artifacts: {}  elapsed: {}{}",
+            "scan: processed {} (discovery ongoing)  repos: {}/{}  artifacts: {}  elapsed: {}{}",
+            app.scan_processed,
             app.items.len(),
+            app.counters.repos_scanned,
             app.artifacts_found,
             elapsed,
             done
         ),
+    };
+
+    let mut spans = vec![Span::raw(progress)];
+    let empty_repos = app.empty_repo_count();
+    if empty_repos > 0 {
+        spans.push(Span::styled(
+            format!("  without ignored artifacts: {empty_repos}"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if let Some(sizing) = &app.sizing {
+        let so_far = app
+            .sizing_bytes
+            .map(|bytes| format!(" {} so far", format_bytes_with(bytes, unit)))
+            .unwrap_or_default();
+        spans.push(Span::styled(
+            format!(
+                "  sizing: {}{so_far}",
+                truncate_middle(sizing, SIZING_WIDTH)
+            ),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if app.counters.other_device_dirs > 0 {
+        spans.push(Span::styled(
+            format!("  other filesystems: {}", app.counters.other_device_dirs),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
+    if app.counters.warnings > 0 {
+        spans.push(Span::styled(
+            format!("  warnings: {}", app.counters.warnings),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let Some(failure) = app.scan_failures.last() {
+        spans.push(Span::styled(
+            format!(
+                "  last failure: {} ({})",
+                truncate_middle(&scan_roots.display_rel(&failure.path), SIZING_WIDTH),
+                failure.kind.as_str()
+            ),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let Some(error) = &app.scan_error {
+        spans.push(Span::styled(
+            format!("  scan failed: {error}"),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    Line::from(spans)
 }
 
+/// The most used keys; the `?` overlay lists the rest.
 fn help_line() -> Line<'static> {
     let key_style = Style::default().fg(Color::LightBlue);
     Line::from(vec![
@@ -1268,22 +4230,152 @@ fn help_line() -> Line<'static> {
         Span::raw(" move  "),
         Span::styled("Space", key_style),
         Span::raw(" toggle  "),
-        Span::styled("a", key_style),
-        Span::raw(" all  "),
-        Span::styled("n", key_style),
-        Span::raw(" none  "),
+        Span::styled("←/→", key_style),
+        Span::raw(" focus  "),
         Span::styled("Tab", key_style),
         Span::raw(" sort  "),
         Span::styled("⏎", key_style),
         Span::raw(" clean  "),
+        Span::styled("?", key_style),
+        Span::raw(" help  "),
         Span::styled("q", key_style),
         Span::raw(" quit"),
     ])
 }
 
+/// Every keybinding, grouped by the screen it works on. The canonical
+/// reference: add new keys here, and to `help_line` only if they are
+/// among the most used.
+const HELP_SECTIONS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Repo list",
+        &[
+            ("↑/↓ PgUp/PgDn", "move the cursor"),
+            ("Space", "toggle the highlighted repo"),
+            ("→ / ←", "move focus into / out of the detail pane"),
+            ("a / n / i", "select all / none / invert (visible repos)"),
+            (
+                "u / U",
+                "hand the highlighted / every visible repo back to the auto policy",
+            ),
+            ("d", "cycle how newly discovered repos are selected"),
+            ("Tab", "cycle the sort order"),
+            ("g", "cycle the age filter"),
+            (
+                "+ / -",
+                "raise / lower the size threshold (0, 1MiB, 10MiB, ... 100GiB)",
+            ),
+            ("p", "cycle how repo paths are shown"),
+            ("t", "select by artifact type"),
+            ("A", "edit the artifact dir names"),
+            (
+                "f",
+                "after a timed-out scan, scan the rest without the time limit",
+            ),
+            ("Enter", "confirm and clean the selection"),
+            ("D", "quick-clean only the highlighted repo"),
+            ("q / Esc", "quit"),
+        ],
+    ),
+    (
+        "Detail pane",
+        &[
+            ("↑/↓ PgUp/PgDn", "move through the repo's artifacts"),
+            ("Space", "plan or keep the highlighted artifact"),
+            ("← / Esc", "back to the repo list"),
+        ],
+    ),
+    (
+        "Select by type",
+        &[
+            ("↑/↓", "move"),
+            (
+                "Space / Enter",
+                "plan or keep the name in every visible repo",
+            ),
+            ("t / q / Esc", "close"),
+        ],
+    ),
+    (
+        "Artifact names",
+        &[
+            ("↑/↓ PgUp/PgDn", "move"),
+            ("Space", "enable or disable the name"),
+            ("+ / i", "add a name"),
+            ("Enter", "apply and rescan"),
+            ("Esc", "close without applying"),
+        ],
+    ),
+    (
+        "Confirm",
+        &[
+            ("↑/↓", "move over the planned dirs"),
+            ("Space", "spare or restore the highlighted dir"),
+            ("y", "delete"),
+            ("n / q / Esc", "cancel"),
+        ],
+    ),
+    (
+        "Cleaning",
+        &[("q / Esc / Ctrl-C", "stop after the current dir")],
+    ),
+    (
+        "Result",
+        &[
+            ("w", "save the result and full error list to a file"),
+            ("q", "quit"),
+            ("any other key", "back to the repo list"),
+        ],
+    ),
+    ("Anywhere", &[("?", "open or close this help")]),
+];
+
+fn help_lines() -> Vec<Line<'static>> {
+    let key_width = HELP_SECTIONS
+        .iter()
+        .flat_map(|(_, keys)| keys.iter())
+        .map(|(key, _)| key.width())
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::new();
+    for (title, keys) in HELP_SECTIONS {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::styled(
+            *title,
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        for (key, action) in *keys {
+            let pad = key_width - key.width();
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(*key, Style::default().fg(Color::LightBlue)),
+                Span::raw(format!("{}  {action}", " ".repeat(pad))),
+            ]));
+        }
+    }
+    lines
+}
+
+fn render_help(frame: &mut Frame) {
+    let popup = centered_rect(80, 90, frame.area());
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(Text::from(help_lines()))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Keys (? or Esc to close)"),
+            )
+            .wrap(Wrap { trim: false }),
+        popup,
+    );
+}
+
 fn spawn_clean_worker(
     targets: Vec<DeleteTarget>,
-    dry_run: bool,
+    options: DeleteOptions,
     cancel: Arc<AtomicBool>,
     tx: mpsc::Sender<AppEvent>,
 ) {
@@ -1293,16 +4385,18 @@ fn spawn_clean_worker(
 
         let summary = execute_delete_with_progress(
             &targets,
-            dry_run,
+            options,
             || cancel.load(Ordering::Relaxed),
             |progress| {
                 last_processed = progress.processed;
-                let idx = progress.processed.saturating_sub(1);
-                let current = targets.get(idx).cloned().unwrap_or_else(|| DeleteTarget {
-                    repo_root: PathBuf::new(),
-                    path: PathBuf::new(),
-                    planned_bytes: 0,
-                });
+                let current = targets
+                    .get(progress.target_index)
+                    .cloned()
+                    .unwrap_or_else(|| DeleteTarget {
+                        repo_root: PathBuf::new(),
+                        path: PathBuf::new(),
+                        planned_bytes: 0,
+                    });
 
                 let _ = tx.send(AppEvent::Clean(CleanEvent::Progress { progress, current }));
             },
@@ -1313,6 +4407,92 @@ fn spawn_clean_worker(
     });
 }
 
+fn spawn_refresh_worker(
+    paths: Vec<PathBuf>,
+    scan_options: ScanOptions,
+    tx: mpsc::Sender<AppEvent>,
+) {
+    thread::spawn(move || {
+        paths.par_iter().for_each(|path| {
+            if let Some(event) = refresh_event(path, &scan_options) {
+                let _ = tx.send(AppEvent::Scan(event));
+            }
+        });
+    });
+}
+
+/// What a refresh of a listed artifact found. Its row only goes when the dir
+/// is gone or definitely no longer an ignored artifact; a failed git call
+/// says nothing about the dir itself, so the row stays and the error is
+/// reported instead.
+fn refresh_event(path: &Path, scan_options: &ScanOptions) -> Option<ScanEvent> {
+    if !path.exists() {
+        return Some(ScanEvent::ArtifactGone {
+            path: path.to_path_buf(),
+        });
+    }
+    match process_candidate(path, scan_options, &WalkErrors::default()) {
+        CandidateOutcome::Artifact(record) => Some(ScanEvent::ArtifactRefreshed { record }),
+        CandidateOutcome::NotIgnored { .. } | CandidateOutcome::OutsideRepo(_) => {
+            Some(ScanEvent::ArtifactGone {
+                path: path.to_path_buf(),
+            })
+        }
+        CandidateOutcome::Failed(error) => Some(ScanEvent::Warning { error }),
+        CandidateOutcome::TimedOut | CandidateOutcome::Skipped => None,
+    }
+}
+
+fn spawn_ignore_recheck_worker(
+    scan_id: u64,
+    pending: Vec<(PathBuf, PathBuf)>,
+    git: GitOptions,
+    tx: mpsc::Sender<AppEvent>,
+) {
+    thread::spawn(move || {
+        for (repo_root, path) in pending {
+            let check = match is_git_ignored(&repo_root, &path, &git) {
+                Ok(true) => IgnoreCheck::Ignored,
+                Ok(false) => IgnoreCheck::NotIgnored,
+                Err(err) => IgnoreCheck::Failed(format!("{err:#}")),
+            };
+            let event = AppEvent::IgnoreRechecked {
+                scan_id,
+                path,
+                check,
+            };
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// Set while raw mode and the alternate screen are active, so the panic hook
+/// and `TerminalGuard::drop` restore the terminal exactly once.
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+fn restore_terminal<W: Write>(out: &mut W) {
+    if !TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let _ = disable_raw_mode();
+    let _ = execute!(out, DisableMouseCapture, Show, LeaveAlternateScreen);
+}
+
+/// Restores the terminal before the previous hook reports the panic, so the
+/// message lands on the normal screen and `RUST_BACKTRACE` still applies.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal(&mut std::io::stdout());
+            previous(info);
+        }));
+    });
+}
+
 struct TerminalGuard {
     terminal: ratatui::Terminal<CrosstermBackend<std::io::Stdout>>,
 }
@@ -1320,15 +4500,21 @@ struct TerminalGuard {
 impl TerminalGuard {
     fn enter() -> Result<Self> {
         enable_raw_mode().context("enable_raw_mode failed")?;
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
 
         let mut stdout = std::io::stdout();
-        execute!(stdout, EnterAlternateScreen, Hide).context("enter alternate screen failed")?;
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture, Hide)
+            .context("enter alternate screen failed")?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = ratatui::Terminal::new(backend).context("failed to create terminal")?;
 
         Ok(Self { terminal })
     }
 
+    fn clear(&mut self) -> Result<()> {
+        self.terminal.clear().context("terminal clear failed")
+    }
+
     fn draw<F>(&mut self, f: F) -> Result<()>
     where
         F: FnOnce(&mut Frame),
@@ -1340,8 +4526,1438 @@ impl TerminalGuard {
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
-        let _ = disable_raw_mode();
-        let mut stdout = std::io::stdout();
-        let _ = execute!(stdout, Show, LeaveAlternateScreen);
+        restore_terminal(&mut std::io::stdout());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clean::SkipReason, scan::DirStats, test_util::make_temp_dir};
+
+    const MIB: u64 = 1024 * 1024;
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn select_all_only_affects_visible_rows() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/big", 10 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/tiny", 1024, now);
+
+        app.select_all(&options, true);
+
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (1, 10 * MIB, 1)
+        );
+        let tiny = app
+            .items
+            .iter()
+            .find(|i| i.report.repo_root == Path::new("/ws/tiny"))
+            .unwrap();
+        assert!(!tiny.selected);
+    }
+
+    #[test]
+    fn select_all_leaves_new_repos_on_auto_policy() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, now);
+
+        app.select_all(&options, true);
+        push_artifact(&mut app, &options, "/ws/fresh", 20 * MIB, now);
+
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (1, 10 * MIB, 1)
+        );
+    }
+
+    #[test]
+    fn invert_flips_visible_repos_and_partial_selections() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/b", 20 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/tiny", 1024, now);
+        app.items[0].report.artifacts.push(ArtifactRecord {
+            repo_root: PathBuf::from("/ws/a"),
+            path: PathBuf::from("/ws/a/dist"),
+            stats: DirStats {
+                size_bytes: 3 * MIB,
+                ..DirStats::default()
+            },
+            unverified: false,
+        });
+
+        app.select_all(&options, true);
+        app.items[0].excluded.insert(PathBuf::from("/ws/a/target"));
+        app.cycle_new_repo_default();
+        app.invert_selection(&options);
+
+        // Only a's formerly kept target is planned; b is now deselected.
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (1, 10 * MIB, 1)
+        );
+        assert_eq!(app.items[0].selection_mode, SelectionMode::Manual);
+        assert_eq!(app.new_repo_default_selected, None);
+        let tiny = app
+            .items
+            .iter()
+            .find(|i| i.report.repo_root == Path::new("/ws/tiny"))
+            .unwrap();
+        assert!(!tiny.selected);
+
+        app.invert_selection(&options);
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (2, 23 * MIB, 2)
+        );
+    }
+
+    #[test]
+    fn new_repo_default_toggle_applies_to_later_repos() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+
+        app.cycle_new_repo_default();
+        push_artifact(&mut app, &options, "/ws/fresh", 20 * MIB, now);
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (1, 20 * MIB, 1)
+        );
+
+        app.cycle_new_repo_default();
+        push_artifact(&mut app, &options, "/ws/old", 5 * MIB, now - 400 * DAY);
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (1, 20 * MIB, 1)
+        );
+
+        app.cycle_new_repo_default();
+        push_artifact(&mut app, &options, "/ws/older", 7 * MIB, now - 400 * DAY);
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (2, 27 * MIB, 2)
+        );
+    }
+
+    #[test]
+    fn age_filter_hides_recent_repos_and_keeps_cursor() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/fresh", 10 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/old", 10 * MIB, now - 100 * DAY);
+        app.restore_selection(&options, Some(PathBuf::from("/ws/old")));
+
+        app.cycle_age_filter(&options);
+        assert_eq!(app.age_filter, AgeFilter::Days30);
+        assert_eq!(app.visible_len(&options), 1);
+        assert_eq!(
+            app.selected_repo_root(&options),
+            Some(PathBuf::from("/ws/old"))
+        );
+
+        app.cycle_age_filter(&options);
+        app.cycle_age_filter(&options);
+        assert_eq!(app.visible_len(&options), 0);
+    }
+
+    #[test]
+    fn size_threshold_steps_by_decades_and_updates_totals() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/small", 5 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/big", 500 * MIB, now);
+        let visible_bytes = |app: &App| {
+            let visibility = app.visibility(&options);
+            app.items
+                .iter()
+                .filter(|item| is_visible(&item.report, &visibility))
+                .map(|item| item.report.total_size_bytes)
+                .sum::<u64>()
+        };
+        assert_eq!(visible_bytes(&app), 505 * MIB);
+
+        app.step_min_size(&options, true);
+        assert_eq!(app.min_size_bytes(&options), 10 * MIB);
+        assert_eq!(app.visible_len(&options), 1);
+        assert_eq!(visible_bytes(&app), 500 * MIB);
+
+        app.step_min_size(&options, true);
+        app.step_min_size(&options, true);
+        assert_eq!(app.min_size_bytes(&options), 1024 * MIB);
+        assert_eq!(app.visible_len(&options), 0);
+
+        for _ in 0..10 {
+            app.step_min_size(&options, false);
+        }
+        assert_eq!(app.min_size_bytes(&options), 0);
+        assert_eq!(app.visible_len(&options), 2);
+    }
+
+    #[test]
+    fn table_row_at_skips_header_and_honors_scroll_offset() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        for i in 0..5 {
+            push_artifact(&mut app, &options, &format!("/ws/r{i}"), 10 * MIB, now);
+        }
+        app.table_area = Some(Rect::new(0, 4, 80, 4));
+
+        assert_eq!(app.table_row_at(&options, 10, 4), None);
+        assert_eq!(app.table_row_at(&options, 10, 5), Some(0));
+        assert_eq!(app.table_row_at(&options, 10, 8), None);
+
+        *app.display_state.offset_mut() = 3;
+        assert_eq!(app.table_row_at(&options, 10, 6), Some(4));
+        assert_eq!(app.table_row_at(&options, 10, 7), None);
+    }
+
+    #[test]
+    fn grouping_by_parent_adds_header_rows_the_cursor_skips() {
+        let options = TuiOptions {
+            group_by_parent: true,
+            ..test_options()
+        };
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        for (repo, size) in [
+            ("/ws/work/a", 30),
+            ("/ws/oss/b", 20),
+            ("/ws/work/c", 10),
+            ("/ws/d", 5),
+        ] {
+            push_artifact(&mut app, &options, repo, size * MIB, now);
+        }
+        let group = |path: &str, repos, size: u64| {
+            TableRow::Group(GroupRow {
+                path: PathBuf::from(path),
+                repos,
+                size_bytes: size * MIB,
+            })
+        };
+        assert_eq!(
+            app.table_rows(&options),
+            [
+                group("/ws", 1, 5),
+                TableRow::Repo(0),
+                group("/ws/oss", 1, 20),
+                TableRow::Repo(1),
+                group("/ws/work", 2, 40),
+                TableRow::Repo(2),
+                TableRow::Repo(3),
+            ]
+        );
+
+        app.table_state.select(Some(0));
+        app.move_cursor_down(&options);
+        app.move_cursor_down(&options);
+        assert_eq!(
+            app.selected_repo_root(&options),
+            Some(PathBuf::from("/ws/work/a"))
+        );
+
+        app.table_area = Some(Rect::new(0, 4, 80, 10));
+        assert_eq!(app.table_row_at(&options, 10, 5), None);
+        assert_eq!(app.table_row_at(&options, 10, 6), Some(0));
+        assert_eq!(app.table_row_at(&options, 10, 10), Some(2));
+    }
+
+    #[test]
+    fn artifact_settings_toggle_and_add_names() {
+        let enabled = ["target", "node_modules"]
+            .into_iter()
+            .map(OsString::from)
+            .collect::<HashSet<_>>();
+        let disabled = [OsString::from("dist")].into_iter().collect();
+        let mut settings = ArtifactSettings::new(&enabled, &disabled);
+        assert_eq!(
+            settings.entries,
+            vec![
+                (OsString::from("dist"), false),
+                (OsString::from("node_modules"), true),
+                (OsString::from("target"), true),
+            ]
+        );
+
+        settings.move_cursor_by(2);
+        settings.toggle_current();
+        settings.add(" .stack-work ");
+        settings.add(".build/checkouts");
+        for bad in ["", "..", "a//b", "../x", "a/./b", "a\\b"] {
+            settings.add(bad);
+        }
+        settings.add("dist");
+        assert_eq!(settings.entries.len(), 5);
+        assert_eq!(settings.entries[settings.cursor].0, OsString::from("dist"));
+
+        let (enabled, disabled) = settings.split();
+        let mut enabled = enabled.into_iter().collect::<Vec<_>>();
+        enabled.sort();
+        assert_eq!(
+            enabled,
+            vec![".build/checkouts", ".stack-work", "dist", "node_modules"]
+        );
+        assert_eq!(disabled, [OsString::from("target")].into_iter().collect());
+    }
+
+    #[test]
+    fn artifact_settings_refuse_to_apply_an_empty_set() {
+        let mut app = App::new(SystemTime::now());
+        app.scan_options.artifacts = ArtifactMatcher::new(HashSet::from([
+            OsString::from("target"),
+            OsString::from(".build/checkouts"),
+        ]));
+        app.screen = Screen::ArtifactSettings(ArtifactSettings::new(
+            &app.scan_options.artifacts.entries(),
+            &HashSet::new(),
+        ));
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let (tx, _rx) = mpsc::channel();
+        let press = |app: &mut App, code| {
+            handle_key_artifact_settings(&scan_roots, &tx, app, KeyEvent::from(code)).unwrap();
+        };
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        let scan_id = app.scan_id;
+        press(&mut app, KeyCode::Enter);
+
+        let Screen::ArtifactSettings(settings) = &app.screen else {
+            panic!("an empty set was applied");
+        };
+        assert!(settings.note.is_some());
+        assert_eq!(app.scan_id, scan_id);
+        assert_eq!(app.scan_options.artifacts.names.len(), 1);
+        assert_eq!(app.scan_options.artifacts.trailing.len(), 1);
+    }
+
+    #[test]
+    fn artifact_type_toggle_selects_only_matching_dirs() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        for (repo, name, size) in [
+            ("/ws/a", "node_modules", 10 * MIB),
+            ("/ws/a", "target", 30 * MIB),
+            ("/ws/b", "node_modules", 20 * MIB),
+        ] {
+            let repo_root = PathBuf::from(repo);
+            let record = ArtifactRecord {
+                path: repo_root.join(name),
+                repo_root,
+                stats: DirStats {
+                    size_bytes: size,
+                    newest_mtime: Some(now),
+                    file_count: 0,
+                    unreadable: 0,
+                    other_device: 0,
+                    excluded: 0,
+                    oldest_mtime: None,
+                    newest_atime: None,
+                    largest_files: Vec::new(),
+                },
+                unverified: false,
+            };
+            app.upsert_artifact(&scan_roots, &options, record, UpsertMode::Append);
+        }
+
+        app.toggle_artifact_type(&options, OsStr::new("node_modules"));
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (2, 30 * MIB, 2)
+        );
+
+        app.toggle_artifact_type(&options, OsStr::new("node_modules"));
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (0, 0, 0)
+        );
+        assert!(app.items.iter().all(|item| !item.selected));
+    }
+
+    #[test]
+    fn page_size_follows_the_terminal_height() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        for i in 0..60 {
+            push_artifact(&mut app, &options, &format!("/ws/r{i:02}"), 10 * MIB, now);
+        }
+        app.ensure_selection_valid(&options);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+        terminal
+            .draw(|frame| render(frame, &scan_roots, &options, &mut app))
+            .unwrap();
+        let small = app.page_rows;
+
+        terminal.backend_mut().resize(100, 50);
+        terminal
+            .draw(|frame| render(frame, &scan_roots, &options, &mut app))
+            .unwrap();
+        assert_eq!(app.page_rows, small + 20);
+
+        let (tx, _rx) = mpsc::channel();
+        let key = KeyEvent::from(KeyCode::PageDown);
+        handle_key_main(&scan_roots, &options, &tx, &mut app, key).unwrap();
+        assert_eq!(app.table_state.selected(), Some(app.page_rows));
+    }
+
+    #[test]
+    fn repo_table_shows_the_stale_ratio_and_marks_its_sort() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, old);
+        app.ensure_selection_valid(&options);
+
+        let screen = render_to_string(&mut app, 100, 30);
+        assert!(screen.contains("Age*  Stale Commit"));
+        assert!(screen.contains("365d   100%"));
+
+        app.sort_mode = SortMode::StaleRatio;
+        let screen = render_to_string(&mut app, 100, 30);
+        assert!(screen.contains("Age Stale* Commit"));
+    }
+
+    #[test]
+    fn footer_gauge_shows_scan_progress_once_the_total_is_known() {
+        let mut app = App::new(SystemTime::now());
+
+        app.scan_processed = 3;
+        assert_eq!(scan_ratio(&app), None);
+        let screen = render_to_string(&mut app, 100, 30);
+        assert!(screen.contains("scan: processed 3"));
+        assert!(!screen.lines().any(|row| row.starts_with("3/")));
+
+        app.scan_total = Some(12);
+        assert_eq!(scan_ratio(&app), Some(0.25));
+        let screen = render_to_string(&mut app, 100, 30);
+        assert!(screen.lines().last().unwrap().starts_with("3/12 "));
+        assert!(screen.contains("scan: 3/12 candidates"));
+
+        app.scan_done = true;
+        assert_eq!(scan_ratio(&app), None);
+    }
+
+    #[test]
+    fn stop_scans_waits_for_workers_to_exit() {
+        let mut app = App::new(SystemTime::now());
+        let cancel = Arc::clone(&app.scan_cancel);
+        app.scan_workers.push(thread::spawn(move || {
+            while !cancel.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+            thread::sleep(Duration::from_millis(20));
+        }));
+
+        app.stop_scans(Duration::from_secs(5));
+        assert!(app.scan_workers.is_empty());
+    }
+
+    #[test]
+    fn reset_to_auto_reapplies_the_policy() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, old);
+        push_artifact(&mut app, &options, "/ws/b", 20 * MIB, now);
+        app.ensure_selection_valid(&options);
+        let auto = app
+            .items
+            .iter()
+            .map(|i| (i.selected, i.selection_mode))
+            .collect::<Vec<_>>();
+        assert!(auto.iter().all(|(_, mode)| *mode == SelectionMode::Auto));
+
+        app.select_all(&options, true);
+        app.toggle_current(&options);
+        let highlighted = app.selected_repo_root(&options).unwrap();
+        app.reset_to_auto(&options, false);
+        for item in &app.items {
+            let expected_mode = if item.report.repo_root == highlighted {
+                SelectionMode::Auto
+            } else {
+                SelectionMode::Manual
+            };
+            assert_eq!(item.selection_mode, expected_mode);
+        }
+
+        app.reset_to_auto(&options, true);
+        assert_eq!(
+            app.items
+                .iter()
+                .map(|i| (i.selected, i.selection_mode))
+                .collect::<Vec<_>>(),
+            auto
+        );
+    }
+
+    #[test]
+    fn confirm_spares_targets_without_touching_the_selection() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, old);
+        push_artifact(&mut app, &options, "/ws/b", 20 * MIB, old);
+        let selected_before = app.items.iter().map(|i| i.selected).collect::<Vec<_>>();
+        let targets = plan_delete_targets(app.items.iter().map(|i| (&i.report, true)));
+
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let clean_cancel = Arc::new(AtomicBool::new(false));
+        let (tx, _rx) = mpsc::channel();
+        open_confirm(&mut app, &tx, targets, 2, false);
+        let press = |app: &mut App, code| {
+            let key = KeyEvent::from(code);
+            handle_key_confirm(&scan_roots, &options, &clean_cancel, &tx, app, key).unwrap();
+        };
+
+        // The tree lists the larger repo first, so the second dir is /ws/a's.
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        let Screen::Confirm(confirm) = &app.screen else {
+            panic!("expected the confirm screen");
+        };
+        assert!(confirm.spared.contains(Path::new("/ws/a/target")));
+        assert_eq!(
+            (
+                confirm.planned_dirs,
+                confirm.planned_bytes,
+                confirm.selected_repos
+            ),
+            (1, 20 * MIB, 1)
+        );
+        let remaining = confirm.remaining_targets();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].path, Path::new("/ws/b/target"));
+
+        press(&mut app, KeyCode::Char('n'));
+        assert!(matches!(app.screen, Screen::Main));
+        assert_eq!(
+            app.items.iter().map(|i| i.selected).collect::<Vec<_>>(),
+            selected_before
+        );
+    }
+
+    #[test]
+    fn quick_clean_targets_highlighted_repo_and_keeps_selection() {
+        let options = TuiOptions {
+            dry_run: false,
+            ..test_options()
+        };
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, old);
+        push_artifact(&mut app, &options, "/ws/b", 20 * MIB, old);
+        for item in &app.items {
+            let path = item.report.artifacts[0].path.clone();
+            app.ignore_checks.insert(path, IgnoreCheck::Ignored);
+        }
+        let selected_before = app.items.iter().map(|i| i.selected).collect::<Vec<_>>();
+        let highlighted = app.selected_repo_root(&options).unwrap();
+
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let (tx, _rx) = mpsc::channel();
+        let key = KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT);
+        handle_key_main(&scan_roots, &options, &tx, &mut app, key).unwrap();
+
+        let Screen::Confirm(confirm) = &app.screen else {
+            panic!("expected the quick-clean confirm");
+        };
+        assert!(confirm.quick);
+        assert_eq!(confirm.targets.len(), 1);
+        assert_eq!(confirm.targets[0].repo_root, highlighted);
+        let targets = confirm.targets.clone();
+        assert_eq!(
+            app.items.iter().map(|i| i.selected).collect::<Vec<_>>(),
+            selected_before
+        );
+
+        app.screen = Screen::Cleaning(CleaningData {
+            total: 1,
+            targets,
+            planned_bytes: 0,
+            processed: 1,
+            deleted_paths: 1,
+            deleted_bytes: 0,
+            skipped_paths: 0,
+            error_count: 0,
+            current: None,
+            started_at: Instant::now(),
+            cancel_requested: false,
+            quick: true,
+        });
+        let summary = DeleteSummary {
+            planned_paths: 1,
+            deleted_paths: 1,
+            ..DeleteSummary::default()
+        };
+        app.apply_clean_event(
+            &scan_roots,
+            &options,
+            &tx,
+            CleanEvent::Finished {
+                summary,
+                canceled: false,
+            },
+        );
+        assert!(matches!(app.screen, Screen::Main));
+        assert_eq!(app.items.len(), 1);
+        assert_ne!(app.items[0].report.repo_root, highlighted);
+    }
+
+    #[test]
+    fn result_report_lists_errors_with_their_context() {
+        let dir = make_temp_dir("clean-my-code-report");
+        let summary = DeleteSummary {
+            planned_paths: 2,
+            skipped_paths: 1,
+            skipped: vec![(PathBuf::from("/ws/b/dist"), SkipReason::NotIgnored)],
+            errors: vec![(
+                PathBuf::from("/ws/a/target"),
+                anyhow::anyhow!("permission denied").context("failed to run git status"),
+            )],
+            ..DeleteSummary::default()
+        };
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let mut lines = format_delete_summary(&scan_roots, &summary, false, false, None);
+        lines.push(RESULT_FOOTER.to_string());
+
+        let json = summary_json(&summary, false, false);
+
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let path = write_result_report(&dir, &lines, Some(&json), now).unwrap();
+        assert_eq!(path, dir.join("clean-code-result-1700000000.txt"));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("root: /ws\n"));
+        assert!(!contents.contains(RESULT_FOOTER));
+        assert!(contents.contains("- b/dist: not-ignored\n"));
+        assert!(contents.contains("- a/target: failed to run git status: permission denied\n"));
+        assert!(contents.ends_with(&format!("\n{json}\n")));
+        assert!(json.contains(r#""skipped":[{"path":"/ws/b/dist","reason":"not-ignored"}]"#));
+
+        // A second save in the same second keeps the first one.
+        let second = write_result_report(&dir, &lines, None, now).unwrap();
+        assert_eq!(second, dir.join("clean-code-result-1700000000-1.txt"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+
+        let missing = dir.join("missing");
+        assert!(write_result_report(&missing, &lines, None, now).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn auto_clean_plans_stale_repos_and_ignores_late_ones() {
+        let options = TuiOptions {
+            clean_all: true,
+            ..test_options()
+        };
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/stale", 10 * MIB, old);
+        push_artifact(&mut app, &options, "/ws/fresh", 10 * MIB, now);
+        app.toggle_current(&options);
+        for item in &app.items {
+            let path = item.report.artifacts[0].path.clone();
+            app.ignore_checks.insert(path, IgnoreCheck::Ignored);
+        }
+
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let clean_cancel = Arc::new(AtomicBool::new(false));
+        let (tx, _rx) = mpsc::channel();
+        start_auto_clean(&scan_roots, &options, &clean_cancel, &tx, &mut app);
+
+        let Screen::Confirm(confirm) = &app.screen else {
+            panic!("expected the Confirm screen");
+        };
+        let planned = confirm
+            .targets
+            .iter()
+            .map(|t| t.repo_root.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(planned, vec![PathBuf::from("/ws/stale")]);
+
+        push_artifact(&mut app, &options, "/ws/late", 10 * MIB, old);
+        let late = app
+            .items
+            .iter()
+            .find(|i| i.report.repo_root == Path::new("/ws/late"))
+            .unwrap();
+        assert!(!late.selected);
+        let Screen::Confirm(confirm) = &app.screen else {
+            panic!("expected the Confirm screen");
+        };
+        assert_eq!(confirm.targets.len(), 1);
+    }
+
+    #[test]
+    fn head_age_sort_puts_unloaded_heads_last_and_resorts() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/b", 10 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/c", 10 * MIB, now);
+        app.sort_mode = SortMode::HeadAge;
+
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let head = |unix_seconds| GitHead {
+            hash: "0".repeat(40),
+            unix_seconds,
+            iso8601: String::new(),
+        };
+        for (repo, unix_seconds) in [("/ws/c", 100), ("/ws/a", 200)] {
+            app.apply_scan_event(
+                &scan_roots,
+                &options,
+                ScanEvent::RepoHead {
+                    repo_root: PathBuf::from(repo),
+                    head: Some(head(unix_seconds)),
+                },
+            );
+        }
+
+        let order = app
+            .items
+            .iter()
+            .map(|i| i.report.repo_root.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["/ws/c", "/ws/a", "/ws/b"]);
+    }
+
+    #[test]
+    fn failed_head_lookups_warn_instead_of_showing_no_commits() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        // The worker sends these before the repo's first artifact.
+        let error = CandidateError {
+            path: PathBuf::from("/ws/a"),
+            kind: CandidateErrorKind::GitHead,
+            message: "git log timed out after 1s".to_string(),
+        };
+        app.apply_scan_event(&scan_roots, &options, ScanEvent::Warning { error });
+        app.apply_scan_event(
+            &scan_roots,
+            &options,
+            ScanEvent::RepoHead {
+                repo_root: PathBuf::from("/ws/a"),
+                head: None,
+            },
+        );
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/b", 10 * MIB, now);
+        app.apply_scan_event(
+            &scan_roots,
+            &options,
+            ScanEvent::RepoHead {
+                repo_root: PathBuf::from("/ws/b"),
+                head: None,
+            },
+        );
+        app.ensure_selection_valid(&options);
+
+        assert_eq!(app.counters.warnings, 1);
+        assert!(app.items.iter().all(|item| item.head_loaded));
+        assert!(app.items[0].head_failed);
+        assert!(!app.items[1].head_failed);
+        let screen = render_to_string(&mut app, 100, 30);
+        assert!(screen.contains("git failed"));
+        assert!(screen.contains("no commits"));
+        let line = progress_line(&scan_roots, &app, None).to_string();
+        assert!(line.contains("last failure: a (git head lookup failed)"));
+    }
+
+    #[test]
+    fn empty_repos_are_listed_but_not_selectable_until_artifacts_arrive() {
+        let options = TuiOptions {
+            include_empty_repos: true,
+            ..test_options()
+        };
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        app.apply_scan_event(
+            &scan_roots,
+            &options,
+            ScanEvent::RepoSeen {
+                repo_root: PathBuf::from("/ws/a"),
+            },
+        );
+        assert_eq!(app.visible_len(&options), 1);
+        assert_eq!(app.empty_repo_count(), 1);
+
+        app.select_all(&options, true);
+        app.toggle_current(&options);
+        assert!(!app.items[0].selected);
+        assert_eq!(app.visible_len(&test_options()), 0);
+
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, old);
+        assert_eq!(app.items.len(), 1);
+        assert!(app.items[0].selected);
+        assert_eq!(app.empty_repo_count(), 0);
+    }
+
+    #[test]
+    fn artifacts_within_a_repo_follow_the_sort_mode() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        for (name, size_bytes, mtime) in [("big", 20 * MIB, now), ("old", 10 * MIB, now - DAY)] {
+            let record = ArtifactRecord {
+                repo_root: PathBuf::from("/ws/a"),
+                path: PathBuf::from("/ws/a").join(name),
+                stats: DirStats {
+                    size_bytes,
+                    newest_mtime: Some(mtime),
+                    ..DirStats::default()
+                },
+                unverified: false,
+            };
+            app.upsert_artifact(&scan_roots, &options, record, UpsertMode::Append);
+        }
+        let names = |app: &App| {
+            app.items[0]
+                .report
+                .artifacts
+                .iter()
+                .map(|a| a.path.file_name().unwrap().to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(app.sort_mode, SortMode::Age);
+        assert_eq!(names(&app), ["old", "big"]);
+        app.toggle_sort_mode(&options);
+        assert_eq!(app.sort_mode, SortMode::Size);
+        assert_eq!(names(&app), ["big", "old"]);
+    }
+
+    #[test]
+    fn artifacts_found_after_a_partial_selection_stay_unplanned() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let record = |name: &str| ArtifactRecord {
+            repo_root: PathBuf::from("/ws/a"),
+            path: PathBuf::from("/ws/a").join(name),
+            stats: DirStats {
+                size_bytes: 10 * MIB,
+                newest_mtime: Some(now),
+                ..DirStats::default()
+            },
+            unverified: false,
+        };
+        for name in ["target", "node_modules"] {
+            app.upsert_artifact(&scan_roots, &options, record(name), UpsertMode::Append);
+        }
+        app.items[0].selected = true;
+        app.items[0].selection_mode = SelectionMode::Manual;
+        app.items[0]
+            .excluded
+            .insert(PathBuf::from("/ws/a/node_modules"));
+
+        app.upsert_artifact(&scan_roots, &options, record("dist"), UpsertMode::Append);
+        let planned = app.items[0]
+            .planned_artifacts()
+            .map(|a| a.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(planned, [PathBuf::from("/ws/a/target")]);
+
+        // Without a partial selection, a new artifact joins the plan.
+        app.items[0].excluded.clear();
+        app.upsert_artifact(&scan_roots, &options, record("out"), UpsertMode::Append);
+        assert_eq!(app.items[0].planned_artifacts().count(), 4);
+    }
+
+    #[test]
+    fn failed_candidates_arrive_as_warnings_with_their_path() {
+        let options = test_options();
+        let mut app = App::new(SystemTime::now());
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        app.apply_scan_event(&scan_roots, &options, ScanEvent::Warnings { count: 2 });
+        app.apply_scan_event(
+            &scan_roots,
+            &options,
+            ScanEvent::Warning {
+                error: CandidateError {
+                    path: PathBuf::from("/ws/a/target"),
+                    kind: CandidateErrorKind::Stats,
+                    message: "permission denied".to_string(),
+                },
+            },
+        );
+
+        assert_eq!(app.counters.warnings, 3);
+        assert_eq!(app.scan_failures.len(), 1);
+        let line = progress_line(&scan_roots, &app, None).to_string();
+        assert!(line.contains("warnings: 3  last failure: a/target (stats calculation failed)"));
+    }
+
+    #[test]
+    fn a_failed_scan_worker_ends_the_scan_and_shows_why() {
+        let options = test_options();
+        let mut app = App::new(SystemTime::now());
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let (tx, _rx) = mpsc::channel();
+        app.apply_event(
+            &scan_roots,
+            &options,
+            &tx,
+            AppEvent::FullScan {
+                scan_id: app.scan_id,
+                event: ScanEvent::Failed {
+                    message: "failed to build rayon thread pool".to_string(),
+                },
+            },
+        );
+
+        assert!(app.scan_done);
+        let line = progress_line(&scan_roots, &app, None).to_string();
+        assert!(line.contains("scan failed: failed to build rayon thread pool"));
+    }
+
+    #[test]
+    fn partial_clean_summary_reports_the_counters_so_far() {
+        let options = TuiOptions {
+            dry_run: false,
+            ..test_options()
+        };
+        let cleaning = CleaningData {
+            targets: Vec::new(),
+            total: 4,
+            planned_bytes: 4 * MIB,
+            processed: 2,
+            deleted_paths: 1,
+            deleted_bytes: MIB,
+            skipped_paths: 1,
+            error_count: 0,
+            current: None,
+            started_at: Instant::now(),
+            cancel_requested: true,
+            quick: false,
+        };
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+
+        let lines = partial_clean_summary(&scan_roots, &cleaning, &options);
+        assert!(lines[1].contains("totals are partial (2 of 4 dirs handled)"));
+        assert!(lines.contains(&"deleted: 1 dirs, reclaimed 1.0 MiB".to_string()));
+        assert!(lines.contains(&"skipped: 1 dirs".to_string()));
+    }
+
+    #[test]
+    fn refresh_keeps_rows_whose_git_check_failed() {
+        let root = make_temp_dir("clean-my-code-refresh");
+        // A `.git` git itself rejects, so the ignore check fails.
+        std::fs::create_dir_all(root.join("broken/.git")).unwrap();
+        std::fs::create_dir_all(root.join("broken/target")).unwrap();
+        let options = ScanOptions::new(HashSet::from([OsString::from("target")]));
+
+        let failed = refresh_event(&root.join("broken/target"), &options);
+        let missing = refresh_event(&root.join("broken/gone"), &options);
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert!(
+            matches!(failed, Some(ScanEvent::Warning { .. })),
+            "{failed:?}"
+        );
+        assert!(matches!(missing, Some(ScanEvent::ArtifactGone { .. })));
+    }
+
+    #[test]
+    fn session_totals_keep_dry_runs_separate() {
+        let mut session = SessionTotals::default();
+        assert_eq!(session.label(None), None);
+
+        let real = DeleteSummary {
+            planned_paths: 3,
+            planned_bytes: 30 * MIB,
+            deleted_paths: 2,
+            deleted_bytes: 20 * MIB,
+            skipped_paths: 1,
+            ..DeleteSummary::default()
+        };
+        session.record(&real, false);
+        session.record(&real, false);
+        // The skipped target counts in neither dirs nor bytes.
+        let dry = DeleteSummary {
+            planned_paths: 2,
+            planned_bytes: 8 * MIB,
+            kept_paths: 1,
+            kept_planned_bytes: 5 * MIB,
+            skipped_paths: 1,
+            ..DeleteSummary::default()
+        };
+        session.record(&dry, true);
+
+        assert_eq!((session.deleted_dirs, session.deleted_bytes), (4, 40 * MIB));
+        assert_eq!((session.dry_run_dirs, session.dry_run_bytes), (1, 5 * MIB));
+        assert_eq!(
+            session.label(None).unwrap(),
+            "reclaimed this session: 40.0 MiB (4 dirs)  dry runs this session: would reclaim 5.0 MiB (1 dirs)"
+        );
+    }
+
+    #[test]
+    fn terminal_is_restored_exactly_once() {
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        let mut out = Vec::new();
+        restore_terminal(&mut out);
+        assert!(!TERMINAL_ACTIVE.load(Ordering::SeqCst));
+        // Shows the cursor and leaves the alternate screen.
+        let written = String::from_utf8_lossy(&out).to_string();
+        assert!(written.contains("\x1b[?25h"), "{written:?}");
+        assert!(written.contains("\x1b[?1049l"), "{written:?}");
+
+        // A later drop of the guard must be a no-op.
+        let mut again = Vec::new();
+        restore_terminal(&mut again);
+        assert!(again.is_empty());
+    }
+
+    fn test_options() -> TuiOptions {
+        TuiOptions {
+            scan_timeout: None,
+            min_size_bytes: MIB,
+            max_size_bytes: None,
+            min_files: 0,
+            max_files: None,
+            dry_run: true,
+            idle_poll: Duration::from_millis(250),
+            report_dir: None,
+            parallel_delete: false,
+            clean_all: false,
+            yes: false,
+            require_clean_worktree: false,
+            prune_empty_parents: false,
+            auto_exit_after: None,
+            include_empty_repos: false,
+            group_by_parent: false,
+            age_basis: AgeBasis::Modified,
+            display_unit: None,
+        }
+    }
+
+    #[test]
+    fn question_mark_toggles_the_help_overlay_over_any_screen() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, now);
+        app.ensure_selection_valid(&options);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let clean_cancel = Arc::new(AtomicBool::new(false));
+        let (tx, _rx) = mpsc::channel();
+        let press = |app: &mut App, code| {
+            handle_key(
+                &scan_roots,
+                &options,
+                &clean_cancel,
+                &tx,
+                app,
+                KeyEvent::from(code),
+            )
+            .unwrap()
+        };
+
+        assert!(!press(&mut app, KeyCode::Char('?')));
+        assert!(app.show_help);
+        // Keys meant for the screen underneath are swallowed, even quit.
+        let selected = app.items[0].selected;
+        assert!(!press(&mut app, KeyCode::Char(' ')));
+        assert!(!press(&mut app, KeyCode::Char('q')));
+        assert_eq!(app.items[0].selected, selected);
+        assert!(!press(&mut app, KeyCode::Esc));
+        assert!(!app.show_help);
+
+        app.screen = Screen::ArtifactSettings(ArtifactSettings {
+            entries: Vec::new(),
+            cursor: 0,
+            input: Some(String::new()),
+            note: None,
+        });
+        press(&mut app, KeyCode::Char('?'));
+        assert!(!app.show_help);
+        let Screen::ArtifactSettings(settings) = &app.screen else {
+            panic!("left the artifact names popup");
+        };
+        assert_eq!(settings.input.as_deref(), Some("?"));
+
+        let keys = help_lines()
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        for key in ["Tab", "Space", "D", "w", "?"] {
+            assert!(keys.contains(key), "help misses {key}");
+        }
+    }
+
+    #[test]
+    fn unverified_artifacts_stay_out_of_auto_selections() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        for (repo, name, unverified) in [
+            ("/ws/a", "target", false),
+            ("/ws/a", "node_modules", true),
+            ("/ws/b", "target", true),
+        ] {
+            let record = ArtifactRecord {
+                path: PathBuf::from(repo).join(name),
+                repo_root: PathBuf::from(repo),
+                stats: DirStats {
+                    size_bytes: 10 * MIB,
+                    newest_mtime: Some(old),
+                    ..DirStats::default()
+                },
+                unverified,
+            };
+            app.upsert_artifact(&scan_roots, &options, record, UpsertMode::Append);
+        }
+
+        let item = |repo: &str| {
+            app.items
+                .iter()
+                .find(|i| i.report.repo_root == Path::new(repo))
+                .unwrap()
+        };
+        let planned = item("/ws/a")
+            .planned_artifacts()
+            .map(|a| a.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(planned, vec![PathBuf::from("/ws/a/target")]);
+        assert!(!item("/ws/b").selected);
+    }
+
+    #[test]
+    fn partly_excluded_artifacts_are_not_counted_as_reclaimable() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let mut app = App::new(now);
+        for (name, excluded) in [("target", 0), ("dist", 2)] {
+            let record = ArtifactRecord {
+                path: PathBuf::from("/ws/a").join(name),
+                repo_root: PathBuf::from("/ws/a"),
+                stats: DirStats {
+                    size_bytes: 10 * MIB,
+                    newest_mtime: Some(old),
+                    excluded,
+                    ..DirStats::default()
+                },
+                unverified: false,
+            };
+            app.upsert_artifact(&scan_roots, &options, record, UpsertMode::Append);
+        }
+        let item = &app.items[0];
+        assert!(item.selected);
+
+        let planned = item
+            .planned_artifacts()
+            .map(|a| a.path.clone())
+            .collect::<Vec<_>>();
+        let targets = plan_delete_targets(std::iter::once((&item.report, true)))
+            .into_iter()
+            .map(|t| t.path)
+            .collect::<Vec<_>>();
+        assert_eq!(planned, targets);
+        assert_eq!(planned, [PathBuf::from("/ws/a/target")]);
+        assert_eq!(
+            summarize_selection(&app.items, &app.visibility(&options)),
+            (1, 10 * MIB, 1)
+        );
+    }
+
+    #[test]
+    fn ignore_rechecks_from_an_earlier_scan_are_dropped() {
+        let options = test_options();
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let mut app = App::new(SystemTime::now());
+        app.scan_id = 2;
+        let (tx, _rx) = mpsc::channel();
+        let path = PathBuf::from("/ws/a/target");
+        let recheck = |scan_id| AppEvent::IgnoreRechecked {
+            scan_id,
+            path: path.clone(),
+            check: IgnoreCheck::NotIgnored,
+        };
+
+        app.apply_event(&scan_roots, &options, &tx, recheck(1));
+        assert!(app.ignore_checks.is_empty());
+        app.apply_event(&scan_roots, &options, &tx, recheck(2));
+        assert_eq!(app.ignore_checks.get(&path), Some(&IgnoreCheck::NotIgnored));
+    }
+
+    #[test]
+    fn resuming_a_timed_out_scan_keeps_items_and_selections() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let root = make_temp_dir("clean-my-code-resume");
+        let scan_roots = ScanRoots::from_canonical(vec![root.clone()]);
+        let mut app = App::new(now);
+        app.scan_timeout = Some(Duration::from_secs(1));
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, old);
+        push_artifact(&mut app, &options, "/ws/b", 10 * MIB, old);
+        app.ensure_selection_valid(&options);
+        app.toggle_current(&options);
+        app.apply_scan_event(&scan_roots, &options, ScanEvent::TimedOut);
+        app.apply_scan_event(&scan_roots, &options, ScanEvent::Finished);
+        let selected = |app: &App| {
+            app.items
+                .iter()
+                .map(|item| (item.report.repo_root.clone(), item.selected))
+                .collect::<Vec<_>>()
+        };
+        let before = selected(&app);
+        let scan_id = app.scan_id;
+        app.ignore_checks
+            .insert(PathBuf::from("/ws/a/target"), IgnoreCheck::Pending);
+
+        let (tx, _rx) = mpsc::channel();
+        handle_key_main(
+            &scan_roots,
+            &options,
+            &tx,
+            &mut app,
+            KeyEvent::from(KeyCode::Char('f')),
+        )
+        .unwrap();
+        app.stop_scans(Duration::from_secs(5));
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(app.scan_id, scan_id + 1);
+        assert!(app.ignore_checks.is_empty());
+        assert_eq!(app.scan_timeout, None);
+        assert!(!app.counters.timed_out);
+        assert!(!app.scan_done);
+        assert_eq!(selected(&app), before);
+    }
+
+    #[test]
+    fn sort_toggle_keeps_the_detail_cursor_on_its_artifact() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        // Least recently used first: target, dist. Largest first: dist, target.
+        for (name, size_bytes, age_days) in [("target", 10 * MIB, 400), ("dist", 20 * MIB, 300)] {
+            let record = ArtifactRecord {
+                path: PathBuf::from("/ws/a").join(name),
+                repo_root: PathBuf::from("/ws/a"),
+                stats: DirStats {
+                    size_bytes,
+                    newest_mtime: Some(now - day * age_days),
+                    ..DirStats::default()
+                },
+                unverified: false,
+            };
+            app.upsert_artifact(&scan_roots, &options, record, UpsertMode::Append);
+        }
+        app.ensure_selection_valid(&options);
+        let (tx, _rx) = mpsc::channel();
+        let press = |app: &mut App, code| {
+            handle_key_main(&scan_roots, &options, &tx, app, KeyEvent::from(code)).unwrap()
+        };
+        let under_cursor = |app: &App| {
+            app.selected_item(&options).unwrap().report.artifacts[app.detail_cursor]
+                .path
+                .clone()
+        };
+
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.focus, Focus::Detail);
+        assert_eq!(under_cursor(&app), Path::new("/ws/a/target"));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.sort_mode, SortMode::Size);
+        assert_eq!(app.detail_cursor, 1);
+        assert_eq!(under_cursor(&app), Path::new("/ws/a/target"));
+        press(&mut app, KeyCode::Up);
+        assert_eq!(under_cursor(&app), Path::new("/ws/a/dist"));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(under_cursor(&app), Path::new("/ws/a/dist"));
+    }
+
+    #[test]
+    fn detail_focus_moves_and_toggles_artifacts() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let mut app = App::new(now);
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        for name in ["target", "dist"] {
+            let record = ArtifactRecord {
+                path: PathBuf::from("/ws/a").join(name),
+                repo_root: PathBuf::from("/ws/a"),
+                stats: DirStats {
+                    size_bytes: 10 * MIB,
+                    newest_mtime: Some(old),
+                    file_count: 0,
+                    unreadable: 0,
+                    other_device: 0,
+                    excluded: 0,
+                    oldest_mtime: None,
+                    newest_atime: None,
+                    largest_files: Vec::new(),
+                },
+                unverified: false,
+            };
+            app.upsert_artifact(&scan_roots, &options, record, UpsertMode::Append);
+        }
+        push_artifact(&mut app, &options, "/ws/b", 10 * MIB, old);
+        app.ensure_selection_valid(&options);
+        let (tx, _rx) = mpsc::channel();
+        let press = |app: &mut App, code| {
+            handle_key_main(&scan_roots, &options, &tx, app, KeyEvent::from(code)).unwrap()
+        };
+
+        let row = app.table_state.selected();
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.focus, Focus::Detail);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.detail_cursor, 1);
+        assert_eq!(app.table_state.selected(), row);
+
+        let item = app.selected_item(&options).unwrap();
+        assert!(item.selected);
+        let kept = item.report.artifacts[1].path.clone();
+        press(&mut app, KeyCode::Char(' '));
+        let item = app.selected_item(&options).unwrap();
+        assert!(item.is_partially_selected());
+        assert!(item.excluded.contains(&kept));
+
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Char(' '));
+        assert!(!app.selected_item(&options).unwrap().selected);
+
+        assert!(!press(&mut app, KeyCode::Esc));
+        assert_eq!(app.focus, Focus::Table);
+    }
+
+    #[test]
+    fn path_display_cycles_and_persists() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(365 * 24 * 60 * 60);
+        let dir = make_temp_dir("clean-my-code-paths");
+        let mut app = App::new(now);
+        app.state = State::load_from(dir.join("state"));
+        push_artifact(&mut app, &options, "/ws/a/b", 10 * MIB, old);
+        assert_eq!(app.items[0].repo_display, "a/b");
+
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        app.cycle_path_display(&scan_roots);
+        app.cycle_path_display(&scan_roots);
+        assert_eq!(app.path_display, PathDisplay::Absolute);
+        assert_eq!(app.items[0].repo_display, "/ws/a/b");
+        let reloaded = State::load_from(dir.join("state"));
+        assert_eq!(PathDisplay::from_state(&reloaded), PathDisplay::Absolute);
+
+        app.cycle_path_display(&scan_roots);
+        assert_eq!(app.items[0].repo_display, "a/b");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn recently_deleted_artifacts_are_not_reinserted_by_scan_events() {
+        let options = test_options();
+        let now = SystemTime::now();
+        let mut app = App::new(now);
+        let just_now = Instant::now();
+        app.mark_deleted(vec![PathBuf::from("/ws/a/target")], just_now);
+
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, now);
+        push_artifact(&mut app, &options, "/ws/b", 10 * MIB, now);
+        let repos = app
+            .items
+            .iter()
+            .map(|item| item.report.repo_root.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(repos, [PathBuf::from("/ws/b")]);
+
+        // Once the marker expires the dir is an ordinary scan result again,
+        // e.g. after a rebuild.
+        let long_ago = just_now
+            .checked_sub(RECENTLY_DELETED_TTL + Duration::from_secs(1))
+            .unwrap();
+        app.mark_deleted(vec![PathBuf::from("/ws/a/target")], long_ago);
+        push_artifact(&mut app, &options, "/ws/a", 10 * MIB, now);
+        assert_eq!(app.items.len(), 2);
+    }
+
+    /// Draws `app` with the test options on a `width` x `height` screen
+    /// under the `/ws` root, one line per row.
+    fn render_to_string(app: &mut App, width: u16, height: u16) -> String {
+        let options = test_options();
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| render(frame, &scan_roots, &options, app))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn push_artifact(
+        app: &mut App,
+        options: &TuiOptions,
+        repo_root: &str,
+        size_bytes: u64,
+        mtime: SystemTime,
+    ) {
+        let repo_root = PathBuf::from(repo_root);
+        let record = ArtifactRecord {
+            path: repo_root.join("target"),
+            repo_root,
+            stats: DirStats {
+                size_bytes,
+                newest_mtime: Some(mtime),
+                file_count: 0,
+                unreadable: 0,
+                other_device: 0,
+                excluded: 0,
+                oldest_mtime: None,
+                newest_atime: None,
+                largest_files: Vec::new(),
+            },
+            unverified: false,
+        };
+        let scan_roots = ScanRoots::from_canonical(vec![PathBuf::from("/ws")]);
+        app.upsert_artifact(&scan_roots, options, record, UpsertMode::Append);
     }
 }